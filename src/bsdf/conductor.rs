@@ -0,0 +1,181 @@
+use super::{fresnel_schlick, reflect, same_hemisphere, Bsdf, BsdfSample, TrowbridgeReitz};
+use crate::core::{Color, Vec3};
+
+/// A metal with GGX microfacet reflection.
+///
+/// The Fresnel reflectance is Schlick's approximation from the reflectance
+/// `f0` at normal incidence. Surfaces with a tiny roughness are perfect
+/// mirrors. Reflects on both sides of the surface.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::bsdf::{Bsdf, Conductor};
+/// use raytracing::core::{Color, Vec3};
+///
+/// let gold = Conductor::new(Color::new(1.0, 0.78, 0.34), 0.1);
+/// let wo = Vec3::new(0.0, 0.6, 0.8);
+///
+/// let sample = gold.sample(&wo, 0.0, (0.3, 0.7)).unwrap();
+/// assert!(sample.wi.z() > 0.0);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Conductor {
+    pub f0: Color,
+    pub distribution: TrowbridgeReitz,
+}
+
+impl Conductor {
+    pub fn new(f0: Color, alpha: f64) -> Conductor {
+        Conductor {
+            f0,
+            distribution: TrowbridgeReitz::new(alpha),
+        }
+    }
+
+    /// Creates a conductor from its complex refractive index `eta + i k`,
+    /// per color channel.
+    pub fn from_ior(eta: Color, k: Color, alpha: f64) -> Conductor {
+        let channel =
+            |eta: f64, k: f64| ((eta - 1.0).powi(2) + k * k) / ((eta + 1.0).powi(2) + k * k);
+
+        Conductor::new(
+            Color::new(
+                channel(eta.r(), k.r()),
+                channel(eta.g(), k.g()),
+                channel(eta.b(), k.b()),
+            ),
+            alpha,
+        )
+    }
+}
+
+/// Mirrors a direction to the upper hemisphere if `flip` is set.
+fn flip_z(w: &Vec3, flip: bool) -> Vec3 {
+    if flip {
+        Vec3::new(w.x(), w.y(), -w.z())
+    } else {
+        *w
+    }
+}
+
+impl Bsdf for Conductor {
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if !same_hemisphere(wo, wi) || self.distribution.is_smooth() {
+            return Color::default();
+        }
+        let flip = wo.z() < 0.0;
+        let (wo, wi) = (flip_z(wo, flip), flip_z(wi, flip));
+        let wm = wo + wi;
+        if wm.dot(&wm) == 0.0 {
+            return Color::default();
+        }
+        let wm = wm.normalize();
+
+        let fresnel = fresnel_schlick(self.f0, wo.dot(&wm));
+        fresnel
+            * (self.distribution.d(&wm) * self.distribution.g(&wo, &wi) / (4.0 * wo.z() * wi.z()))
+    }
+
+    fn sample(&self, wo: &Vec3, _uc: f64, u: (f64, f64)) -> Option<BsdfSample> {
+        if wo.z() == 0.0 {
+            return None;
+        }
+        if self.distribution.is_smooth() {
+            let wi = Vec3::new(-wo.x(), -wo.y(), wo.z());
+            return Some(BsdfSample {
+                wi,
+                value: fresnel_schlick(self.f0, wi.z()) * (1.0 / wi.z().abs()),
+                pdf: 1.0,
+                specular: true,
+            });
+        }
+
+        let flip = wo.z() < 0.0;
+        let upper = flip_z(wo, flip);
+        let wm = self.distribution.sample_visible(&upper, u);
+        let wi = reflect(&upper, &wm);
+        if wi.z() <= 0.0 {
+            return None;
+        }
+        let wi = flip_z(&wi, flip);
+
+        Some(BsdfSample {
+            wi,
+            value: self.eval(wo, &wi),
+            pdf: self.pdf(wo, &wi),
+            specular: false,
+        })
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        if !same_hemisphere(wo, wi) || self.distribution.is_smooth() {
+            return 0.0;
+        }
+        let flip = wo.z() < 0.0;
+        let (wo, wi) = (flip_z(wo, flip), flip_z(wi, flip));
+        let wm = wo + wi;
+        if wm.dot(&wm) == 0.0 {
+            return 0.0;
+        }
+        let wm = wm.normalize();
+
+        self.distribution.visible_d(&wo, &wm) / (4.0 * wo.dot(&wm).abs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsdf::test_utils::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_sampling() {
+        for alpha in [0.05, 0.3, 0.8] {
+            let bsdf = Conductor::new(Color::new(0.9, 0.6, 0.3), alpha);
+
+            for wo in outgoing_directions() {
+                assert_sample_consistent(&bsdf, &wo);
+                // Uniform sampling cannot integrate narrow lobes.
+                if alpha > 0.1 {
+                    let integral = pdf_integral(&bsdf, &wo, 50_000);
+                    assert!(integral < 1.05, "{} at alpha {}", integral, alpha);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_white_furnace() {
+        // A perfectly reflecting metal only loses energy to masking, which
+        // grows with the roughness.
+        let wo = Vec3::new(0.3, 0.0, 1.0).normalize();
+        for (alpha, minimum) in [(0.1, 0.95), (0.5, 0.6)] {
+            let bsdf = Conductor::new(Color::new(1.0, 1.0, 1.0), alpha);
+
+            let sampled = albedo(&bsdf, &wo, 50_000).r();
+            let integrated = uniform_albedo(&bsdf, &wo, 200_000).r();
+            assert!(sampled > minimum && sampled <= 1.0 + 1e-9, "{}", sampled);
+            assert_abs_diff_eq!(sampled, integrated, epsilon = 0.03);
+        }
+    }
+
+    #[test]
+    fn test_smooth_conductor_is_mirror() {
+        let bsdf = Conductor::new(Color::new(0.5, 0.5, 0.5), 0.0);
+        let wo = Vec3::new(0.3, 0.0, 1.0).normalize();
+
+        let sample = bsdf.sample(&wo, 0.0, (0.1, 0.9)).unwrap();
+
+        assert!(sample.specular);
+        assert_abs_diff_eq!(sample.wi, Vec3::new(-wo.x(), 0.0, wo.z()));
+    }
+
+    #[test]
+    fn test_from_ior() {
+        let bsdf = Conductor::from_ior(Color::new(1.5, 1.5, 1.5), Color::default(), 0.1);
+
+        assert_abs_diff_eq!(bsdf.f0.r(), 0.04, epsilon = 1e-12);
+    }
+}
@@ -0,0 +1,123 @@
+use super::{fresnel_dielectric, refract, Bsdf, BsdfSample};
+use crate::core::{Color, Vec3};
+
+/// A smooth interface between two dielectrics such as air and glass,
+/// reflecting and refracting light according to the Fresnel equations.
+///
+/// `eta` is the refractive index on the inside, below the surface,
+/// relative to the outside. Transmitted light is tinted by
+/// `transmittance`.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::bsdf::{Bsdf, Dielectric};
+/// use raytracing::core::Vec3;
+///
+/// let glass = Dielectric::new(1.5);
+/// let wo = Vec3::new(0.0, 0.0, 1.0);
+///
+/// let reflected = glass.sample(&wo, 0.0, (0.5, 0.5)).unwrap();
+/// let refracted = glass.sample(&wo, 0.5, (0.5, 0.5)).unwrap();
+///
+/// assert_eq!(reflected.wi, Vec3::new(0.0, 0.0, 1.0));
+/// assert!((reflected.pdf - 0.04).abs() < 1e-12);
+/// assert!(refracted.wi.z() < 0.0);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Dielectric {
+    pub eta: f64,
+    pub transmittance: Color,
+}
+
+impl Dielectric {
+    /// Creates a clear dielectric.
+    pub fn new(eta: f64) -> Dielectric {
+        Dielectric {
+            eta,
+            transmittance: Color::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Bsdf for Dielectric {
+    fn eval(&self, _wo: &Vec3, _wi: &Vec3) -> Color {
+        Color::default()
+    }
+
+    fn sample(&self, wo: &Vec3, uc: f64, _u: (f64, f64)) -> Option<BsdfSample> {
+        let reflectance = fresnel_dielectric(wo.z(), self.eta);
+        let transmittance = 1.0 - reflectance;
+
+        if uc < reflectance {
+            let wi = Vec3::new(-wo.x(), -wo.y(), wo.z());
+            if wi.z() == 0.0 {
+                return None;
+            }
+            let value = reflectance / wi.z().abs();
+
+            Some(BsdfSample {
+                wi,
+                value: Color::new(value, value, value),
+                pdf: reflectance,
+                specular: true,
+            })
+        } else {
+            let (wi, eta) = refract(wo, &Vec3::new(0.0, 0.0, 1.0), self.eta)?;
+            if wi.z() == 0.0 {
+                return None;
+            }
+
+            Some(BsdfSample {
+                wi,
+                value: self.transmittance * (transmittance / (wi.z().abs() * eta * eta)),
+                pdf: transmittance,
+                specular: true,
+            })
+        }
+    }
+
+    fn pdf(&self, _wo: &Vec3, _wi: &Vec3) -> f64 {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsdf::test_utils::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_energy_is_conserved() {
+        // Without the radiance scaling, reflection and transmission add up
+        // to all of the light.
+        let glass = Dielectric::new(1.5);
+
+        for wo in outgoing_directions() {
+            let reflected = glass.sample(&wo, 0.0, (0.5, 0.5)).unwrap();
+            let refracted = glass.sample(&wo, 0.9999, (0.5, 0.5));
+
+            let eta = if wo.z() > 0.0 { 1.5 } else { 1.0 / 1.5 };
+            let transmitted = refracted.map_or(0.0, |sample| {
+                sample.value.r() * sample.wi.z().abs() * eta * eta
+            });
+            assert_abs_diff_eq!(
+                reflected.value.r() * reflected.wi.z().abs() + transmitted,
+                1.0,
+                epsilon = 1e-9
+            );
+        }
+    }
+
+    #[test]
+    fn test_total_internal_reflection() {
+        let glass = Dielectric::new(1.5);
+        let wo = Vec3::new(0.9, 0.0, -0.2).normalize();
+
+        let sample = glass.sample(&wo, 0.9999, (0.5, 0.5)).unwrap();
+
+        assert_eq!(sample.pdf, 1.0);
+        assert!(sample.wi.z() < 0.0);
+    }
+}
@@ -0,0 +1,85 @@
+use std::f64::consts::PI;
+
+use super::{same_hemisphere, Bsdf, BsdfSample};
+use crate::core::{Color, Vec3};
+use crate::render::{cosine_hemisphere_pdf, cosine_sample_hemisphere};
+
+/// Ideal diffuse reflection, scattering light equally in all directions of
+/// the hemisphere. Reflects on both sides of the surface.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::bsdf::{Bsdf, Lambertian};
+/// use raytracing::core::{Color, Vec3};
+///
+/// let bsdf = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+/// let up = Vec3::new(0.0, 0.0, 1.0);
+///
+/// assert_eq!(bsdf.eval(&up, &up), Color::new(0.5, 0.5, 0.5) * std::f64::consts::FRAC_1_PI);
+/// assert_eq!(bsdf.eval(&up, &-up), Color::default());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Lambertian {
+    pub reflectance: Color,
+}
+
+impl Lambertian {
+    pub fn new(reflectance: Color) -> Lambertian {
+        Lambertian { reflectance }
+    }
+}
+
+impl Bsdf for Lambertian {
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if same_hemisphere(wo, wi) {
+            self.reflectance * (1.0 / PI)
+        } else {
+            Color::default()
+        }
+    }
+
+    fn sample(&self, wo: &Vec3, _uc: f64, u: (f64, f64)) -> Option<BsdfSample> {
+        let mut wi = cosine_sample_hemisphere(u.0, u.1);
+        if wo.z() < 0.0 {
+            wi = Vec3::new(wi.x(), wi.y(), -wi.z());
+        }
+        let pdf = cosine_hemisphere_pdf(wi.z().abs());
+        if pdf == 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            wi,
+            value: self.reflectance * (1.0 / PI),
+            pdf,
+            specular: false,
+        })
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        if same_hemisphere(wo, wi) {
+            cosine_hemisphere_pdf(wi.z().abs())
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsdf::test_utils::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_sampling() {
+        let bsdf = Lambertian::new(Color::new(0.8, 0.5, 0.2));
+
+        for wo in outgoing_directions() {
+            assert_sample_consistent(&bsdf, &wo);
+            assert_abs_diff_eq!(pdf_integral(&bsdf, &wo, 100_000), 1.0, epsilon = 0.02);
+            assert_abs_diff_eq!(albedo(&bsdf, &wo, 1000), bsdf.reflectance, epsilon = 1e-9);
+        }
+    }
+}
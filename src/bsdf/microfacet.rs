@@ -0,0 +1,157 @@
+use std::f64::consts::PI;
+
+use crate::core::Vec3;
+
+/// Roughness below which surfaces are treated as perfectly smooth.
+const SMOOTH_ALPHA: f64 = 1e-3;
+
+/// The isotropic Trowbridge-Reitz (GGX) distribution of microfacet
+/// normals, with the height-correlated Smith masking function.
+///
+/// `alpha` is the width of the distribution, commonly the square of the
+/// perceptual roughness.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TrowbridgeReitz {
+    pub alpha: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha: f64) -> TrowbridgeReitz {
+        TrowbridgeReitz { alpha }
+    }
+
+    /// Returns whether the surface is so smooth it should be treated as a
+    /// perfect specular surface, as the distribution degenerates.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < SMOOTH_ALPHA
+    }
+
+    /// Returns the density of microfacets with normal `wm`.
+    pub fn d(&self, wm: &Vec3) -> f64 {
+        let cos2 = wm.z() * wm.z();
+        if cos2 == 0.0 {
+            return 0.0;
+        }
+        let tan2 = (1.0 - cos2) / cos2;
+        let alpha2 = self.alpha * self.alpha;
+        let e = 1.0 + tan2 / alpha2;
+
+        1.0 / (PI * alpha2 * cos2 * cos2 * e * e)
+    }
+
+    fn lambda(&self, w: &Vec3) -> f64 {
+        let cos2 = w.z() * w.z();
+        if cos2 == 0.0 {
+            return f64::INFINITY;
+        }
+        let tan2 = (1.0 - cos2) / cos2;
+
+        ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    /// Returns the fraction of microfacets visible from `w`.
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Returns the fraction of microfacets visible from both directions.
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Returns the density of microfacet normals `wm` as seen from `w`.
+    pub fn visible_d(&self, w: &Vec3, wm: &Vec3) -> f64 {
+        if w.z() == 0.0 {
+            return 0.0;
+        }
+
+        self.g1(w) / w.z().abs() * self.d(wm) * w.dot(wm).abs()
+    }
+
+    /// Samples a microfacet normal visible from `w` with density
+    /// [`TrowbridgeReitz::visible_d`] (Heitz, "Sampling the GGX
+    /// Distribution of Visible Normals").
+    pub fn sample_visible(&self, w: &Vec3, u: (f64, f64)) -> Vec3 {
+        // Stretch to the configuration where the distribution is a
+        // hemisphere.
+        let mut wh = Vec3::new(self.alpha * w.x(), self.alpha * w.y(), w.z()).normalize();
+        if wh.z() < 0.0 {
+            wh = -wh;
+        }
+
+        let t1 = if wh.z() < 0.99999 {
+            Vec3::new(0.0, 0.0, 1.0).cross(&wh).normalize()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = wh.cross(&t1);
+
+        let r = u.0.sqrt();
+        let phi = 2.0 * PI * u.1;
+        let (px, py) = (r * phi.cos(), r * phi.sin());
+        let h = (1.0 - px * px).sqrt();
+        let s = (1.0 + wh.z()) / 2.0;
+        let py = (1.0 - s) * h + s * py;
+        let pz = (1.0 - px * px - py * py).max(0.0).sqrt();
+
+        let nh = t1 * px + t2 * py + wh * pz;
+        Vec3::new(self.alpha * nh.x(), self.alpha * nh.y(), nh.z().max(1e-6)).normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn uniform_sphere(rng: &mut StdRng) -> Vec3 {
+        let z = 1.0 - 2.0 * rng.gen::<f64>();
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.gen::<f64>();
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
+    }
+
+    #[test]
+    fn test_projected_area_is_one() {
+        // ∫ D(wm) cos θm dwm = 1 over the hemisphere.
+        let mut rng = StdRng::seed_from_u64(0);
+        for alpha in [0.2, 0.5, 1.0] {
+            let distribution = TrowbridgeReitz::new(alpha);
+            let samples = 200_000;
+            let sum: f64 = (0..samples)
+                .map(|_| {
+                    let wm = uniform_sphere(&mut rng);
+                    if wm.z() > 0.0 {
+                        distribution.d(&wm) * wm.z()
+                    } else {
+                        0.0
+                    }
+                })
+                .sum();
+
+            assert_abs_diff_eq!(sum * 4.0 * PI / samples as f64, 1.0, epsilon = 0.05);
+        }
+    }
+
+    #[test]
+    fn test_visible_normals_face_viewer() {
+        let distribution = TrowbridgeReitz::new(0.5);
+        let w = Vec3::new(0.8, 0.0, 0.3).normalize();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..100 {
+            let wm = distribution.sample_visible(&w, (rng.gen(), rng.gen()));
+            assert!(wm.z() > 0.0);
+            assert!(w.dot(&wm) >= -1e-9);
+        }
+    }
+
+    #[test]
+    fn test_masking_limits() {
+        let distribution = TrowbridgeReitz::new(0.3);
+
+        assert_eq!(distribution.g1(&Vec3::new(0.0, 0.0, 1.0)), 1.0);
+        assert_eq!(distribution.g1(&Vec3::new(1.0, 0.0, 0.0)), 0.0);
+    }
+}
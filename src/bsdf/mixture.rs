@@ -0,0 +1,110 @@
+use super::{Bsdf, BsdfSample};
+use crate::core::{Color, Vec3};
+
+/// A weighted sum of lobes.
+///
+/// Lobes are sampled with probabilities proportional to their weights, and
+/// non-specular samples are evaluated against all lobes, so the mixture
+/// stays consistent between [`Bsdf::sample`], [`Bsdf::eval`] and
+/// [`Bsdf::pdf`].
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::bsdf::{Bsdf, Lambertian, Mixture};
+/// use raytracing::core::{Color, Vec3};
+///
+/// let white = Lambertian::new(Color::new(1.0, 1.0, 1.0));
+/// let black = Lambertian::new(Color::default());
+/// let gray = Mixture::default().with(0.5, white).with(0.5, black);
+/// let up = Vec3::new(0.0, 0.0, 1.0);
+///
+/// assert_eq!(gray.eval(&up, &up), Lambertian::new(Color::new(0.5, 0.5, 0.5)).eval(&up, &up));
+/// ```
+#[derive(Debug, Default)]
+pub struct Mixture {
+    lobes: Vec<(f64, Box<dyn Bsdf>)>,
+}
+
+impl Mixture {
+    /// Adds a lobe, leaving out lobes without weight.
+    pub fn with(mut self, weight: f64, lobe: impl Bsdf + 'static) -> Mixture {
+        if weight > 0.0 {
+            self.lobes.push((weight, Box::new(lobe)));
+        }
+
+        self
+    }
+
+    /// Returns the number of lobes with a weight.
+    pub fn len(&self) -> usize {
+        self.lobes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lobes.is_empty()
+    }
+
+    fn total_weight(&self) -> f64 {
+        self.lobes.iter().map(|(weight, _)| weight).sum()
+    }
+}
+
+impl Bsdf for Mixture {
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        self.lobes
+            .iter()
+            .fold(Color::default(), |sum, (weight, lobe)| {
+                sum + lobe.eval(wo, wi) * *weight
+            })
+    }
+
+    fn sample(&self, wo: &Vec3, uc: f64, u: (f64, f64)) -> Option<BsdfSample> {
+        let total = self.total_weight();
+        let mut remaining = uc * total;
+        let mut chosen = self.lobes.last()?;
+        for lobe in &self.lobes {
+            if remaining < lobe.0 {
+                chosen = lobe;
+                break;
+            }
+            remaining -= lobe.0;
+        }
+        let (weight, lobe) = chosen;
+
+        // Reuse the part of `uc` left after choosing the lobe.
+        let uc = (remaining / weight).clamp(0.0, 1.0 - f64::EPSILON);
+        let sample = lobe.sample(wo, uc, u)?;
+        if sample.specular {
+            return Some(BsdfSample {
+                value: sample.value * *weight,
+                pdf: sample.pdf * weight / total,
+                ..sample
+            });
+        }
+
+        let pdf = self.pdf(wo, &sample.wi);
+        if pdf == 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            value: self.eval(wo, &sample.wi),
+            pdf,
+            ..sample
+        })
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        let total = self.total_weight();
+        if total == 0.0 {
+            return 0.0;
+        }
+
+        self.lobes
+            .iter()
+            .map(|(weight, lobe)| weight * lobe.pdf(wo, wi))
+            .sum::<f64>()
+            / total
+    }
+}
@@ -0,0 +1,265 @@
+mod conductor;
+mod dielectric;
+mod lambertian;
+mod microfacet;
+mod mixture;
+mod principled;
+mod rough_dielectric;
+mod sheen;
+mod specular;
+
+pub use conductor::Conductor;
+pub use dielectric::Dielectric;
+pub use lambertian::Lambertian;
+pub use microfacet::TrowbridgeReitz;
+pub use mixture::Mixture;
+pub use principled::Principled;
+pub use rough_dielectric::RoughDielectric;
+pub use sheen::Sheen;
+pub use specular::SpecularReflection;
+
+use std::fmt::Debug;
+
+use crate::core::{Color, Vec3};
+
+/// A direction chosen by [`Bsdf::sample`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BsdfSample {
+    /// The incident direction, in the local shading frame.
+    pub wi: Vec3,
+    /// The value of the BSDF for the sampled pair of directions.
+    pub value: Color,
+    /// The density the direction was chosen with. For specular lobes this
+    /// is the probability of picking the lobe rather than a density.
+    pub pdf: f64,
+    /// Whether the direction was chosen from a delta distribution, which
+    /// [`Bsdf::eval`] and [`Bsdf::pdf`] cannot return.
+    pub specular: bool,
+}
+
+impl BsdfSample {
+    /// Returns the factor the sample scales incoming light by, that is the
+    /// BSDF times the cosine divided by the density.
+    pub fn weight(&self) -> Color {
+        if self.pdf == 0.0 {
+            Color::default()
+        } else {
+            self.value * (self.wi.z().abs() / self.pdf)
+        }
+    }
+}
+
+/// How a surface scatters light.
+///
+/// Directions are given in the local shading frame, where the surface
+/// normal is the z axis, and both point away from the surface: `wo`
+/// towards the viewer and `wi` towards the light. The normal is the
+/// geometric one, so for transmissive surfaces negative z components are
+/// inside the object.
+///
+/// Values are for radiance: transmission is scaled by the squared inverse
+/// of the relative refractive index.
+pub trait Bsdf: Debug + Send + Sync {
+    /// Returns the value of the BSDF, without the cosine factor.
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color;
+
+    /// Chooses an incident direction for `wo` from the random numbers `uc`
+    /// and `u` in `[0, 1)`, or returns `None` if no light is scattered.
+    fn sample(&self, wo: &Vec3, uc: f64, u: (f64, f64)) -> Option<BsdfSample>;
+
+    /// Returns the density with which [`Bsdf::sample`] chooses `wi`.
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64;
+}
+
+/// Returns whether two local directions lie on the same side of the
+/// surface.
+pub fn same_hemisphere(a: &Vec3, b: &Vec3) -> bool {
+    a.z() * b.z() > 0.0
+}
+
+/// Mirrors a direction about a unit normal, keeping it pointing away from
+/// the surface.
+pub(crate) fn reflect(wo: &Vec3, normal: &Vec3) -> Vec3 {
+    -*wo + *normal * (2.0 * wo.dot(normal))
+}
+
+/// Refracts `wi` through a surface with unit normal `normal` and the
+/// relative refractive index `eta` of the side opposite to `normal`.
+///
+/// Returns the refracted direction and the relative index along its path,
+/// or `None` on total internal reflection.
+pub(crate) fn refract(wi: &Vec3, normal: &Vec3, eta: f64) -> Option<(Vec3, f64)> {
+    let (mut normal, mut eta) = (*normal, eta);
+    let mut cos_i = normal.dot(wi);
+    if cos_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_i = -cos_i;
+        normal = -normal;
+    }
+
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+
+    Some((
+        (-*wi / eta + normal * (cos_i / eta - cos_t)).normalize(),
+        eta,
+    ))
+}
+
+/// Returns the fraction of unpolarized light reflected by a dielectric
+/// interface, for the cosine of the incident angle and the relative
+/// refractive index `eta`, from the side the normal points to.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::bsdf::fresnel_dielectric;
+///
+/// assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-12);
+/// assert_eq!(fresnel_dielectric(-0.1, 1.5), 1.0);
+/// ```
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let (mut cos_i, mut eta) = (cos_i.clamp(-1.0, 1.0), eta);
+    if cos_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_i = -cos_i;
+    }
+
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).max(0.0).sqrt();
+
+    let parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (parallel * parallel + perpendicular * perpendicular) / 2.0
+}
+
+/// Schlick's approximation of the Fresnel reflectance for the reflectance
+/// `f0` at normal incidence.
+pub fn fresnel_schlick(f0: Color, cos_i: f64) -> Color {
+    let weight = (1.0 - cos_i.abs().min(1.0)).powi(5);
+
+    f0 + (Color::new(1.0, 1.0, 1.0) - f0) * weight
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::f64::consts::PI;
+
+    pub(crate) fn outgoing_directions() -> Vec<Vec3> {
+        [
+            (0.0, 0.0, 1.0),
+            (0.5, 0.2, 0.8),
+            (-0.9, 0.1, 0.3),
+            (0.3, -0.3, -0.7),
+        ]
+        .into_iter()
+        .map(|(x, y, z)| Vec3::new(x, y, z).normalize())
+        .collect()
+    }
+
+    /// Checks that sampled values and densities agree with `eval` and
+    /// `pdf` for non-specular samples.
+    pub(crate) fn assert_sample_consistent(bsdf: &dyn Bsdf, wo: &Vec3) {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..200 {
+            let Some(sample) = bsdf.sample(wo, rng.gen(), (rng.gen(), rng.gen())) else {
+                continue;
+            };
+            assert_abs_diff_eq!(sample.wi.dot(&sample.wi), 1.0, epsilon = 1e-9);
+            if sample.specular {
+                continue;
+            }
+
+            let pdf = bsdf.pdf(wo, &sample.wi);
+            let value = bsdf.eval(wo, &sample.wi);
+            assert_abs_diff_eq!(sample.pdf, pdf, epsilon = 1e-6 * pdf.max(1.0));
+            assert_abs_diff_eq!(sample.value, value, epsilon = 1e-6 * value.r().max(1.0));
+        }
+    }
+
+    /// Estimates the fraction of light arriving along `wo` that is
+    /// scattered, by sampling.
+    pub(crate) fn albedo(bsdf: &dyn Bsdf, wo: &Vec3, samples: usize) -> Color {
+        let mut rng = StdRng::seed_from_u64(5);
+        let sum = (0..samples).fold(Color::default(), |sum, _| {
+            match bsdf.sample(wo, rng.gen(), (rng.gen(), rng.gen())) {
+                Some(sample) => sum + sample.weight(),
+                None => sum,
+            }
+        });
+
+        sum * (1.0 / samples as f64)
+    }
+
+    /// Estimates the same as [`albedo`] by integrating `eval` over the
+    /// sphere with uniformly distributed directions.
+    pub(crate) fn uniform_albedo(bsdf: &dyn Bsdf, wo: &Vec3, samples: usize) -> Color {
+        let mut rng = StdRng::seed_from_u64(9);
+        let sum = (0..samples).fold(Color::default(), |sum, _| {
+            let wi = uniform_sphere(&mut rng);
+            sum + bsdf.eval(wo, &wi) * wi.z().abs()
+        });
+
+        sum * (4.0 * PI / samples as f64)
+    }
+
+    /// Integrates the density of `bsdf` over the sphere by uniform
+    /// sampling.
+    pub(crate) fn pdf_integral(bsdf: &dyn Bsdf, wo: &Vec3, samples: usize) -> f64 {
+        let mut rng = StdRng::seed_from_u64(7);
+        let sum: f64 = (0..samples)
+            .map(|_| bsdf.pdf(wo, &uniform_sphere(&mut rng)))
+            .sum();
+
+        sum * 4.0 * PI / samples as f64
+    }
+
+    fn uniform_sphere(rng: &mut StdRng) -> Vec3 {
+        let z = 1.0 - 2.0 * rng.gen::<f64>();
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.gen::<f64>();
+
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_refract_keeps_tangent_direction() {
+        let wi = Vec3::new(1.0, 0.0, 1.0).normalize();
+        let (wt, eta) = refract(&wi, &Vec3::new(0.0, 0.0, 1.0), 1.5).unwrap();
+
+        assert_eq!(eta, 1.5);
+        assert!(wt.x() < 0.0 && wt.z() < 0.0);
+        // Snell's law: sin θi = η sin θt.
+        assert_abs_diff_eq!(wi.x(), 1.5 * -wt.x(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_refract_total_internal_reflection() {
+        let wi = Vec3::new(1.0, 0.0, -0.2).normalize();
+
+        assert_eq!(refract(&wi, &Vec3::new(0.0, 0.0, 1.0), 1.5), None);
+    }
+
+    #[test]
+    fn test_fresnel_schlick_limits() {
+        let f0 = Color::new(0.5, 0.2, 0.1);
+
+        assert_eq!(fresnel_schlick(f0, 1.0), f0);
+        assert_eq!(fresnel_schlick(f0, 0.0), Color::new(1.0, 1.0, 1.0));
+    }
+}
@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Conductor, Lambertian, Mixture, RoughDielectric, Sheen};
+use crate::core::Color;
+
+/// Clearcoat reflectance at normal incidence, that of a varnish with a
+/// refractive index of 1.5.
+const CLEARCOAT_F0: f64 = 0.04;
+
+/// Parameters of a principled material in the style of the Disney BRDF
+/// and Blender's Principled BSDF, all in `[0, 1]`.
+///
+/// The material is a mixture of physically based lobes, see
+/// [`Principled::bsdf`].
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Principled {
+    pub base_color: Color,
+    /// Blends from a dielectric to a metal tinted by the base color.
+    pub metallic: f64,
+    /// Perceptual roughness; the GGX width is its square.
+    pub roughness: f64,
+    /// Dielectric reflectance at normal incidence, scaled so 0.5 is 4%, the
+    /// reflectance of a refractive index of 1.5.
+    pub specular: f64,
+    /// Strength of a second, white specular layer.
+    pub clearcoat: f64,
+    pub clearcoat_roughness: f64,
+    /// Strength of the grazing sheen of cloth.
+    pub sheen: f64,
+    /// Blends the sheen color from white to the hue of the base color.
+    pub sheen_tint: f64,
+    /// Blends the diffuse base into refraction through the surface.
+    pub transmission: f64,
+}
+
+impl Default for Principled {
+    fn default() -> Self {
+        Principled {
+            base_color: Color::new(0.8, 0.8, 0.8),
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.03,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            transmission: 0.0,
+        }
+    }
+}
+
+impl Principled {
+    /// Returns the refractive index implied by `specular`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::bsdf::Principled;
+    ///
+    /// assert!((Principled::default().ior() - 1.5).abs() < 1e-12);
+    /// ```
+    pub fn ior(&self) -> f64 {
+        let f0 = (0.08 * self.specular).clamp(0.0, 0.999);
+
+        (1.0 + f0.sqrt()) / (1.0 - f0.sqrt())
+    }
+
+    /// Builds the lobes of the material.
+    ///
+    /// The dielectric part is a Lambertian base under a GGX specular
+    /// reflection, turning into a rough dielectric with `transmission`;
+    /// the metallic part a GGX conductor with the base color as
+    /// reflectance. Sheen and clearcoat are added on top.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::bsdf::Principled;
+    ///
+    /// let plastic = Principled::default();
+    /// let gold = Principled { metallic: 1.0, ..Default::default() };
+    ///
+    /// assert_eq!(plastic.bsdf().len(), 2);
+    /// assert_eq!(gold.bsdf().len(), 1);
+    /// ```
+    pub fn bsdf(&self) -> Mixture {
+        let alpha = self.roughness * self.roughness;
+        let dielectric = 1.0 - self.metallic;
+        let opaque = dielectric * (1.0 - self.transmission);
+        let luminance = self.base_color.luminance();
+        let tint = if luminance > 0.0 {
+            self.base_color * (1.0 / luminance)
+        } else {
+            Color::new(1.0, 1.0, 1.0)
+        };
+        let sheen_color =
+            Color::new(1.0, 1.0, 1.0) * (1.0 - self.sheen_tint) + tint * self.sheen_tint;
+        let specular = 0.08 * self.specular;
+        let clearcoat_alpha = self.clearcoat_roughness * self.clearcoat_roughness;
+
+        Mixture::default()
+            .with(opaque, Lambertian::new(self.base_color))
+            .with(
+                opaque,
+                Conductor::new(Color::new(specular, specular, specular), alpha),
+            )
+            .with(
+                dielectric * self.transmission,
+                RoughDielectric {
+                    transmittance: self.base_color,
+                    ..RoughDielectric::new(self.ior(), alpha)
+                },
+            )
+            .with(self.metallic, Conductor::new(self.base_color, alpha))
+            .with(dielectric * self.sheen, Sheen { color: sheen_color })
+            .with(
+                0.25 * self.clearcoat,
+                Conductor::new(
+                    Color::new(CLEARCOAT_F0, CLEARCOAT_F0, CLEARCOAT_F0),
+                    clearcoat_alpha,
+                ),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsdf::test_utils::*;
+    use crate::bsdf::Bsdf;
+
+    #[test]
+    fn test_lobes_are_consistent() {
+        let materials = [
+            Principled::default(),
+            Principled {
+                metallic: 0.5,
+                roughness: 0.3,
+                clearcoat: 1.0,
+                sheen: 1.0,
+                ..Default::default()
+            },
+            Principled {
+                transmission: 1.0,
+                roughness: 0.2,
+                ..Default::default()
+            },
+        ];
+
+        for material in materials {
+            let bsdf = material.bsdf();
+            for wo in outgoing_directions() {
+                assert_sample_consistent(&bsdf, &wo);
+            }
+        }
+    }
+
+    #[test]
+    fn test_metal_is_tinted_by_base_color() {
+        let copper = Principled {
+            base_color: Color::new(0.95, 0.64, 0.54),
+            metallic: 1.0,
+            roughness: 0.4,
+            ..Default::default()
+        };
+        let wo = crate::core::Vec3::new(0.0, 0.0, 1.0);
+
+        let albedo = albedo(&copper.bsdf(), &wo, 20_000);
+
+        assert!(albedo.r() > albedo.g() && albedo.g() > albedo.b());
+    }
+
+    #[test]
+    fn test_smooth_glass_is_specular() {
+        let glass = Principled {
+            transmission: 1.0,
+            roughness: 0.0,
+            ..Default::default()
+        };
+        let wo = crate::core::Vec3::new(0.0, 0.0, 1.0);
+
+        let sample = glass.bsdf().sample(&wo, 0.9, (0.5, 0.5)).unwrap();
+
+        assert!(sample.specular);
+        assert!(sample.wi.z() < 0.0);
+    }
+}
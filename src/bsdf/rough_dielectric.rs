@@ -0,0 +1,202 @@
+use super::{
+    fresnel_dielectric, reflect, refract, same_hemisphere, Bsdf, BsdfSample, Dielectric,
+    TrowbridgeReitz,
+};
+use crate::core::{Color, Vec3};
+
+/// A rough dielectric interface such as frosted glass, reflecting and
+/// refracting light through GGX distributed microfacets (Walter et al.,
+/// "Microfacet Models for Refraction through Rough Surfaces").
+///
+/// `eta` and `transmittance` are as for [`Dielectric`], which is used for
+/// surfaces with a tiny roughness.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RoughDielectric {
+    pub eta: f64,
+    pub transmittance: Color,
+    pub distribution: TrowbridgeReitz,
+}
+
+impl RoughDielectric {
+    /// Creates a clear rough dielectric.
+    pub fn new(eta: f64, alpha: f64) -> RoughDielectric {
+        RoughDielectric {
+            eta,
+            transmittance: Color::new(1.0, 1.0, 1.0),
+            distribution: TrowbridgeReitz::new(alpha),
+        }
+    }
+
+    fn smooth(&self) -> Dielectric {
+        Dielectric {
+            eta: self.eta,
+            transmittance: self.transmittance,
+        }
+    }
+
+    /// Returns the generalized half vector of a pair of directions, facing
+    /// the outside, and the relative refractive index along the path.
+    fn half_vector(&self, wo: &Vec3, wi: &Vec3) -> Option<(Vec3, f64)> {
+        let (cos_o, cos_i) = (wo.z(), wi.z());
+        if cos_o == 0.0 || cos_i == 0.0 {
+            return None;
+        }
+
+        let eta = match (same_hemisphere(wo, wi), cos_o > 0.0) {
+            (true, _) => 1.0,
+            (false, true) => self.eta,
+            (false, false) => 1.0 / self.eta,
+        };
+        let wm = *wi * eta + *wo;
+        if wm.dot(&wm) == 0.0 {
+            return None;
+        }
+        let mut wm = wm.normalize();
+        if wm.z() < 0.0 {
+            wm = -wm;
+        }
+
+        // Microfacets seen from behind do not contribute.
+        if wm.dot(wi) * cos_i < 0.0 || wm.dot(wo) * cos_o < 0.0 {
+            return None;
+        }
+
+        Some((wm, eta))
+    }
+}
+
+impl Bsdf for RoughDielectric {
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if self.distribution.is_smooth() {
+            return Color::default();
+        }
+        let Some((wm, eta)) = self.half_vector(wo, wi) else {
+            return Color::default();
+        };
+
+        let (cos_o, cos_i) = (wo.z(), wi.z());
+        let reflectance = fresnel_dielectric(wo.dot(&wm), self.eta);
+        let d = self.distribution.d(&wm);
+        let g = self.distribution.g(wo, wi);
+
+        if same_hemisphere(wo, wi) {
+            let value = d * g * reflectance / (4.0 * cos_i * cos_o).abs();
+            Color::new(value, value, value)
+        } else {
+            let denominator = (wi.dot(&wm) + wo.dot(&wm) / eta).powi(2) * cos_i * cos_o;
+            let value =
+                d * g * (1.0 - reflectance) * (wi.dot(&wm) * wo.dot(&wm) / denominator).abs()
+                    / (eta * eta);
+            self.transmittance * value
+        }
+    }
+
+    fn sample(&self, wo: &Vec3, uc: f64, u: (f64, f64)) -> Option<BsdfSample> {
+        if self.distribution.is_smooth() {
+            return self.smooth().sample(wo, uc, u);
+        }
+
+        let wm = self.distribution.sample_visible(wo, u);
+        let reflectance = fresnel_dielectric(wo.dot(&wm), self.eta);
+        let wi = if uc < reflectance {
+            let wi = reflect(wo, &wm);
+            if !same_hemisphere(wo, &wi) {
+                return None;
+            }
+            wi
+        } else {
+            let (wi, _) = refract(wo, &wm, self.eta)?;
+            if same_hemisphere(wo, &wi) || wi.z() == 0.0 {
+                return None;
+            }
+            wi
+        };
+
+        let pdf = self.pdf(wo, &wi);
+        if pdf == 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            wi,
+            value: self.eval(wo, &wi),
+            pdf,
+            specular: false,
+        })
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+        let Some((wm, eta)) = self.half_vector(wo, wi) else {
+            return 0.0;
+        };
+
+        let reflectance = fresnel_dielectric(wo.dot(&wm), self.eta);
+        let visible = self.distribution.visible_d(wo, &wm);
+
+        if same_hemisphere(wo, wi) {
+            visible / (4.0 * wo.dot(&wm).abs()) * reflectance
+        } else {
+            let denominator = (wi.dot(&wm) + wo.dot(&wm) / eta).powi(2);
+            visible * wi.dot(&wm).abs() / denominator * (1.0 - reflectance)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsdf::test_utils::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_sampling() {
+        for alpha in [0.1, 0.4] {
+            let bsdf = RoughDielectric::new(1.5, alpha);
+
+            for wo in outgoing_directions() {
+                assert_sample_consistent(&bsdf, &wo);
+                let integral = pdf_integral(&bsdf, &wo, 50_000);
+                assert!(integral < 1.05, "{} at alpha {}", integral, alpha);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reflects_and_transmits() {
+        let bsdf = RoughDielectric::new(1.5, 0.3);
+        let wo = Vec3::new(0.0, 0.0, 1.0);
+
+        let reflected = bsdf.eval(&wo, &wo);
+        let transmitted = bsdf.eval(&wo, &-wo);
+
+        assert!(reflected.r() > 0.0);
+        assert!(transmitted.r() > reflected.r());
+    }
+
+    #[test]
+    fn test_energy_is_bounded() {
+        let bsdf = RoughDielectric::new(1.5, 0.3);
+        let wo = Vec3::new(0.0, 0.0, 1.0);
+
+        // Entering light is scaled by 1/η² for radiance.
+        let sampled = albedo(&bsdf, &wo, 50_000).r();
+        let integrated = uniform_albedo(&bsdf, &wo, 200_000).r();
+        assert!(sampled < 1.0);
+        assert_abs_diff_eq!(sampled, 0.04 + 0.96 / 2.25, epsilon = 0.05);
+        assert_abs_diff_eq!(sampled, integrated, epsilon = 0.03);
+    }
+
+    #[test]
+    fn test_smooth_falls_back_to_dielectric() {
+        let bsdf = RoughDielectric::new(1.5, 0.0);
+        let wo = Vec3::new(0.0, 0.0, 1.0);
+
+        assert_eq!(
+            bsdf.sample(&wo, 0.5, (0.5, 0.5)),
+            Dielectric::new(1.5).sample(&wo, 0.5, (0.5, 0.5))
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::f64::consts::PI;
+
+use super::{same_hemisphere, Bsdf, BsdfSample};
+use crate::core::{Color, Vec3};
+use crate::render::{cosine_hemisphere_pdf, cosine_sample_hemisphere};
+
+/// The retro-reflective sheen of cloth at grazing angles, after the Disney
+/// principled BRDF. Reflects on both sides of the surface.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sheen {
+    pub color: Color,
+}
+
+impl Bsdf for Sheen {
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if !same_hemisphere(wo, wi) {
+            return Color::default();
+        }
+        let cos_d = (*wo + *wi).normalize().dot(wi).abs();
+
+        self.color * ((1.0 - cos_d).powi(5) / PI)
+    }
+
+    fn sample(&self, wo: &Vec3, _uc: f64, u: (f64, f64)) -> Option<BsdfSample> {
+        let mut wi = cosine_sample_hemisphere(u.0, u.1);
+        if wo.z() < 0.0 {
+            wi = Vec3::new(wi.x(), wi.y(), -wi.z());
+        }
+        let pdf = self.pdf(wo, &wi);
+        if pdf == 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            wi,
+            value: self.eval(wo, &wi),
+            pdf,
+            specular: false,
+        })
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        if same_hemisphere(wo, wi) {
+            cosine_hemisphere_pdf(wi.z().abs())
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsdf::test_utils::*;
+
+    #[test]
+    fn test_sheen_grows_at_grazing_angles() {
+        let sheen = Sheen {
+            color: Color::new(1.0, 1.0, 1.0),
+        };
+        let up = Vec3::new(0.0, 0.0, 1.0);
+        let grazing = Vec3::new(1.0, 0.0, 0.05).normalize();
+
+        assert_eq!(sheen.eval(&up, &up), Color::default());
+        assert!(sheen.eval(&grazing, &up).r() > 0.0);
+        for wo in outgoing_directions() {
+            assert_sample_consistent(&sheen, &wo);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use super::{Bsdf, BsdfSample};
+use crate::core::{Color, Vec3};
+
+/// A perfect mirror, reflecting the given fraction of light at every angle.
+/// Reflects on both sides of the surface.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpecularReflection {
+    pub reflectance: Color,
+}
+
+impl SpecularReflection {
+    pub fn new(reflectance: Color) -> SpecularReflection {
+        SpecularReflection { reflectance }
+    }
+}
+
+impl Bsdf for SpecularReflection {
+    fn eval(&self, _wo: &Vec3, _wi: &Vec3) -> Color {
+        Color::default()
+    }
+
+    fn sample(&self, wo: &Vec3, _uc: f64, _u: (f64, f64)) -> Option<BsdfSample> {
+        let wi = Vec3::new(-wo.x(), -wo.y(), wo.z());
+        if wi.z() == 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            wi,
+            value: self.reflectance * (1.0 / wi.z().abs()),
+            pdf: 1.0,
+            specular: true,
+        })
+    }
+
+    fn pdf(&self, _wo: &Vec3, _wi: &Vec3) -> f64 {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsdf::test_utils::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_mirror_direction_and_weight() {
+        let bsdf = SpecularReflection::new(Color::new(0.9, 0.8, 0.7));
+
+        for wo in outgoing_directions() {
+            let sample = bsdf.sample(&wo, 0.5, (0.5, 0.5)).unwrap();
+
+            assert!(sample.specular);
+            assert_abs_diff_eq!(sample.wi, Vec3::new(-wo.x(), -wo.y(), wo.z()));
+            assert_abs_diff_eq!(sample.weight(), bsdf.reflectance, epsilon = 1e-12);
+        }
+    }
+}
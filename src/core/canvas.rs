@@ -46,6 +46,33 @@ impl Canvas {
         &mut self.pixels[y * self.width + x]
     }
 
+    /// Returns a copy of the canvas scaled to the given size, using the
+    /// nearest pixel for every target pixel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::core::{Canvas, Color};
+    ///
+    /// let mut canvas = Canvas::new(2, 1);
+    /// canvas[(1, 0)] = Color::new(1.0, 0.0, 0.0);
+    ///
+    /// let resized = canvas.resize_nearest(4, 2);
+    ///
+    /// assert_eq!(resized[(3, 1)], Color::new(1.0, 0.0, 0.0));
+    /// assert_eq!(resized[(1, 1)], Color::default());
+    /// ```
+    pub fn resize_nearest(&self, width: usize, height: usize) -> Canvas {
+        let mut resized = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                resized[(x, y)] = self[(x * self.width / width, y * self.height / height)];
+            }
+        }
+
+        resized
+    }
+
     /// Returns an iterator over the pixels of the canvas.
     pub fn iter_pixels(&self) -> impl Iterator<Item = &Color> {
         self.pixels.iter()
@@ -1,6 +1,7 @@
 use std::ops::{Add, Mul, Sub};
 
 use approx::AbsDiffEq;
+use serde::{Deserialize, Serialize};
 
 /// A color with RGB values.
 ///
@@ -8,7 +9,7 @@ use approx::AbsDiffEq;
 ///
 /// Values out of the range `[0, 1]` are valid but should be clamped when
 /// converting to a pixel value.
-#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Color(f64, f64, f64);
 
 impl Color {
@@ -55,6 +56,19 @@ impl Color {
             self.b().clamp(0.0, 1.0),
         )
     }
+
+    /// Returns the relative luminance of the color (Rec. 709 weights).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::core::Color;
+    ///
+    /// assert_eq!(Color::new(1.0, 1.0, 1.0).luminance(), 1.0);
+    /// ```
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+    }
 }
 
 impl From<(f64, f64, f64)> for Color {
@@ -63,9 +77,9 @@ impl From<(f64, f64, f64)> for Color {
     }
 }
 
-impl Into<(f64, f64, f64)> for Color {
-    fn into(self) -> (f64, f64, f64) {
-        (self.r(), self.g(), self.b())
+impl From<Color> for (f64, f64, f64) {
+    fn from(color: Color) -> Self {
+        (color.r(), color.g(), color.b())
     }
 }
 
@@ -97,7 +111,7 @@ impl Mul<Color> for f64 {
     type Output = Color;
 
     fn mul(self, rhs: Color) -> Color {
-        return rhs * self;
+        rhs * self
     }
 }
 
@@ -105,7 +119,7 @@ impl Mul<Color> for Color {
     type Output = Color;
 
     fn mul(self, rhs: Color) -> Color {
-        return Color::new(self.r() * rhs.r(), self.g() * rhs.g(), self.b() * rhs.b());
+        Color::new(self.r() * rhs.r(), self.g() * rhs.g(), self.b() * rhs.b())
     }
 }
 
@@ -0,0 +1,85 @@
+use super::Vec3;
+
+/// An orthonormal basis around a surface normal.
+///
+/// Shading code works in the local frame, where the normal is the z axis,
+/// so cosines with the normal are simply the z component.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::{Frame, Vec3};
+///
+/// let frame = Frame::from_normal(Vec3::new(0.0, 1.0, 0.0));
+///
+/// assert_eq!(frame.to_local(&Vec3::new(0.0, 1.0, 0.0)).z(), 1.0);
+/// assert_eq!(frame.to_world(&Vec3::new(0.0, 0.0, 1.0)), Vec3::new(0.0, 1.0, 0.0));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Frame {
+    pub s: Vec3,
+    pub t: Vec3,
+    pub n: Vec3,
+}
+
+impl Frame {
+    /// Builds a frame around `normal`, which does not need to be
+    /// normalized.
+    pub fn from_normal(normal: Vec3) -> Frame {
+        // Duff et al., "Building an Orthonormal Basis, Revisited".
+        let n = normal.normalize();
+        let sign = 1.0_f64.copysign(n.z());
+        let a = -1.0 / (sign + n.z());
+        let b = n.x() * n.y() * a;
+
+        Frame {
+            s: Vec3::new(1.0 + sign * n.x() * n.x() * a, sign * b, -sign * n.x()),
+            t: Vec3::new(b, sign + n.y() * n.y() * a, -n.y()),
+            n,
+        }
+    }
+
+    /// Converts a world space direction to the local frame.
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(v.dot(&self.s), v.dot(&self.t), v.dot(&self.n))
+    }
+
+    /// Converts a direction in the local frame to world space.
+    pub fn to_world(&self, v: &Vec3) -> Vec3 {
+        self.s * v.x() + self.t * v.y() + self.n * v.z()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_frame_is_orthonormal() {
+        for normal in [
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(-0.3, 0.1, -2.0),
+        ] {
+            let frame = Frame::from_normal(normal);
+
+            for (a, b) in [(frame.s, frame.t), (frame.t, frame.n), (frame.n, frame.s)] {
+                assert_abs_diff_eq!(a.dot(&b), 0.0, epsilon = 1e-12);
+            }
+            for v in [frame.s, frame.t, frame.n] {
+                assert_abs_diff_eq!(v.dot(&v), 1.0, epsilon = 1e-12);
+            }
+            assert_abs_diff_eq!(frame.s.cross(&frame.t), frame.n, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let frame = Frame::from_normal(Vec3::new(1.0, -2.0, 0.5));
+        let v = Vec3::new(0.3, 0.4, -0.7);
+
+        assert_abs_diff_eq!(frame.to_world(&frame.to_local(&v)), v, epsilon = 1e-12);
+    }
+}
@@ -1,7 +1,8 @@
 use approx::AbsDiff;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     convert::TryInto,
-    iter::{once, repeat},
+    iter::{once, repeat_n},
     ops::{Index, IndexMut, Mul},
 };
 
@@ -59,10 +60,9 @@ impl<const R: usize, const C: usize> Matrix<R, C> {
     pub fn identity<const T: usize>() -> Matrix<T, T> {
         let rows = (0..T)
             .map(|r| {
-                repeat(0.0)
-                    .take(r)
+                repeat_n(0.0, r)
                     .chain(once(1.0))
-                    .chain(repeat(0.0).take(T - 1 - r))
+                    .chain(repeat_n(0.0, T - 1 - r))
                     .collect::<Vec<_>>()
                     .try_into()
                     .unwrap()
@@ -93,7 +93,7 @@ impl<const R: usize, const C: usize> Matrix<R, C> {
     }
 
     pub fn rows(&self) -> impl Iterator<Item = [f64; C]> + '_ {
-        self.rows.iter().map(|row| *row)
+        self.rows.iter().copied()
     }
 
     pub fn cols(&self) -> impl Iterator<Item = [f64; R]> + '_ {
@@ -108,13 +108,56 @@ impl<const R: usize, const C: usize> Matrix<R, C> {
 
     pub fn elements(&self) -> impl Iterator<Item = f64> + '_ {
         self.rows()
-            .flat_map(|row| row.clone().iter().map(|el| *el).collect::<Vec<_>>())
+            .flat_map(|row| row.iter().copied().collect::<Vec<_>>())
     }
 }
 
 impl<const T: usize> Matrix<T, T> {
+    /// Returns the determinant, computed by Gaussian elimination with
+    /// partial pivoting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::core::Matrix;
+    ///
+    /// let m: Matrix<2, 2> = Matrix::new([[1.0, 2.0], [4.0, 3.0]]);
+    ///
+    /// assert_eq!(m.determinant(), -5.0);
+    /// assert_eq!(Matrix::<2, 2>::default().determinant(), 0.0);
+    /// ```
+    #[allow(clippy::needless_range_loop)]
+    pub fn determinant(&self) -> f64 {
+        let mut rows = self.rows;
+        let mut determinant = 1.0;
+
+        for p in 0..T {
+            let pivot = (p..T)
+                .max_by(|&i, &j| rows[i][p].abs().total_cmp(&rows[j][p].abs()))
+                .unwrap();
+            if rows[pivot][p] == 0.0 {
+                return 0.0;
+            }
+            if pivot != p {
+                rows.swap(pivot, p);
+                determinant = -determinant;
+            }
+            determinant *= rows[p][p];
+
+            for i in p + 1..T {
+                let factor = rows[i][p] / rows[p][p];
+                for j in p..T {
+                    rows[i][j] -= factor * rows[p][j];
+                }
+            }
+        }
+
+        determinant
+    }
+
+    #[allow(clippy::needless_range_loop)]
     pub fn inverse(&self) -> Self {
-        let mut inv_rows = self.rows.clone();
+        let mut inv_rows = self.rows;
 
         for p in 0..T {
             let pivot = inv_rows[p][p];
@@ -135,7 +178,7 @@ impl<const T: usize> Matrix<T, T> {
 
             for j in 0..T {
                 if j != p {
-                    inv_rows[p][j] = inv_rows[p][j] / pivot;
+                    inv_rows[p][j] /= pivot;
                 }
             }
 
@@ -214,6 +257,34 @@ impl<const R: usize, const C: usize> approx::AbsDiffEq for Matrix<R, C> {
     }
 }
 
+/// Serializes a matrix as a list of rows.
+impl<const R: usize, const C: usize> Serialize for Matrix<R, C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.rows.iter().map(|row| row.as_slice()))
+    }
+}
+
+impl<'de, const R: usize, const C: usize> Deserialize<'de> for Matrix<R, C> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = Vec::<Vec<f64>>::deserialize(deserializer)?;
+        if rows.len() != R {
+            return Err(de::Error::invalid_length(
+                rows.len(),
+                &format!("{} rows", R).as_str(),
+            ));
+        }
+
+        let mut matrix = Matrix::default();
+        for (r, row) in rows.into_iter().enumerate() {
+            matrix.rows[r] = row.try_into().map_err(|row: Vec<f64>| {
+                de::Error::invalid_length(row.len(), &format!("{} columns", C).as_str())
+            })?;
+        }
+
+        Ok(matrix)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::core::test_utils::arbitrary_matrix3;
@@ -297,6 +368,26 @@ mod tests {
         assert_abs_diff_eq!(m.inverse(), expected);
     }
 
+    #[test]
+    fn test_matrix_determinant() {
+        let m = Matrix::new([
+            [-2.0, -8.0, 3.0, 5.0],
+            [-3.0, 1.0, 7.0, 3.0],
+            [1.0, 2.0, -9.0, 6.0],
+            [-6.0, 7.0, 7.0, -9.0],
+        ]);
+
+        assert_abs_diff_eq!(m.determinant(), -4071.0, epsilon = 1e-9);
+        assert_eq!(Matrix::<4, 4>::identity::<4>().determinant(), 1.0);
+
+        // A zero on the diagonal needs a row swap.
+        let swapped = Matrix::new([[0.0, 1.0], [1.0, 0.0]]);
+        assert_eq!(swapped.determinant(), -1.0);
+
+        let singular = Matrix::new([[1.0, 2.0], [2.0, 4.0]]);
+        assert_eq!(singular.determinant(), 0.0);
+    }
+
     #[test]
     fn test_matrix_index() {
         let m = Matrix::new([[1.0, 2.0], [4.0, 3.0]]);
@@ -322,6 +413,17 @@ mod tests {
         assert_eq!(m[(1, 1)], 6.0);
     }
 
+    #[test]
+    fn test_matrix_serde() {
+        let m = Matrix::new([[1.0, 2.0], [4.0, 3.0]]);
+        let json = serde_json::to_string(&m).unwrap();
+
+        assert_eq!(json, "[[1.0,2.0],[4.0,3.0]]");
+        assert_eq!(serde_json::from_str::<Matrix<2, 2>>(&json).unwrap(), m);
+        assert!(serde_json::from_str::<Matrix<2, 2>>("[[1.0,2.0],[4.0]]").is_err());
+        assert!(serde_json::from_str::<Matrix<2, 2>>("[[1.0,2.0]]").is_err());
+    }
+
     proptest! {
         #[test]
         fn test_matrix_mul_identity(m in arbitrary_matrix3()) {
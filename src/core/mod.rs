@@ -1,15 +1,19 @@
 mod canvas;
 mod color;
+mod frame;
 mod matrix;
 mod point;
+mod ray;
 #[cfg(test)]
-mod test_utils;
+pub(crate) mod test_utils;
 mod vec3;
 
 pub mod transformations;
 
 pub use canvas::Canvas;
 pub use color::Color;
+pub use frame::Frame;
 pub use matrix::Matrix;
 pub use point::Point;
+pub use ray::Ray;
 pub use vec3::Vec3;
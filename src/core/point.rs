@@ -1,6 +1,7 @@
 use std::ops::{Add, Sub};
 
 use approx::AbsDiffEq;
+use serde::{Deserialize, Serialize};
 
 use super::{Matrix, Vec3};
 
@@ -17,7 +18,7 @@ use super::{Matrix, Vec3};
 ///
 /// assert_eq!(p + v, Point::new(2.0, 4.0, 6.0));
 /// ```
-#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Point(f64, f64, f64);
 
 impl Point {
@@ -110,9 +111,9 @@ impl Sub<Vec3> for Point {
     }
 }
 
-impl Into<Matrix<4, 1>> for Point {
-    fn into(self) -> Matrix<4, 1> {
-        Matrix::new([[self.x()], [self.y()], [self.z()], [1.0]])
+impl From<Point> for Matrix<4, 1> {
+    fn from(p: Point) -> Self {
+        Matrix::new([[p.x()], [p.y()], [p.z()], [1.0]])
     }
 }
 
@@ -0,0 +1,65 @@
+use super::{transformations::Transform, Matrix, Point, Vec3};
+
+/// A ray starting at `origin` and travelling along `direction`.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::{Point, Ray, Vec3};
+///
+/// let ray = Ray::new(Point::new(2.0, 3.0, 4.0), Vec3::new(1.0, 0.0, 0.0));
+///
+/// assert_eq!(ray.position(2.5), Point::new(4.5, 3.0, 4.0));
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Ray {
+    pub origin: Point,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// Creates a new ray.
+    pub fn new(origin: Point, direction: Vec3) -> Ray {
+        Ray { origin, direction }
+    }
+
+    /// Returns the point at distance `t` along the ray.
+    pub fn position(&self, t: f64) -> Point {
+        self.origin + self.direction * t
+    }
+}
+
+impl Transform for Ray {
+    fn transform(self, transformation_matrix: &Matrix<4, 4>) -> Self {
+        Ray::new(
+            self.origin.transform(transformation_matrix),
+            self.direction.transform(transformation_matrix),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::transformations::{scale, translate};
+
+    #[test]
+    fn test_ray_translate() {
+        let ray = Ray::new(Point::new(1.0, 2.0, 3.0), Vec3::new(0.0, 1.0, 0.0));
+
+        assert_eq!(
+            ray.transform(&translate(3.0, 4.0, 5.0)),
+            Ray::new(Point::new(4.0, 6.0, 8.0), Vec3::new(0.0, 1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn test_ray_scale() {
+        let ray = Ray::new(Point::new(1.0, 2.0, 3.0), Vec3::new(0.0, 1.0, 0.0));
+
+        assert_eq!(
+            ray.transform(&scale(2.0, 3.0, 4.0)),
+            Ray::new(Point::new(2.0, 6.0, 12.0), Vec3::new(0.0, 3.0, 0.0))
+        );
+    }
+}
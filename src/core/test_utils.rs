@@ -15,5 +15,5 @@ pub fn arbitrary_point() -> impl Strategy<Value = Point> {
 
 pub fn arbitrary_matrix3() -> impl Strategy<Value = Matrix<3, 3>> {
     const RANGE: Range<f64> = -1e3f64..1e3f64;
-    [[RANGE; 3], [RANGE; 3], [RANGE; 3]].prop_map(|rows| Matrix::new(rows))
+    [[RANGE; 3], [RANGE; 3], [RANGE; 3]].prop_map(Matrix::new)
 }
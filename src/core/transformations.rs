@@ -1,6 +1,10 @@
-use super::Matrix;
+use super::{Matrix, Point, Vec3};
 
-trait Transform {
+/// Applies a 4x4 transformation matrix to a value.
+///
+/// Implemented for every type that converts to and from a 4x1 column matrix,
+/// i.e. [`Point`](super::Point) and [`Vec3`](super::Vec3).
+pub trait Transform {
     fn transform(self, transformation_matrix: &Matrix<4, 4>) -> Self;
 }
 
@@ -127,10 +131,63 @@ pub fn rotate(radians_x: f64, radians_y: f64, radians_z: f64) -> Matrix<4, 4> {
     rotate_x(radians_x) * rotate_y(radians_y) * rotate_z(radians_z)
 }
 
+/// Creates a 4x4 shearing matrix, moving each component in proportion to the other two.
+///
+/// # Arguments
+///
+/// * `xy` - The amount x moves in proportion to y.
+/// * `xz` - The amount x moves in proportion to z.
+/// * `yx` - The amount y moves in proportion to x.
+/// * `yz` - The amount y moves in proportion to z.
+/// * `zx` - The amount z moves in proportion to x.
+/// * `zy` - The amount z moves in proportion to y.
+///
+/// # Returns
+///
+/// A 4x4 shearing matrix
+pub fn shear(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Matrix<4, 4> {
+    let mut m = Matrix::<4, 4>::identity();
+    m[(0, 1)] = xy;
+    m[(0, 2)] = xz;
+    m[(1, 0)] = yx;
+    m[(1, 2)] = yz;
+    m[(2, 0)] = zx;
+    m[(2, 1)] = zy;
+
+    m
+}
+
+/// Creates the world to camera transformation of an eye at `from` looking
+/// at `to`, with `up` pointing roughly upwards.
+///
+/// # Arguments
+///
+/// * `from` - The position of the eye.
+/// * `to` - The point the eye looks at, along the negative z-axis.
+/// * `up` - The approximate up direction, which does not need to be
+///   perpendicular to the view direction.
+///
+/// # Returns
+///
+/// A 4x4 view matrix
+pub fn view_transform(from: Point, to: Point, up: Vec3) -> Matrix<4, 4> {
+    let forward = (to - from).normalize();
+    let left = forward.cross(&up.normalize());
+    let true_up = left.cross(&forward);
+    let orientation = Matrix::new([
+        [left.x(), left.y(), left.z(), 0.0],
+        [true_up.x(), true_up.y(), true_up.z(), 0.0],
+        [-forward.x(), -forward.y(), -forward.z(), 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    orientation * translate(-from.x(), -from.y(), -from.z())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{test_utils::arbitrary_vec3, Point, Vec3};
+    use crate::core::test_utils::arbitrary_vec3;
     use approx::abs_diff_eq;
     use proptest::prelude::*;
 
@@ -188,6 +245,62 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_shear_point() {
+        let point = Point::new(2.0, 3.0, 4.0);
+
+        assert_eq!(
+            point.transform(&shear(1.0, 0.0, 0.0, 0.0, 0.0, 0.0)),
+            Point::new(5.0, 3.0, 4.0)
+        );
+        assert_eq!(
+            point.transform(&shear(0.0, 0.0, 0.0, 0.0, 0.0, 1.0)),
+            Point::new(2.0, 3.0, 7.0)
+        );
+    }
+
+    #[test]
+    fn test_view_transform_default_orientation() {
+        let transformation = view_transform(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+
+        assert_eq!(transformation, Matrix::<4, 4>::identity());
+    }
+
+    #[test]
+    fn test_view_transform_moves_the_world() {
+        let transformation = view_transform(
+            Point::new(0.0, 0.0, 8.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+
+        assert_eq!(transformation, translate(0.0, 0.0, -8.0));
+    }
+
+    #[test]
+    fn test_view_transform_arbitrary() {
+        let transformation = view_transform(
+            Point::new(1.0, 3.0, 2.0),
+            Point::new(4.0, -2.0, 8.0),
+            Vec3::new(1.0, 1.0, 0.0),
+        );
+
+        assert!(abs_diff_eq!(
+            transformation,
+            Matrix::new([
+                [-0.50709, 0.50709, 0.67612, -2.36643],
+                [0.76772, 0.60609, 0.12122, -2.82843],
+                [-0.35857, 0.59761, -0.71714, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+            epsilon = 1e-5
+        ));
+    }
+
     proptest! {
         #[test]
         fn test_translate_vec3_noop(v in arbitrary_vec3()) {
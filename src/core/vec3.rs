@@ -1,6 +1,7 @@
 use std::ops::{Add, Div, Mul, Neg, Sub};
 
 use approx::AbsDiffEq;
+use serde::{Deserialize, Serialize};
 
 use super::Matrix;
 
@@ -13,7 +14,7 @@ use super::Matrix;
 ///
 /// let v = Vec3::new(1.0, 2.0, 3.0);
 /// ```
-#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Vec3(f64, f64, f64);
 
 impl Vec3 {
@@ -31,10 +32,13 @@ impl Vec3 {
         self.2
     }
 
+    /// Returns the Euclidean length of the vector.
     pub fn magnitude(&self) -> f64 {
-        self.x().abs() + self.y().abs() + self.z().abs()
+        self.dot(self).sqrt()
     }
 
+    /// Returns the vector scaled to unit length, or the zero vector for the
+    /// zero vector.
     pub fn normalize(&self) -> Vec3 {
         let mag = self.magnitude();
 
@@ -56,6 +60,11 @@ impl Vec3 {
             self.x() * other.y() - self.y() * other.x(),
         )
     }
+
+    /// Reflects the vector about a unit normal.
+    pub fn reflect(&self, normal: &Vec3) -> Vec3 {
+        *self - *normal * (2.0 * self.dot(normal))
+    }
 }
 
 impl From<(f64, f64, f64)> for Vec3 {
@@ -120,9 +129,9 @@ impl Mul<Vec3> for Vec3 {
     }
 }
 
-impl Into<Matrix<4, 1>> for Vec3 {
-    fn into(self) -> Matrix<4, 1> {
-        Matrix::new([[self.x()], [self.y()], [self.z()], [0.0]])
+impl From<Vec3> for Matrix<4, 1> {
+    fn from(v: Vec3) -> Self {
+        Matrix::new([[v.x()], [v.y()], [v.z()], [0.0]])
     }
 }
 
@@ -193,15 +202,15 @@ mod tests {
 
     #[test]
     fn test_vec3_magnitude() {
-        assert_eq!(Vec3::new(1.0, 2.0, 3.0).magnitude(), 6.0);
+        assert_eq!(Vec3::new(1.0, 2.0, 3.0).magnitude(), 14.0_f64.sqrt());
         assert_eq!(Vec3::new(1.0, 0.0, 0.0).magnitude(), 1.0);
-        assert_eq!(Vec3::new(-1.0, -5.0, 8.0).magnitude(), 14.0);
+        assert_eq!(Vec3::new(-2.0, -4.0, 4.0).magnitude(), 6.0);
     }
 
     #[test]
     fn test_vec3_normalize() {
-        let v = Vec3::new(20.0, 60.0, 120.0);
-        assert_eq!(v.normalize(), Vec3::new(0.1, 0.3, 0.6));
+        let v = Vec3::new(30.0, 0.0, -40.0);
+        assert_eq!(v.normalize(), Vec3::new(0.6, 0.0, -0.8));
     }
 
     #[test]
@@ -226,6 +235,23 @@ mod tests {
         assert_eq!(v2 * v1, Vec3::new(1.0, -2.0, 1.0));
     }
 
+    #[test]
+    fn test_vec3_reflect() {
+        let v = Vec3::new(1.0, -1.0, 0.0);
+        assert_eq!(
+            v.reflect(&Vec3::new(0.0, 1.0, 0.0)),
+            Vec3::new(1.0, 1.0, 0.0)
+        );
+
+        let half = 2.0_f64.sqrt() / 2.0;
+        let v = Vec3::new(0.0, -1.0, 0.0);
+        approx::assert_abs_diff_eq!(
+            v.reflect(&Vec3::new(half, half, 0.0)),
+            Vec3::new(1.0, 0.0, 0.0),
+            epsilon = 1e-12
+        );
+    }
+
     proptest! {
         #[test]
         fn test_vec3_mul_f64_commutative(v in arbitrary_vec3(), i in -1000.0..1000.0) {
@@ -0,0 +1,352 @@
+use crate::core::{transformations::Transform, Matrix, Point, Ray, Vec3};
+
+/// An axis-aligned bounding box.
+///
+/// An empty box has an infinite `min` and negative infinite `max`, so
+/// merging anything into it yields that thing.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::Point;
+/// use raytracing::geometry::BoundingBox;
+///
+/// let a = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+/// let b = BoundingBox::new(Point::new(0.0, 0.0, 0.0), Point::new(2.0, 3.0, 4.0));
+///
+/// let merged = a.merge(&b);
+///
+/// assert_eq!(merged.min, Point::new(-1.0, -1.0, -1.0));
+/// assert_eq!(merged.max, Point::new(2.0, 3.0, 4.0));
+/// assert!(merged.contains(&Point::new(1.5, 2.5, -0.5)));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    /// Creates a new bounding box spanning from `min` to `max`.
+    pub fn new(min: Point, max: Point) -> BoundingBox {
+        BoundingBox { min, max }
+    }
+
+    /// Returns a box containing nothing.
+    pub fn empty() -> BoundingBox {
+        BoundingBox::new(
+            Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        )
+    }
+
+    /// Returns the smallest box containing all given points.
+    pub fn from_points(points: impl IntoIterator<Item = Point>) -> BoundingBox {
+        points
+            .into_iter()
+            .fold(BoundingBox::empty(), |bounds, point| {
+                bounds.add_point(point)
+            })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x() > self.max.x() || self.min.y() > self.max.y() || self.min.z() > self.max.z()
+    }
+
+    /// Returns the box grown to contain `point`.
+    pub fn add_point(&self, point: Point) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(
+                self.min.x().min(point.x()),
+                self.min.y().min(point.y()),
+                self.min.z().min(point.z()),
+            ),
+            Point::new(
+                self.max.x().max(point.x()),
+                self.max.y().max(point.y()),
+                self.max.z().max(point.z()),
+            ),
+        )
+    }
+
+    /// Returns the smallest box containing both boxes.
+    pub fn merge(&self, other: &BoundingBox) -> BoundingBox {
+        self.add_point(other.min).add_point(other.max)
+    }
+
+    /// Returns whether the point lies inside or on the box.
+    pub fn contains(&self, point: &Point) -> bool {
+        (self.min.x()..=self.max.x()).contains(&point.x())
+            && (self.min.y()..=self.max.y()).contains(&point.y())
+            && (self.min.z()..=self.max.z()).contains(&point.z())
+    }
+
+    /// Returns whether the other box lies completely inside this box.
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains(&other.min) && self.contains(&other.max)
+    }
+
+    /// Returns the vector from `min` to `max`.
+    pub fn extent(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn centroid(&self) -> Point {
+        self.min + self.extent() * 0.5
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        let e = self.extent();
+        2.0 * (e.x() * e.y() + e.y() * e.z() + e.z() * e.x())
+    }
+
+    /// Returns the index of the longest axis (0 = x, 1 = y, 2 = z).
+    pub fn longest_axis(&self) -> usize {
+        let e = self.extent();
+        if e.x() >= e.y() && e.x() >= e.z() {
+            0
+        } else if e.y() >= e.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Returns the box containing the transformed box, by transforming all
+    /// eight corners.
+    pub fn transform(&self, transformation_matrix: &Matrix<4, 4>) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+
+        let (min, max) = (self.min, self.max);
+        BoundingBox::from_points(
+            [
+                Point::new(min.x(), min.y(), min.z()),
+                Point::new(min.x(), min.y(), max.z()),
+                Point::new(min.x(), max.y(), min.z()),
+                Point::new(min.x(), max.y(), max.z()),
+                Point::new(max.x(), min.y(), min.z()),
+                Point::new(max.x(), min.y(), max.z()),
+                Point::new(max.x(), max.y(), min.z()),
+                Point::new(max.x(), max.y(), max.z()),
+            ]
+            .map(|corner| corner.transform(transformation_matrix)),
+        )
+    }
+
+    /// Returns the distances at which the ray enters and leaves the box, or
+    /// `None` if it misses. The entry distance is negative when the ray
+    /// starts inside the box; boxes entirely behind the origin are missed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::core::{Point, Ray, Vec3};
+    /// use raytracing::geometry::BoundingBox;
+    ///
+    /// let bounds = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+    /// let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+    ///
+    /// assert_eq!(bounds.intersect(&ray), Some((4.0, 6.0)));
+    /// ```
+    pub fn intersect(&self, ray: &Ray) -> Option<(f64, f64)> {
+        self.intersect_slabs(&ray.origin, &inverse_direction(&ray.direction))
+    }
+
+    /// Returns whether the ray hits the box in front of its origin.
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        self.intersect(ray).is_some()
+    }
+
+    /// Slab test with a precomputed reciprocal of the ray direction, so it
+    /// can be reused for many boxes.
+    pub fn intersect_slabs(&self, origin: &Point, inverse_direction: &Vec3) -> Option<(f64, f64)> {
+        let (tx_min, tx_max) = slab(
+            self.min.x(),
+            self.max.x(),
+            origin.x(),
+            inverse_direction.x(),
+        );
+        let (ty_min, ty_max) = slab(
+            self.min.y(),
+            self.max.y(),
+            origin.y(),
+            inverse_direction.y(),
+        );
+        let (tz_min, tz_max) = slab(
+            self.min.z(),
+            self.max.z(),
+            origin.z(),
+            inverse_direction.z(),
+        );
+
+        let t_min = tx_min.max(ty_min).max(tz_min);
+        let t_max = tx_max.min(ty_max).min(tz_max);
+
+        if t_min > t_max || t_max < 0.0 {
+            None
+        } else {
+            Some((t_min, t_max))
+        }
+    }
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        BoundingBox::empty()
+    }
+}
+
+/// Returns the component-wise reciprocal of a ray direction.
+pub fn inverse_direction(direction: &Vec3) -> Vec3 {
+    Vec3::new(
+        1.0 / direction.x(),
+        1.0 / direction.y(),
+        1.0 / direction.z(),
+    )
+}
+
+fn slab(min: f64, max: f64, origin: f64, inverse_direction: f64) -> (f64, f64) {
+    let t0 = (min - origin) * inverse_direction;
+    let t1 = (max - origin) * inverse_direction;
+
+    // A ray parallel to the slab and starting on its plane yields NaN.
+    if t0.is_nan() || t1.is_nan() {
+        return (f64::NEG_INFINITY, f64::INFINITY);
+    }
+
+    (t0.min(t1), t0.max(t1))
+}
+
+/// A value with a known axis-aligned extent in its own coordinate space.
+pub trait Bounded {
+    /// Returns the object space bounding box.
+    fn bounds(&self) -> BoundingBox;
+}
+
+impl Bounded for BoundingBox {
+    fn bounds(&self) -> BoundingBox {
+        *self
+    }
+}
+
+impl Bounded for Point {
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(*self, *self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_utils::arbitrary_point;
+    use crate::core::transformations::{rotate_y, scale, translate};
+    use approx::assert_abs_diff_eq;
+    use proptest::prelude::*;
+
+    fn unit_box() -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_empty_box() {
+        let bounds = BoundingBox::empty();
+
+        assert!(bounds.is_empty());
+        assert!(!bounds.contains(&Point::default()));
+        assert_eq!(bounds.surface_area(), 0.0);
+        assert_eq!(bounds.merge(&unit_box()), unit_box());
+    }
+
+    #[test]
+    fn test_box_contains_box() {
+        let inner = BoundingBox::new(Point::new(0.0, 0.0, 0.0), Point::new(0.5, 1.0, 0.5));
+        let outer = BoundingBox::new(Point::new(0.0, 0.0, 0.0), Point::new(0.5, 1.1, 0.5));
+
+        assert!(unit_box().contains_box(&inner));
+        assert!(!unit_box().contains_box(&outer));
+    }
+
+    #[test]
+    fn test_box_surface_area() {
+        let bounds = BoundingBox::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 2.0, 3.0));
+
+        assert_eq!(bounds.surface_area(), 22.0);
+        assert_eq!(bounds.longest_axis(), 2);
+        assert_eq!(bounds.centroid(), Point::new(0.5, 1.0, 1.5));
+    }
+
+    #[test]
+    fn test_box_transform() {
+        let transformed = unit_box().transform(&(translate(1.0, 0.0, 0.0) * scale(2.0, 1.0, 1.0)));
+
+        assert_eq!(transformed.min, Point::new(-1.0, -1.0, -1.0));
+        assert_eq!(transformed.max, Point::new(3.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_box_transform_rotation() {
+        let transformed = unit_box().transform(&rotate_y(std::f64::consts::PI / 4.0));
+        let half_diagonal = 2.0_f64.sqrt();
+
+        assert_abs_diff_eq!(
+            transformed.max,
+            Point::new(half_diagonal, 1.0, half_diagonal)
+        );
+    }
+
+    #[test]
+    fn test_box_intersect() {
+        let cases = [
+            (Point::new(5.0, 0.5, 0.0), Vec3::new(-1.0, 0.0, 0.0), true),
+            (Point::new(-5.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0), true),
+            (Point::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), true),
+            (Point::new(0.0, 0.5, 0.0), Vec3::new(0.0, 0.0, 1.0), true),
+            (Point::new(-2.0, 0.0, 0.0), Vec3::new(2.0, 4.0, 6.0), false),
+            (Point::new(2.0, 0.0, 2.0), Vec3::new(0.0, 0.0, -1.0), false),
+            (Point::new(2.0, 2.0, 0.0), Vec3::new(-1.0, 0.0, 0.0), false),
+        ];
+
+        for (origin, direction, hit) in cases {
+            assert_eq!(unit_box().intersects(&Ray::new(origin, direction)), hit);
+        }
+    }
+
+    #[test]
+    fn test_box_behind_ray_is_missed() {
+        let ray = Ray::new(Point::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 1.0));
+
+        assert_eq!(unit_box().intersect(&ray), None);
+        assert!(!unit_box().intersects(&ray));
+
+        let inside = Ray::new(Point::new(0.0, 0.0, 0.5), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(unit_box().intersect(&inside), Some((-1.5, 0.5)));
+    }
+
+    #[test]
+    fn test_box_intersect_on_face_plane() {
+        let ray = Ray::new(Point::new(1.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+
+        assert_eq!(unit_box().intersect(&ray), Some((4.0, 6.0)));
+    }
+
+    proptest! {
+        #[test]
+        fn test_box_from_points_contains_points(
+            a in arbitrary_point(),
+            b in arbitrary_point(),
+            c in arbitrary_point()
+        ) {
+            let bounds = BoundingBox::from_points([a, b, c]);
+
+            prop_assert!(bounds.contains(&a));
+            prop_assert!(bounds.contains(&b));
+            prop_assert!(bounds.contains(&c));
+        }
+    }
+}
@@ -0,0 +1,419 @@
+use super::{inverse_direction, Bounded, BoundingBox};
+use crate::core::{Point, Ray};
+
+const BIN_COUNT: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+/// Cost of traversing a node relative to intersecting a primitive.
+const TRAVERSAL_COST: f64 = 0.125;
+
+/// A bounding volume hierarchy over a slice of primitives.
+///
+/// The hierarchy is built with the surface area heuristic, evaluated over a
+/// fixed number of bins per split, and flattened into a single array in
+/// depth-first order: the first child of a node directly follows it.
+///
+/// The hierarchy only stores primitive indices; intersecting primitives is
+/// left to a closure, so it works for any list of [`Bounded`] values.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::{Point, Ray, Vec3};
+/// use raytracing::geometry::{BoundingBox, Bvh};
+///
+/// let boxes: Vec<_> = (0..10)
+///     .map(|i| {
+///         let x = i as f64 * 3.0;
+///         BoundingBox::new(Point::new(x, 0.0, 0.0), Point::new(x + 1.0, 1.0, 1.0))
+///     })
+///     .collect();
+/// let bvh = Bvh::build(&boxes);
+///
+/// let ray = Ray::new(Point::new(-5.0, 0.5, 0.5), Vec3::new(1.0, 0.0, 0.0));
+/// let hit = bvh.intersect(&ray, f64::INFINITY, |i, ray, _| {
+///     boxes[i].intersect(ray).map(|(t, _)| t)
+/// });
+///
+/// assert_eq!(hit, Some((0, 5.0)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Node {
+    bounds: BoundingBox,
+    kind: NodeKind,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum NodeKind {
+    Leaf { first: usize, count: usize },
+    Interior { second_child: usize, axis: usize },
+}
+
+struct BuildItem {
+    index: usize,
+    bounds: BoundingBox,
+    centroid: Point,
+}
+
+impl Bvh {
+    /// Builds a hierarchy over the given primitives.
+    pub fn build<T: Bounded>(primitives: &[T]) -> Bvh {
+        let mut items: Vec<_> = primitives
+            .iter()
+            .enumerate()
+            .map(|(index, primitive)| {
+                let bounds = primitive.bounds();
+                BuildItem {
+                    index,
+                    bounds,
+                    centroid: bounds.centroid(),
+                }
+            })
+            .collect();
+
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity(2 * items.len()),
+            indices: Vec::with_capacity(items.len()),
+        };
+        if !items.is_empty() {
+            bvh.build_node(&mut items);
+        }
+
+        bvh
+    }
+
+    /// Returns the bounds of all primitives.
+    pub fn bounds(&self) -> BoundingBox {
+        self.nodes
+            .first()
+            .map_or_else(BoundingBox::empty, |node| node.bounds)
+    }
+
+    /// Returns the number of nodes in the hierarchy.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the closest hit along the ray closer than `t_max`, as the
+    /// index of the primitive and the distance.
+    ///
+    /// `hit` is called with a primitive index, the ray and the distance of
+    /// the closest hit so far, and returns the distance of its hit, if any.
+    /// Children are visited front to back so far away nodes can be skipped.
+    pub fn intersect(
+        &self,
+        ray: &Ray,
+        t_max: f64,
+        mut hit: impl FnMut(usize, &Ray, f64) -> Option<f64>,
+    ) -> Option<(usize, f64)> {
+        let mut closest: Option<(usize, f64)> = None;
+        let mut t_max = t_max;
+
+        self.traverse(ray, t_max, |index, ray| {
+            if let Some(t) = hit(index, ray, t_max).filter(|&t| t < t_max) {
+                t_max = t;
+                closest = Some((index, t));
+            }
+            (false, t_max)
+        });
+
+        closest
+    }
+
+    /// Returns whether any primitive is hit closer than `t_max`, stopping
+    /// at the first hit. Meant for shadow rays.
+    pub fn any_hit(
+        &self,
+        ray: &Ray,
+        t_max: f64,
+        mut hit: impl FnMut(usize, &Ray) -> Option<f64>,
+    ) -> bool {
+        let mut found = false;
+
+        self.traverse(ray, t_max, |index, ray| {
+            found = hit(index, ray).is_some_and(|t| t < t_max);
+            (found, t_max)
+        });
+
+        found
+    }
+
+    /// Walks the nodes hit by the ray front to back. `visit` is called for
+    /// every primitive in a hit leaf and returns whether to stop, and the
+    /// distance beyond which nodes can be skipped.
+    fn traverse(&self, ray: &Ray, t_max: f64, mut visit: impl FnMut(usize, &Ray) -> (bool, f64)) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let inverse_direction = inverse_direction(&ray.direction);
+        let negative = [
+            inverse_direction.x() < 0.0,
+            inverse_direction.y() < 0.0,
+            inverse_direction.z() < 0.0,
+        ];
+        let mut t_max = t_max;
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0);
+
+        while let Some(current) = stack.pop() {
+            let node = &self.nodes[current];
+            match node.bounds.intersect_slabs(&ray.origin, &inverse_direction) {
+                Some((t_near, _)) if t_near <= t_max => {}
+                _ => continue,
+            }
+
+            match node.kind {
+                NodeKind::Leaf { first, count } => {
+                    for &index in &self.indices[first..first + count] {
+                        let (stop, distance) = visit(index, ray);
+                        if stop {
+                            return;
+                        }
+                        t_max = distance;
+                    }
+                }
+                NodeKind::Interior { second_child, axis } => {
+                    if negative[axis] {
+                        stack.push(current + 1);
+                        stack.push(second_child);
+                    } else {
+                        stack.push(second_child);
+                        stack.push(current + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    fn build_node(&mut self, items: &mut [BuildItem]) -> usize {
+        let bounds = items.iter().fold(BoundingBox::empty(), |bounds, item| {
+            bounds.merge(&item.bounds)
+        });
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            bounds,
+            kind: NodeKind::Leaf { first: 0, count: 0 },
+        });
+
+        match find_split(items, &bounds) {
+            Some((axis, mid)) => {
+                let (left, right) = items.split_at_mut(mid);
+                self.build_node(left);
+                let second_child = self.build_node(right);
+                self.nodes[node].kind = NodeKind::Interior { second_child, axis };
+            }
+            None => {
+                let first = self.indices.len();
+                self.indices.extend(items.iter().map(|item| item.index));
+                self.nodes[node].kind = NodeKind::Leaf {
+                    first,
+                    count: items.len(),
+                };
+            }
+        }
+
+        node
+    }
+}
+
+/// Finds the cheapest split according to the surface area heuristic and
+/// partitions the items accordingly. Returns the split axis and the number
+/// of items on the left side, or `None` if a leaf is cheaper.
+fn find_split(items: &mut [BuildItem], bounds: &BoundingBox) -> Option<(usize, usize)> {
+    if items.len() <= 1 {
+        return None;
+    }
+
+    let centroid_bounds = BoundingBox::from_points(items.iter().map(|item| item.centroid));
+    let axis = centroid_bounds.longest_axis();
+    let (min, max) = (
+        component(&centroid_bounds.min, axis),
+        component(&centroid_bounds.max, axis),
+    );
+    if max <= min {
+        // All centroids coincide, splitting cannot separate them.
+        return None;
+    }
+
+    let bin_of = |item: &BuildItem| {
+        let offset = (component(&item.centroid, axis) - min) / (max - min);
+        ((offset * BIN_COUNT as f64) as usize).min(BIN_COUNT - 1)
+    };
+
+    let mut bins = [(BoundingBox::empty(), 0usize); BIN_COUNT];
+    for item in items.iter() {
+        let bin = &mut bins[bin_of(item)];
+        bin.0 = bin.0.merge(&item.bounds);
+        bin.1 += 1;
+    }
+
+    // Cost of splitting after every bin, sweeping from both sides.
+    let mut left_area = [0.0; BIN_COUNT - 1];
+    let mut left_count = [0; BIN_COUNT - 1];
+    let (mut accumulated, mut count) = (BoundingBox::empty(), 0);
+    for i in 0..BIN_COUNT - 1 {
+        accumulated = accumulated.merge(&bins[i].0);
+        count += bins[i].1;
+        left_area[i] = accumulated.surface_area();
+        left_count[i] = count;
+    }
+
+    let (mut accumulated, mut count) = (BoundingBox::empty(), 0);
+    let mut best: Option<(usize, f64)> = None;
+    for i in (0..BIN_COUNT - 1).rev() {
+        accumulated = accumulated.merge(&bins[i + 1].0);
+        count += bins[i + 1].1;
+        if left_count[i] == 0 || count == 0 {
+            continue;
+        }
+
+        let cost = TRAVERSAL_COST
+            + (left_area[i] * left_count[i] as f64 + accumulated.surface_area() * count as f64)
+                / bounds.surface_area();
+        if best.is_none_or(|(_, best_cost)| cost < best_cost) {
+            best = Some((i, cost));
+        }
+    }
+
+    let (split_bin, cost) = best?;
+    if items.len() <= MAX_LEAF_SIZE && cost >= items.len() as f64 {
+        return None;
+    }
+
+    let mut mid = 0;
+    for i in 0..items.len() {
+        if bin_of(&items[i]) <= split_bin {
+            items.swap(i, mid);
+            mid += 1;
+        }
+    }
+
+    Some((axis, mid))
+}
+
+fn component(point: &Point, axis: usize) -> f64 {
+    match axis {
+        0 => point.x(),
+        1 => point.y(),
+        _ => point.z(),
+    }
+}
+
+impl Bounded for Bvh {
+    fn bounds(&self) -> BoundingBox {
+        Bvh::bounds(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Vec3;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn random_boxes(count: usize) -> Vec<BoundingBox> {
+        let mut rng = StdRng::seed_from_u64(3);
+        (0..count)
+            .map(|_| {
+                let min = Point::new(
+                    rng.gen_range(-50.0..50.0),
+                    rng.gen_range(-50.0..50.0),
+                    rng.gen_range(-50.0..50.0),
+                );
+                min.bounds().add_point(min + Vec3::new(1.0, 1.0, 1.0))
+            })
+            .collect()
+    }
+
+    fn brute_force(boxes: &[BoundingBox], ray: &Ray) -> Option<(usize, f64)> {
+        boxes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, b)| b.intersect(ray).map(|(t, _)| (i, t)))
+            .filter(|&(_, t)| t >= 0.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    fn hit(boxes: &[BoundingBox], i: usize, ray: &Ray) -> Option<f64> {
+        boxes[i]
+            .intersect(ray)
+            .map(|(t, _)| t)
+            .filter(|&t| t >= 0.0)
+    }
+
+    #[test]
+    fn test_bvh_empty() {
+        let bvh = Bvh::build::<BoundingBox>(&[]);
+        let ray = Ray::new(Point::default(), Vec3::new(0.0, 0.0, 1.0));
+
+        assert!(bvh.bounds().is_empty());
+        assert_eq!(
+            bvh.intersect(&ray, f64::INFINITY, |_, _, _| Some(1.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_bvh_contains_every_primitive_once() {
+        let boxes = random_boxes(500);
+        let bvh = Bvh::build(&boxes);
+
+        let mut indices = bvh.indices.clone();
+        indices.sort();
+
+        assert_eq!(indices, (0..500).collect::<Vec<_>>());
+        assert!(bvh.node_count() > 1);
+        assert_eq!(
+            bvh.bounds(),
+            boxes
+                .iter()
+                .fold(BoundingBox::empty(), |bounds, b| bounds.merge(b))
+        );
+    }
+
+    #[test]
+    fn test_bvh_matches_brute_force() {
+        let boxes = random_boxes(500);
+        let bvh = Bvh::build(&boxes);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..200 {
+            let ray = Ray::new(
+                Point::new(
+                    rng.gen_range(-60.0..60.0),
+                    rng.gen_range(-60.0..60.0),
+                    -100.0,
+                ),
+                Vec3::new(rng.gen_range(-0.3..0.3), rng.gen_range(-0.3..0.3), 1.0),
+            );
+
+            let expected = brute_force(&boxes, &ray);
+            let actual = bvh.intersect(&ray, f64::INFINITY, |i, ray, _| hit(&boxes, i, ray));
+
+            assert_eq!(actual.map(|(_, t)| t), expected.map(|(_, t)| t));
+            assert_eq!(
+                bvh.any_hit(&ray, f64::INFINITY, |i, ray| hit(&boxes, i, ray)),
+                expected.is_some()
+            );
+        }
+    }
+
+    #[test]
+    fn test_bvh_any_hit_respects_distance() {
+        let boxes = [BoundingBox::new(
+            Point::new(-1.0, -1.0, 4.0),
+            Point::new(1.0, 1.0, 5.0),
+        )];
+        let bvh = Bvh::build(&boxes);
+        let ray = Ray::new(Point::default(), Vec3::new(0.0, 0.0, 1.0));
+
+        assert!(bvh.any_hit(&ray, 10.0, |i, ray| hit(&boxes, i, ray)));
+        assert!(!bvh.any_hit(&ray, 3.0, |i, ray| hit(&boxes, i, ray)));
+    }
+}
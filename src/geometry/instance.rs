@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use super::{Bounded, BoundingBox};
+use crate::core::{transformations::Transform, Matrix, Ray, Vec3};
+
+/// A placement of shared geometry in the scene.
+///
+/// Instances share their geometry (and any acceleration structure inside
+/// it) through an [`Arc`], and only store their own transformation and
+/// optionally the index of the surface they belong to, so the same mesh can
+/// be placed many times without copying it.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use raytracing::core::{transformations::translate, Point};
+/// use raytracing::geometry::{Bounded, BoundingBox, Instance};
+///
+/// let geometry = Arc::new(BoundingBox::new(
+///     Point::new(-1.0, -1.0, -1.0),
+///     Point::new(1.0, 1.0, 1.0),
+/// ));
+///
+/// let a = Instance::new(geometry.clone(), translate(5.0, 0.0, 0.0));
+/// let b = Instance::new(geometry, translate(-5.0, 0.0, 0.0)).with_surface(2);
+///
+/// assert_eq!(a.bounds().min, Point::new(4.0, -1.0, -1.0));
+/// assert_eq!(b.surface, Some(2));
+/// ```
+#[derive(Debug)]
+pub struct Instance<T> {
+    pub geometry: Arc<T>,
+    /// Index of the surface the instance belongs to, which the world uses to
+    /// look up its material and other per-object settings.
+    pub surface: Option<usize>,
+    transformation: Matrix<4, 4>,
+    inverse: Matrix<4, 4>,
+    bounds: BoundingBox,
+}
+
+impl<T: Bounded> Instance<T> {
+    /// Creates an instance of `geometry` placed with the given object to
+    /// world transformation.
+    pub fn new(geometry: Arc<T>, transformation: Matrix<4, 4>) -> Instance<T> {
+        let bounds = geometry.bounds().transform(&transformation);
+
+        Instance {
+            geometry,
+            surface: None,
+            transformation,
+            inverse: transformation.inverse(),
+            bounds,
+        }
+    }
+}
+
+impl<T> Instance<T> {
+    /// Returns the instance assigned to the given surface.
+    pub fn with_surface(self, surface: usize) -> Instance<T> {
+        Instance {
+            surface: Some(surface),
+            ..self
+        }
+    }
+
+    pub fn transformation(&self) -> &Matrix<4, 4> {
+        &self.transformation
+    }
+
+    /// Transforms a world space ray into the space of the geometry.
+    ///
+    /// The direction is not normalized, so distances along the object space
+    /// ray equal distances along the world space ray.
+    pub fn to_object(&self, ray: &Ray) -> Ray {
+        ray.transform(&self.inverse)
+    }
+
+    /// Transforms an object space normal into a unit world space normal.
+    pub fn normal_to_world(&self, normal: &Vec3) -> Vec3 {
+        normal.transform(&self.inverse.transpose()).normalize()
+    }
+
+    /// Intersects the geometry with the world space ray, returning the
+    /// distance found by `intersect` for the object space ray.
+    pub fn intersect<R>(&self, ray: &Ray, intersect: impl FnOnce(&T, &Ray) -> R) -> R {
+        intersect(&self.geometry, &self.to_object(ray))
+    }
+}
+
+impl<T> Clone for Instance<T> {
+    fn clone(&self) -> Self {
+        Instance {
+            geometry: self.geometry.clone(),
+            surface: self.surface,
+            transformation: self.transformation,
+            inverse: self.inverse,
+            bounds: self.bounds,
+        }
+    }
+}
+
+impl<T> Bounded for Instance<T> {
+    /// Returns the world space bounds of the instance.
+    fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        transformations::{scale, translate},
+        Point,
+    };
+    use crate::geometry::Bvh;
+    use approx::assert_abs_diff_eq;
+
+    fn unit_box() -> Arc<BoundingBox> {
+        Arc::new(BoundingBox::new(
+            Point::new(-1.0, -1.0, -1.0),
+            Point::new(1.0, 1.0, 1.0),
+        ))
+    }
+
+    #[test]
+    fn test_instances_share_geometry() {
+        let geometry = unit_box();
+        let instances: Vec<_> = (0..100)
+            .map(|i| Instance::new(geometry.clone(), translate(i as f64 * 3.0, 0.0, 0.0)))
+            .collect();
+
+        assert_eq!(Arc::strong_count(&geometry), 101);
+        assert_eq!(instances[99].bounds().max, Point::new(298.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_instance_intersect_preserves_distance() {
+        let instance = Instance::new(unit_box(), translate(0.0, 0.0, 10.0) * scale(2.0, 2.0, 2.0));
+        let ray = Ray::new(Point::default(), Vec3::new(0.0, 0.0, 1.0));
+
+        let hit = instance.intersect(&ray, |geometry, ray| geometry.intersect(ray));
+
+        assert_eq!(hit, Some((8.0, 12.0)));
+    }
+
+    #[test]
+    fn test_instance_normal_to_world() {
+        let instance = Instance::new(unit_box(), scale(1.0, 0.5, 1.0));
+        let normal = instance.normal_to_world(&Vec3::new(0.0, 1.0, 0.0));
+
+        assert_abs_diff_eq!(normal, Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_instance_normal_to_world_non_uniform_scale() {
+        // Stretching x and squashing y tilts normals towards the y axis.
+        let instance = Instance::new(unit_box(), scale(2.0, 0.5, 1.0));
+        let normal = instance.normal_to_world(&Vec3::new(1.0, 1.0, 0.0));
+
+        assert_abs_diff_eq!(normal.magnitude(), 1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(
+            normal,
+            Vec3::new(0.5, 2.0, 0.0).normalize(),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_bvh_over_instances() {
+        let geometry = unit_box();
+        let instances: Vec<_> = (0..10)
+            .map(|i| Instance::new(geometry.clone(), translate(0.0, 0.0, i as f64 * 5.0)))
+            .collect();
+        let bvh = Bvh::build(&instances);
+        let ray = Ray::new(Point::new(0.0, 0.0, 12.0), Vec3::new(0.0, 0.0, 1.0));
+
+        let hit = bvh.intersect(&ray, f64::INFINITY, |i, ray, _| {
+            instances[i].intersect(ray, |geometry, ray| {
+                geometry
+                    .intersect(ray)
+                    .map(|(t, _)| t)
+                    .filter(|&t| t >= 0.0)
+            })
+        });
+
+        assert_eq!(hit, Some((3, 2.0)));
+    }
+}
@@ -0,0 +1,252 @@
+use anyhow::{bail, Context, Result};
+
+use super::{Bounded, BoundingBox, Bvh};
+use crate::core::{Point, Ray, Vec3};
+
+/// A triangle with its vertices in counter-clockwise order seen from the
+/// front.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Triangle {
+    pub vertices: [Point; 3],
+}
+
+impl Triangle {
+    pub fn new(a: Point, b: Point, c: Point) -> Triangle {
+        Triangle {
+            vertices: [a, b, c],
+        }
+    }
+
+    /// Returns the unit normal on the front side.
+    pub fn normal(&self) -> Vec3 {
+        let [a, b, c] = self.vertices;
+
+        (b - a).cross(&(c - a)).normalize()
+    }
+
+    /// Returns the distance to the triangle along the ray, which may be
+    /// negative, using the Möller-Trumbore algorithm.
+    pub fn intersect(&self, ray: &Ray) -> Option<f64> {
+        let [a, b, c] = self.vertices;
+        let (edge1, edge2) = (b - a, c - a);
+        let p = ray.direction.cross(&edge2);
+        let determinant = edge1.dot(&p);
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+
+        let f = 1.0 / determinant;
+        let to_origin = ray.origin - a;
+        let u = f * to_origin.dot(&p);
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = to_origin.cross(&edge1);
+        let v = f * ray.direction.dot(&q);
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        Some(f * edge2.dot(&q))
+    }
+}
+
+impl Bounded for Triangle {
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::from_points(self.vertices)
+    }
+}
+
+/// Triangles with a bounding volume hierarchy over them.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::{Point, Ray, Vec3};
+/// use raytracing::geometry::Mesh;
+///
+/// let mesh = Mesh::parse_obj(
+///     "
+/// v 0 0 0
+/// v 1 0 0
+/// v 1 1 0
+/// v 0 1 0
+/// f 1 2 3 4
+/// ",
+/// )
+/// .unwrap();
+/// let ray = Ray::new(Point::new(0.25, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0));
+///
+/// assert_eq!(mesh.triangles().len(), 2);
+/// assert_eq!(
+///     mesh.intersect(&ray, f64::INFINITY),
+///     Some((2.0, Vec3::new(0.0, 0.0, 1.0)))
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    triangles: Vec<Triangle>,
+    bvh: Bvh,
+}
+
+impl Mesh {
+    pub fn new(triangles: Vec<Triangle>) -> Mesh {
+        let bvh = Bvh::build(&triangles);
+
+        Mesh { triangles, bvh }
+    }
+
+    /// Reads the vertices and faces of a Wavefront OBJ file.
+    ///
+    /// Faces with more than three vertices are split into a fan of
+    /// triangles. Texture coordinates, normals, groups and materials are
+    /// ignored.
+    pub fn parse_obj(source: &str) -> Result<Mesh> {
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+
+        for (i, line) in source.lines().enumerate() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("v") => {
+                    let coordinates = fields
+                        .take(3)
+                        .map(str::parse)
+                        .collect::<Result<Vec<f64>, _>>()
+                        .with_context(|| format!("Invalid vertex on line {}", i + 1))?;
+                    let [x, y, z] = coordinates[..] else {
+                        bail!("Line {}: a vertex needs 3 coordinates", i + 1);
+                    };
+                    vertices.push(Point::new(x, y, z));
+                }
+                Some("f") => {
+                    let face = fields
+                        .map(|field| vertex_index(field, vertices.len()))
+                        .collect::<Result<Vec<_>>>()
+                        .with_context(|| format!("Invalid face on line {}", i + 1))?;
+                    if face.len() < 3 {
+                        bail!("Line {}: a face needs at least 3 vertices", i + 1);
+                    }
+
+                    for pair in face[1..].windows(2) {
+                        triangles.push(Triangle::new(
+                            vertices[face[0]],
+                            vertices[pair[0]],
+                            vertices[pair[1]],
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Mesh::new(triangles))
+    }
+
+    pub fn triangles(&self) -> &[Triangle] {
+        &self.triangles
+    }
+
+    /// Returns the closest hit in front of the ray origin and closer than
+    /// `t_max`, with the normal of the triangle that was hit.
+    pub fn intersect(&self, ray: &Ray, t_max: f64) -> Option<(f64, Vec3)> {
+        self.bvh
+            .intersect(ray, t_max, |index, ray, _| {
+                self.triangles[index].intersect(ray).filter(|&t| t > 0.0)
+            })
+            .map(|(index, t)| (t, self.triangles[index].normal()))
+    }
+}
+
+impl Bounded for Mesh {
+    fn bounds(&self) -> BoundingBox {
+        self.bvh.bounds()
+    }
+}
+
+/// Returns the zero based vertex index of a face entry such as `3`,
+/// `3/1/2` or `-1`, where negative indices count back from the last vertex.
+fn vertex_index(field: &str, count: usize) -> Result<usize> {
+    let index: i64 = field.split('/').next().unwrap_or_default().parse()?;
+    let index = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if !(0..count as i64).contains(&index) {
+        bail!("Vertex {} does not exist", field);
+    }
+
+    Ok(index as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_triangle_intersect() {
+        let triangle = Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        );
+
+        let hit = Ray::new(Point::new(0.0, 0.5, -2.0), Vec3::new(0.0, 0.0, 1.0));
+        let outside = Ray::new(Point::new(1.0, 1.0, -2.0), Vec3::new(0.0, 0.0, 1.0));
+        let parallel = Ray::new(Point::new(0.0, -1.0, -2.0), Vec3::new(0.0, 1.0, 0.0));
+
+        assert_eq!(triangle.intersect(&hit), Some(2.0));
+        assert_eq!(triangle.intersect(&outside), None);
+        assert_eq!(triangle.intersect(&parallel), None);
+        assert_eq!(triangle.normal(), Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_parse_obj() {
+        let mesh = Mesh::parse_obj(
+            "
+# A pyramid without a base.
+v 0 1 0
+v -1 0 -1
+v 1 0 -1
+v 0 0 1
+vn 0 1 0
+f 1/1/1 2/2/1 3/3/1
+f -4 -2 -1
+f 1 4 2
+",
+        )
+        .unwrap();
+
+        assert_eq!(mesh.triangles().len(), 3);
+        assert_eq!(mesh.triangles()[1].vertices[1], Point::new(1.0, 0.0, -1.0));
+        assert_eq!(mesh.bounds().min, Point::new(-1.0, 0.0, -1.0));
+
+        let ray = Ray::new(Point::new(0.0, 5.0, -0.2), Vec3::new(0.0, -1.0, 0.0));
+        let (t, normal) = mesh.intersect(&ray, f64::INFINITY).unwrap();
+        assert_abs_diff_eq!(t, 4.2, epsilon = 1e-12);
+        assert_abs_diff_eq!(
+            normal.dot(&Vec3::new(0.0, 1.0, -1.0).normalize()).abs(),
+            1.0,
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_parse_obj_errors() {
+        let cases = [
+            ("v 1 2", "Line 1: a vertex needs 3 coordinates"),
+            ("v 1 2 x", "Invalid vertex on line 1"),
+            ("v 0 0 0\nf 1 1", "Line 2: a face needs at least 3 vertices"),
+            ("v 0 0 0\nf 1 2 3", "Vertex 2 does not exist"),
+        ];
+
+        for (source, message) in cases {
+            let error = format!("{:#}", Mesh::parse_obj(source).unwrap_err());
+            assert!(error.contains(message), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,11 @@
+mod bounding_box;
+mod bvh;
+mod instance;
+mod mesh;
+mod primitive;
+
+pub use bounding_box::{inverse_direction, Bounded, BoundingBox};
+pub use bvh::Bvh;
+pub use instance::Instance;
+pub use mesh::{Mesh, Triangle};
+pub use primitive::Primitive;
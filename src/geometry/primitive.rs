@@ -0,0 +1,384 @@
+use super::{Bounded, BoundingBox, Mesh};
+use crate::core::{Point, Ray, Vec3};
+
+/// Below this the ray is considered parallel to a surface.
+const EPSILON: f64 = 1e-9;
+
+/// A shape in its own object space, as in The Ray Tracer Challenge.
+///
+/// Spheres have radius 1 around the origin, cubes span -1 to 1 on every
+/// axis, planes are the xz plane, and cylinders and cones have radius 1
+/// (respectively |y|) around the y axis between `minimum` and `maximum`.
+/// Objects are placed in the scene with an [`Instance`](super::Instance).
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::{Point, Ray, Vec3};
+/// use raytracing::geometry::Primitive;
+///
+/// let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+/// let (t, normal) = Primitive::Sphere.intersect(&ray, f64::INFINITY).unwrap();
+///
+/// assert_eq!(t, 4.0);
+/// assert_eq!(normal, Vec3::new(0.0, 0.0, -1.0));
+/// assert_eq!(Primitive::Sphere.intersect(&ray, 3.0), None);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Primitive {
+    Sphere,
+    Plane,
+    Cube,
+    Cylinder {
+        minimum: f64,
+        maximum: f64,
+        closed: bool,
+    },
+    Cone {
+        minimum: f64,
+        maximum: f64,
+        closed: bool,
+    },
+    Mesh(Mesh),
+}
+
+impl Primitive {
+    /// Returns the closest hit in front of the ray origin and closer than
+    /// `t_max`, as the distance and the object space normal there, which is
+    /// not necessarily normalized.
+    pub fn intersect(&self, ray: &Ray, t_max: f64) -> Option<(f64, Vec3)> {
+        let mut closest = None;
+        let mut add = |t: f64, normal: Vec3| {
+            if t > 0.0 && t < closest.map_or(t_max, |(closest, _)| closest) {
+                closest = Some((t, normal));
+            }
+        };
+
+        match self {
+            Primitive::Sphere => {
+                let to_ray = ray.origin - Point::default();
+                let a = ray.direction.dot(&ray.direction);
+                let b = 2.0 * ray.direction.dot(&to_ray);
+                let c = to_ray.dot(&to_ray) - 1.0;
+
+                for t in solve_quadratic(a, b, c) {
+                    add(t, ray.position(t) - Point::default());
+                }
+            }
+            Primitive::Plane => {
+                if ray.direction.y().abs() >= EPSILON {
+                    add(
+                        -ray.origin.y() / ray.direction.y(),
+                        Vec3::new(0.0, 1.0, 0.0),
+                    );
+                }
+            }
+            Primitive::Cube => {
+                let bounds =
+                    BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+                if let Some((t_near, t_far)) = bounds.intersect(ray) {
+                    for t in [t_near, t_far] {
+                        add(t, cube_normal(&ray.position(t)));
+                    }
+                }
+            }
+            &Primitive::Cylinder {
+                minimum,
+                maximum,
+                closed,
+            } => {
+                let (o, d) = (ray.origin, ray.direction);
+                let a = d.x() * d.x() + d.z() * d.z();
+                let b = 2.0 * (o.x() * d.x() + o.z() * d.z());
+                let c = o.x() * o.x() + o.z() * o.z() - 1.0;
+
+                if a >= EPSILON {
+                    for t in solve_quadratic(a, b, c) {
+                        let point = ray.position(t);
+                        if minimum < point.y() && point.y() < maximum {
+                            add(t, Vec3::new(point.x(), 0.0, point.z()));
+                        }
+                    }
+                }
+                if closed {
+                    for (t, normal) in caps(ray, minimum, maximum, |_| 1.0) {
+                        add(t, normal);
+                    }
+                }
+            }
+            &Primitive::Cone {
+                minimum,
+                maximum,
+                closed,
+            } => {
+                let (o, d) = (ray.origin, ray.direction);
+                let a = d.x() * d.x() - d.y() * d.y() + d.z() * d.z();
+                let b = 2.0 * (o.x() * d.x() - o.y() * d.y() + o.z() * d.z());
+                let c = o.x() * o.x() - o.y() * o.y() + o.z() * o.z();
+
+                let roots = if a.abs() >= EPSILON {
+                    solve_quadratic(a, b, c)
+                } else if b.abs() >= EPSILON {
+                    // Parallel to one half of the cone, which is hit once.
+                    vec![-c / (2.0 * b)]
+                } else {
+                    Vec::new()
+                };
+                for t in roots {
+                    let point = ray.position(t);
+                    if minimum < point.y() && point.y() < maximum {
+                        let radius = (point.x() * point.x() + point.z() * point.z()).sqrt();
+                        let y = if point.y() > 0.0 { -radius } else { radius };
+                        add(t, Vec3::new(point.x(), y, point.z()));
+                    }
+                }
+                if closed {
+                    for (t, normal) in caps(ray, minimum, maximum, f64::abs) {
+                        add(t, normal);
+                    }
+                }
+            }
+            Primitive::Mesh(mesh) => {
+                if let Some((t, normal)) = mesh.intersect(ray, t_max) {
+                    add(t, normal);
+                }
+            }
+        }
+
+        closest
+    }
+}
+
+impl Bounded for Primitive {
+    /// Returns the object space bounds, which are infinite for planes and
+    /// for open ended cylinders and cones.
+    fn bounds(&self) -> BoundingBox {
+        match self {
+            Primitive::Sphere | Primitive::Cube => {
+                BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+            }
+            Primitive::Plane => BoundingBox::new(
+                Point::new(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+                Point::new(f64::INFINITY, 0.0, f64::INFINITY),
+            ),
+            &Primitive::Cylinder {
+                minimum, maximum, ..
+            } => BoundingBox::new(
+                Point::new(-1.0, minimum, -1.0),
+                Point::new(1.0, maximum, 1.0),
+            ),
+            &Primitive::Cone {
+                minimum, maximum, ..
+            } => {
+                let radius = minimum.abs().max(maximum.abs());
+                BoundingBox::new(
+                    Point::new(-radius, minimum, -radius),
+                    Point::new(radius, maximum, radius),
+                )
+            }
+            Primitive::Mesh(mesh) => mesh.bounds(),
+        }
+    }
+}
+
+/// Returns the real roots of a x² + b x + c.
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+
+    let root = discriminant.sqrt();
+    vec![(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+}
+
+fn cube_normal(point: &Point) -> Vec3 {
+    let (x, y, z) = (point.x().abs(), point.y().abs(), point.z().abs());
+    if x >= y && x >= z {
+        Vec3::new(point.x(), 0.0, 0.0)
+    } else if y >= z {
+        Vec3::new(0.0, point.y(), 0.0)
+    } else {
+        Vec3::new(0.0, 0.0, point.z())
+    }
+}
+
+/// Returns the hits with the end caps of a cylinder or cone at `minimum`
+/// and `maximum`, whose radius at height y is given by `radius`.
+fn caps(
+    ray: &Ray,
+    minimum: f64,
+    maximum: f64,
+    radius: impl Fn(f64) -> f64,
+) -> impl Iterator<Item = (f64, Vec3)> {
+    let parallel = ray.direction.y().abs() < EPSILON;
+    let ray = *ray;
+
+    [(minimum, -1.0), (maximum, 1.0)]
+        .into_iter()
+        .filter(move |(y, _)| !parallel && y.is_finite())
+        .filter_map(move |(y, side)| {
+            let t = (y - ray.origin.y()) / ray.direction.y();
+            let point = ray.position(t);
+            let r = radius(y);
+
+            (point.x() * point.x() + point.z() * point.z() <= r * r)
+                .then_some((t, Vec3::new(0.0, side, 0.0)))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn hit(primitive: &Primitive, origin: Point, direction: Vec3) -> Option<(f64, Vec3)> {
+        primitive
+            .intersect(&Ray::new(origin, direction), f64::INFINITY)
+            .map(|(t, normal)| (t, normal.normalize()))
+    }
+
+    #[test]
+    fn test_sphere_from_inside() {
+        let (t, normal) = hit(
+            &Primitive::Sphere,
+            Point::default(),
+            Vec3::new(0.0, 0.0, 1.0),
+        )
+        .unwrap();
+
+        assert_eq!(t, 1.0);
+        assert_eq!(normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(
+            hit(
+                &Primitive::Sphere,
+                Point::new(0.0, 0.0, 5.0),
+                Vec3::new(0.0, 0.0, 1.0)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_plane() {
+        let (t, normal) = hit(
+            &Primitive::Plane,
+            Point::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+        )
+        .unwrap();
+
+        assert_eq!(t, 1.0);
+        assert_eq!(normal, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(
+            hit(
+                &Primitive::Plane,
+                Point::new(0.0, 1.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cube_normals() {
+        let cases = [
+            (Point::new(5.0, 0.5, 0.0), Vec3::new(-1.0, 0.0, 0.0), 4.0),
+            (Point::new(0.5, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 4.0),
+            (Point::new(0.0, 0.5, 0.0), Vec3::new(0.0, 1.0, 0.0), 0.5),
+        ];
+
+        for (origin, direction, distance) in cases {
+            let (t, normal) = hit(&Primitive::Cube, origin, direction).unwrap();
+
+            assert_eq!(t, distance);
+            assert_eq!(normal.dot(&direction).abs(), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_truncated_cylinder() {
+        let cylinder = Primitive::Cylinder {
+            minimum: 1.0,
+            maximum: 2.0,
+            closed: false,
+        };
+        let closed = Primitive::Cylinder {
+            minimum: 1.0,
+            maximum: 2.0,
+            closed: true,
+        };
+
+        let (t, normal) = hit(
+            &cylinder,
+            Point::new(0.0, 1.5, -5.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        )
+        .unwrap();
+        assert_eq!(t, 4.0);
+        assert_eq!(normal, Vec3::new(0.0, 0.0, -1.0));
+
+        // Straight down the middle only hits the caps.
+        let down = (Point::new(0.0, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(hit(&cylinder, down.0, down.1), None);
+        assert_eq!(
+            hit(&closed, down.0, down.1),
+            Some((1.0, Vec3::new(0.0, 1.0, 0.0)))
+        );
+    }
+
+    #[test]
+    fn test_cone() {
+        let cone = Primitive::Cone {
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        };
+
+        let (t, _) = hit(&cone, Point::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0)).unwrap();
+        assert_abs_diff_eq!(t, 5.0);
+
+        // Parallel to one half of the cone.
+        let (t, _) = hit(
+            &cone,
+            Point::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 1.0).normalize(),
+        )
+        .unwrap();
+        assert_abs_diff_eq!(t, 0.35355, epsilon = 1e-5);
+
+        let (_, normal) = hit(&cone, Point::new(1.0, 1.0, -5.0), Vec3::new(0.0, 0.0, 1.0)).unwrap();
+        assert_abs_diff_eq!(
+            normal,
+            Vec3::new(1.0, -1.0, 0.0).normalize(),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_closed_cone_caps() {
+        let cone = Primitive::Cone {
+            minimum: -0.5,
+            maximum: 0.5,
+            closed: true,
+        };
+
+        let (t, normal) = hit(&cone, Point::new(0.0, 0.4, 0.0), Vec3::new(0.0, 1.0, 0.0)).unwrap();
+
+        assert_abs_diff_eq!(t, 0.1, epsilon = 1e-12);
+        assert_eq!(normal, Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let cone = Primitive::Cone {
+            minimum: -5.0,
+            maximum: 3.0,
+            closed: true,
+        };
+
+        assert_eq!(cone.bounds().min, Point::new(-5.0, -5.0, -5.0));
+        assert_eq!(cone.bounds().max, Point::new(5.0, 3.0, 5.0));
+        assert!(Primitive::Plane.bounds().max.x().is_infinite());
+    }
+}
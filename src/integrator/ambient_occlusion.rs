@@ -0,0 +1,72 @@
+use rand::{Rng, RngCore};
+
+use super::{Integrator, World};
+use crate::core::{Color, Frame, Ray};
+use crate::render::cosine_sample_hemisphere;
+
+/// Shades surfaces by how much of the hemisphere above them is open.
+///
+/// Every camera hit casts `samples` cosine weighted rays; the fraction
+/// that does not hit anything within `distance` is returned as a gray
+/// value. Rays that miss the scene are white.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AmbientOcclusion {
+    pub samples: usize,
+    pub distance: f64,
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        AmbientOcclusion {
+            samples: 16,
+            distance: 1.0,
+        }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, world: &dyn World, ray: &Ray, rng: &mut dyn RngCore) -> Color {
+        let Some(hit) = world.intersect(ray) else {
+            return Color::new(1.0, 1.0, 1.0);
+        };
+
+        let normal = hit.facing_normal(&ray.direction);
+        let frame = Frame::from_normal(normal);
+        let origin = hit.point + normal * hit.offset();
+        let open = (0..self.samples)
+            .filter(|_| {
+                let direction = frame.to_world(&cosine_sample_hemisphere(rng.gen(), rng.gen()));
+                world
+                    .intersect(&Ray::new(origin, direction))
+                    .is_none_or(|hit| hit.t > self.distance)
+            })
+            .count();
+
+        let value = open as f64 / self.samples.max(1) as f64;
+        Color::new(value, value, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrator::test_worlds::{down_at, Floor};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_open_and_covered_surfaces() {
+        let mut world = Floor::new();
+        world.blocker = true;
+        let integrator = AmbientOcclusion {
+            samples: 32,
+            distance: 10.0,
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let open = integrator.radiance(&world, &down_at(100.0), &mut rng);
+        let covered = integrator.radiance(&world, &down_at(-100.0), &mut rng);
+
+        assert_eq!(open, Color::new(1.0, 1.0, 1.0));
+        assert_eq!(covered, Color::new(0.0, 0.0, 0.0));
+    }
+}
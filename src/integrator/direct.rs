@@ -0,0 +1,73 @@
+use rand::RngCore;
+
+use super::{light_hit, sample_lights, Integrator, World};
+use crate::core::{Color, Frame, Ray};
+
+/// Shades the first surface with the light arriving straight from the
+/// light sources, without any bounces.
+///
+/// Surfaces reflect light according to their BSDF, see
+/// [`Hit::bsdf`](super::Hit::bsdf), lights are sampled once each, see
+/// [`Light::sample`](crate::scene::Light::sample). Emitting surfaces add
+/// their emission, area lights are seen directly.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct DirectLighting;
+
+impl Integrator for DirectLighting {
+    fn radiance(&self, world: &dyn World, ray: &Ray, rng: &mut dyn RngCore) -> Color {
+        let hit = world.intersect(ray);
+        if let Some((_, emitted)) = light_hit(world, ray, hit.map(|hit| hit.t)) {
+            return emitted;
+        }
+        let Some(hit) = hit else {
+            return world.background(ray);
+        };
+
+        let frame = Frame::from_normal(hit.normal);
+        let wo = frame.to_local(&-ray.direction.normalize());
+        let bsdf = hit.bsdf();
+
+        hit.emission + sample_lights(world, &hit, &frame, &wo, bsdf.as_ref(), None, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Vec3;
+    use crate::integrator::test_worlds::{down_at, Floor};
+    use crate::scene::LightKind;
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_inverse_square_falloff() {
+        let mut world = Floor::new();
+        world.blocker = true;
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let lit = DirectLighting.radiance(&world, &down_at(0.0), &mut rng);
+        let shadowed = DirectLighting.radiance(&world, &down_at(-1.0), &mut rng);
+
+        // The light is 2 units above the floor.
+        assert_abs_diff_eq!(lit.r(), 1.0 / (4.0 * PI), epsilon = 1e-6);
+        assert_eq!(shadowed, Color::default());
+    }
+
+    #[test]
+    fn test_directional_light() {
+        let mut world = Floor::new();
+        world.blocker = true;
+        world.lights[0].kind = LightKind::Directional {
+            direction: Vec3::new(0.0, -1.0, 0.0),
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let far = DirectLighting.radiance(&world, &down_at(100.0), &mut rng);
+        let shadowed = DirectLighting.radiance(&world, &down_at(-100.0), &mut rng);
+
+        assert_abs_diff_eq!(far.r(), 1.0 / PI, epsilon = 1e-6);
+        assert_eq!(shadowed, Color::default());
+    }
+}
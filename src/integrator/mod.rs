@@ -0,0 +1,466 @@
+mod ambient_occlusion;
+mod direct;
+mod normals;
+mod path;
+mod whitted;
+
+pub use ambient_occlusion::AmbientOcclusion;
+pub use direct::DirectLighting;
+pub use normals::Normals;
+pub use path::PathTracer;
+pub use whitted::Whitted;
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, Error};
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::bsdf::{Bsdf, Lambertian};
+use crate::core::{Color, Frame, Point, Ray, Vec3};
+use crate::light::{EnvironmentMap, LightSample, MeshLight, MisHeuristic};
+use crate::scene::{Light, Material};
+
+/// Distance by which rays leaving a surface are moved along its normal, so
+/// they do not hit the surface they start on, relative to the size of the
+/// coordinates of the hit point. See [`Hit::offset`].
+pub const SURFACE_OFFSET: f64 = 1e-6;
+
+/// The closest surface found along a ray.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Hit {
+    /// Distance along the ray.
+    pub t: f64,
+    pub point: Point,
+    /// Unit surface normal.
+    pub normal: Vec3,
+    pub material: Material,
+    /// Light emitted by the surface.
+    pub emission: Color,
+    /// Index of the mesh light the surface belongs to, see
+    /// [`World::mesh_lights`].
+    pub light: Option<usize>,
+}
+
+impl Hit {
+    /// Returns the BSDF of the surface: the principled material when there
+    /// is one, a diffuse surface of the material color otherwise.
+    pub fn bsdf(&self) -> Box<dyn Bsdf> {
+        match &self.material.principled {
+            Some(principled) => Box::new(principled.bsdf()),
+            None => Box::new(Lambertian::new(self.material.color)),
+        }
+    }
+
+    /// Returns the distance rays leaving the surface are moved along the
+    /// normal. The rounding error of the hit point grows with its
+    /// coordinates, so [`SURFACE_OFFSET`] is scaled by the largest of them
+    /// once it exceeds one.
+    pub fn offset(&self) -> f64 {
+        let point = self.point;
+        let scale = point.x().abs().max(point.y().abs()).max(point.z().abs());
+
+        SURFACE_OFFSET * scale.max(1.0)
+    }
+
+    /// Returns the normal flipped to the side of the surface `direction`
+    /// comes from.
+    pub fn facing_normal(&self, direction: &Vec3) -> Vec3 {
+        if self.normal.dot(direction) > 0.0 {
+            -self.normal
+        } else {
+            self.normal
+        }
+    }
+}
+
+/// The scene as seen by an integrator: something rays can be traced
+/// through.
+pub trait World {
+    /// Returns the closest hit along `ray` in front of its origin.
+    fn intersect(&self, ray: &Ray) -> Option<Hit>;
+
+    /// Returns the light arriving along rays that hit nothing, from the
+    /// environment map if there is one.
+    fn background(&self, ray: &Ray) -> Color {
+        self.environment()
+            .map_or_else(Color::default, |environment| {
+                environment.radiance(&ray.direction)
+            })
+    }
+
+    /// Returns the image surrounding the scene, which is sampled as a light
+    /// source.
+    fn environment(&self) -> Option<&EnvironmentMap> {
+        None
+    }
+
+    /// Returns the lights of the scene.
+    fn lights(&self) -> &[Light] {
+        &[]
+    }
+
+    /// Returns the emissive geometry that is sampled as light sources.
+    fn mesh_lights(&self) -> &[MeshLight] {
+        &[]
+    }
+
+    /// Returns whether a surface lies between `point` and `light`.
+    fn is_shadowed(&self, point: Point, light: Point) -> bool {
+        self.is_occluded(&Ray::new(point, light - point), 1.0)
+    }
+
+    /// Returns whether a surface lies along `ray` closer than `distance`,
+    /// measured in multiples of the ray direction.
+    fn is_occluded(&self, ray: &Ray, distance: f64) -> bool {
+        self.intersect(ray).is_some_and(|hit| hit.t < distance)
+    }
+}
+
+/// Estimates the light reflected towards `wo` at `hit` that arrives straight
+/// from the lights, see [`Light::sample_count`] for the number of samples.
+///
+/// `frame` is built around the normal of the hit, `wo` is in that frame.
+/// With `mis` the samples are weighted against BSDF sampling, so the light
+/// found by following the BSDF must be weighted with [`light_weight`].
+pub(crate) fn sample_lights(
+    world: &dyn World,
+    hit: &Hit,
+    frame: &Frame,
+    wo: &Vec3,
+    bsdf: &dyn Bsdf,
+    mis: Option<MisHeuristic>,
+    mut rng: &mut dyn RngCore,
+) -> Color {
+    // Adds the light of one sample, taken among `count` of the light.
+    let contribution = |sample: LightSample, count: f64, delta: bool| {
+        let wi = frame.to_local(&sample.wi);
+        let value = bsdf.eval(wo, &wi);
+        if value == Color::default() {
+            return Color::default();
+        }
+
+        // Stop just short of the sampled point, which may lie on emissive
+        // geometry of the world.
+        let origin = hit.point + hit.normal * hit.offset().copysign(wi.z());
+        let occluded = if sample.distance.is_finite() {
+            let target = hit.point + sample.wi * sample.distance;
+            world.is_occluded(&Ray::new(origin, target - origin), 1.0 - SURFACE_OFFSET)
+        } else {
+            world.is_occluded(&Ray::new(origin, sample.wi), f64::INFINITY)
+        };
+        if occluded {
+            return Color::default();
+        }
+
+        let weight = match mis {
+            Some(heuristic) if !delta => heuristic.weight(count * sample.pdf, bsdf.pdf(wo, &wi)),
+            _ => 1.0,
+        };
+        value * sample.radiance * (wi.z().abs() * weight / (sample.pdf * count))
+    };
+
+    let mut color = Color::default();
+    for light in world.lights() {
+        let count = light.sample_count() as f64;
+        for u in light.sample_offsets(&mut rng) {
+            if let Some(sample) = light.sample(&hit.point, u) {
+                color = color + contribution(sample, count, light.is_delta());
+            }
+        }
+    }
+    for mesh_light in world.mesh_lights() {
+        if let Some(sample) = mesh_light.sample(&hit.point, (rng.gen(), rng.gen())) {
+            color = color + contribution(sample, 1.0, false);
+        }
+    }
+    if let Some(environment) = world.environment() {
+        if let Some(sample) = environment.sample((rng.gen(), rng.gen())) {
+            color = color + contribution(sample, 1.0, false);
+        }
+    }
+
+    color
+}
+
+/// Returns the closest light `ray` hits before the surface at distance
+/// `surface`, with the light it emits back along the ray.
+pub(crate) fn light_hit<'a>(
+    world: &'a dyn World,
+    ray: &Ray,
+    surface: Option<f64>,
+) -> Option<(&'a Light, Color)> {
+    world
+        .lights()
+        .iter()
+        .filter_map(|light| light.intersect(ray).map(|hit| (light, hit)))
+        .filter(|(_, (t, _))| surface.is_none_or(|surface| *t < surface))
+        .min_by(|(_, (a, _)), (_, (b, _))| a.total_cmp(b))
+        .map(|(light, (_, emitted))| (light, emitted))
+}
+
+/// Returns the weight of the emission of `hit`, found by following a BSDF
+/// sample with density `pdf` from `point`, when the mesh light it belongs
+/// to was also sampled there by [`sample_lights`].
+pub(crate) fn emission_weight(
+    world: &dyn World,
+    heuristic: MisHeuristic,
+    hit: &Hit,
+    point: &Point,
+    pdf: f64,
+) -> f64 {
+    match hit.light.and_then(|index| world.mesh_lights().get(index)) {
+        Some(mesh_light) => heuristic.weight(pdf, mesh_light.pdf(point, &hit.point, &hit.normal)),
+        None => 1.0,
+    }
+}
+
+/// Returns the weight of the background seen in `direction`, found by
+/// following a BSDF sample with density `pdf`, when the environment map was
+/// also sampled by [`sample_lights`].
+pub(crate) fn background_weight(
+    world: &dyn World,
+    heuristic: MisHeuristic,
+    direction: &Vec3,
+    pdf: f64,
+) -> f64 {
+    match world.environment() {
+        Some(environment) => heuristic.weight(pdf, environment.pdf(direction)),
+        None => 1.0,
+    }
+}
+
+/// Returns the weight of light found by following a BSDF sample with
+/// density `pdf` from `point`, when the light was also sampled there by
+/// [`sample_lights`].
+pub(crate) fn light_weight(
+    heuristic: MisHeuristic,
+    light: &Light,
+    point: &Point,
+    direction: &Vec3,
+    pdf: f64,
+) -> f64 {
+    let light_pdf = light.pdf(point, &direction.normalize()) * light.sample_count() as f64;
+
+    heuristic.weight(pdf, light_pdf)
+}
+
+/// Computes the light arriving along a camera ray.
+///
+/// Integrators are used through trait objects so the render loop does not
+/// depend on the one selected, see [`IntegratorKind`].
+pub trait Integrator: Send + Sync {
+    fn radiance(&self, world: &dyn World, ray: &Ray, rng: &mut dyn RngCore) -> Color;
+}
+
+/// The integrators that can be selected by name.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegratorKind {
+    /// Recursive Phong shading with hard shadows, see [`Whitted`].
+    #[default]
+    Whitted,
+    /// Global illumination, see [`PathTracer`].
+    Path,
+    /// Unlit occlusion of the surroundings, see [`AmbientOcclusion`].
+    AmbientOcclusion,
+    /// Surface normals as colors, see [`Normals`].
+    Normals,
+    /// Light arriving straight from the light sources, see
+    /// [`DirectLighting`].
+    Direct,
+}
+
+impl IntegratorKind {
+    pub const ALL: [IntegratorKind; 5] = [
+        IntegratorKind::Whitted,
+        IntegratorKind::Path,
+        IntegratorKind::AmbientOcclusion,
+        IntegratorKind::Normals,
+        IntegratorKind::Direct,
+    ];
+
+    /// Returns the name used to select the integrator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::integrator::IntegratorKind;
+    ///
+    /// assert_eq!(IntegratorKind::AmbientOcclusion.name(), "ambient_occlusion");
+    /// assert_eq!("path".parse::<IntegratorKind>().unwrap(), IntegratorKind::Path);
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntegratorKind::Whitted => "whitted",
+            IntegratorKind::Path => "path",
+            IntegratorKind::AmbientOcclusion => "ambient_occlusion",
+            IntegratorKind::Normals => "normals",
+            IntegratorKind::Direct => "direct",
+        }
+    }
+
+    /// Creates the integrator with default settings, following rays for at
+    /// most `max_depth` surfaces where that applies.
+    pub fn build(&self, max_depth: usize) -> Box<dyn Integrator> {
+        match self {
+            IntegratorKind::Whitted => Box::new(Whitted { max_depth }),
+            IntegratorKind::Path => Box::new(PathTracer {
+                max_depth,
+                ..Default::default()
+            }),
+            IntegratorKind::AmbientOcclusion => Box::new(AmbientOcclusion::default()),
+            IntegratorKind::Normals => Box::new(Normals),
+            IntegratorKind::Direct => Box::new(DirectLighting),
+        }
+    }
+}
+
+impl fmt::Display for IntegratorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for IntegratorKind {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        IntegratorKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = IntegratorKind::ALL.iter().map(|kind| kind.name()).collect();
+                anyhow!(
+                    "Unknown integrator {:?}, expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_worlds {
+    use super::*;
+
+    /// A floor at y = 0 below a point light, with an optional blocker at
+    /// y = 1 covering x < 0.
+    pub(crate) struct Floor {
+        pub material: Material,
+        pub lights: Vec<Light>,
+        pub blocker: bool,
+        pub background: Color,
+    }
+
+    impl Floor {
+        pub(crate) fn new() -> Floor {
+            Floor {
+                material: Material {
+                    color: Color::new(1.0, 1.0, 1.0),
+                    ..Default::default()
+                },
+                lights: vec![Light {
+                    position: Point::new(0.0, 2.0, 0.0),
+                    intensity: Color::new(1.0, 1.0, 1.0),
+                    ..Default::default()
+                }],
+                blocker: false,
+                background: Color::default(),
+            }
+        }
+
+        fn plane(&self, ray: &Ray, height: f64) -> Option<Hit> {
+            let t = (height - ray.origin.y()) / ray.direction.y();
+            if !(t > 0.0 && t.is_finite()) {
+                return None;
+            }
+
+            Some(Hit {
+                t,
+                point: ray.position(t),
+                normal: Vec3::new(0.0, 1.0, 0.0),
+                material: self.material,
+                emission: Color::default(),
+                light: None,
+            })
+        }
+    }
+
+    impl World for Floor {
+        fn intersect(&self, ray: &Ray) -> Option<Hit> {
+            let blocker = self
+                .plane(ray, 1.0)
+                .filter(|hit| self.blocker && hit.point.x() < 0.0);
+
+            blocker.or_else(|| self.plane(ray, 0.0))
+        }
+
+        fn background(&self, _ray: &Ray) -> Color {
+            self.background
+        }
+
+        fn lights(&self) -> &[Light] {
+            &self.lights
+        }
+    }
+
+    pub(crate) fn down_at(x: f64) -> Ray {
+        Ray::new(Point::new(x, 0.5, 0.0), Vec3::new(0.0, -1.0, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_worlds::*;
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_kind_names_round_trip() {
+        for kind in IntegratorKind::ALL {
+            assert_eq!(kind.name().parse::<IntegratorKind>().unwrap(), kind);
+        }
+        assert!("photon_mapping".parse::<IntegratorKind>().is_err());
+    }
+
+    #[test]
+    fn test_offset_scales_with_the_hit_point() {
+        let hit = |point| Hit {
+            t: 1.0,
+            point,
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            material: Material::default(),
+            emission: Color::default(),
+            light: None,
+        };
+
+        assert_eq!(hit(Point::new(0.5, 0.0, -0.2)).offset(), SURFACE_OFFSET);
+        assert_eq!(
+            hit(Point::new(1.0, -4e6, 2.0)).offset(),
+            SURFACE_OFFSET * 4e6
+        );
+    }
+
+    #[test]
+    fn test_is_shadowed() {
+        let mut world = Floor::new();
+        world.blocker = true;
+
+        assert!(world.is_shadowed(Point::new(-1.0, 0.0, 0.0), Point::new(-1.0, 2.0, 0.0)));
+        assert!(!world.is_shadowed(Point::new(1.0, 0.0, 0.0), Point::new(1.0, 2.0, 0.0)));
+    }
+
+    #[test]
+    fn test_every_kind_renders_misses_without_panicking() {
+        let world = Floor::new();
+        let ray = Ray::new(Point::new(0.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+
+        for kind in IntegratorKind::ALL {
+            let color = kind
+                .build(5)
+                .radiance(&world, &ray, &mut StdRng::seed_from_u64(0));
+            assert!(color.r().is_finite());
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use rand::RngCore;
+
+use super::{Integrator, World};
+use crate::core::{Color, Ray};
+
+/// Shows the world space surface normal, mapping each component from
+/// `[-1, 1]` to `[0, 1]`. Rays that miss the scene are black.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Normals;
+
+impl Integrator for Normals {
+    fn radiance(&self, world: &dyn World, ray: &Ray, _rng: &mut dyn RngCore) -> Color {
+        match world.intersect(ray) {
+            Some(hit) => Color::new(
+                (hit.normal.x() + 1.0) / 2.0,
+                (hit.normal.y() + 1.0) / 2.0,
+                (hit.normal.z() + 1.0) / 2.0,
+            ),
+            None => Color::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrator::test_worlds::{down_at, Floor};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_floor_normal() {
+        let color = Normals.radiance(&Floor::new(), &down_at(0.0), &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(color, Color::new(0.5, 1.0, 0.5));
+    }
+}
@@ -0,0 +1,530 @@
+use rand::{Rng, RngCore};
+
+use super::{
+    background_weight, emission_weight, light_hit, light_weight, sample_lights, Integrator, World,
+};
+use crate::core::{Color, Frame, Point, Ray};
+use crate::light::MisHeuristic;
+
+/// Estimates global illumination by following random paths from the camera.
+///
+/// At every surface the path continues in a direction sampled from the
+/// surface's BSDF, see [`Hit::bsdf`](super::Hit::bsdf); for diffuse
+/// surfaces the weight of a bounce is simply the material color. After
+/// `roulette_depth` bounces paths are terminated at random with a
+/// probability that grows as their throughput falls, surviving paths are
+/// weighted up to keep the estimate unbiased.
+///
+/// With `next_event_estimation` the lights are also sampled at every
+/// surface. Light found both ways is weighted with `heuristic`, so small
+/// lights are found by sampling them and glossy reflections of large lights
+/// by following the BSDF, without counting either twice. Point lights can
+/// only be found by sampling them. Emissive surfaces are sampled when they
+/// belong to one of the [`World::mesh_lights`].
+///
+/// # Examples
+///
+/// ```
+/// use rand::{rngs::StdRng, SeedableRng};
+/// use raytracing::core::{Color, Point, Ray, Vec3};
+/// use raytracing::integrator::{Hit, Integrator, PathTracer, World};
+///
+/// struct Sky;
+///
+/// impl World for Sky {
+///     fn intersect(&self, _ray: &Ray) -> Option<Hit> {
+///         None
+///     }
+///
+///     fn background(&self, _ray: &Ray) -> Color {
+///         Color::new(0.5, 0.7, 1.0)
+///     }
+/// }
+///
+/// let ray = Ray::new(Point::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+/// let color = PathTracer::default().radiance(&Sky, &ray, &mut StdRng::seed_from_u64(0));
+///
+/// assert_eq!(color, Color::new(0.5, 0.7, 1.0));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PathTracer {
+    /// Maximum number of surfaces on a path.
+    pub max_depth: usize,
+    /// Number of surfaces after which Russian roulette starts.
+    pub roulette_depth: usize,
+    /// Whether lights are sampled at every surface.
+    pub next_event_estimation: bool,
+    /// How light sampling and BSDF sampling are combined.
+    pub heuristic: MisHeuristic,
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        PathTracer {
+            max_depth: 16,
+            roulette_depth: 3,
+            next_event_estimation: true,
+            heuristic: MisHeuristic::default(),
+        }
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, world: &dyn World, ray: &Ray, rng: &mut dyn RngCore) -> Color {
+        let mut radiance = Color::default();
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = *ray;
+        // The vertex the ray leaves from, unless it is the camera or a
+        // perfectly specular surface, with the density of its direction.
+        let mut previous: Option<(Point, f64)> = None;
+
+        for depth in 0..self.max_depth {
+            let hit = world.intersect(&ray);
+            if let Some((light, emitted)) = light_hit(world, &ray, hit.map(|hit| hit.t)) {
+                let weight = match previous {
+                    Some((point, pdf)) if self.next_event_estimation => {
+                        light_weight(self.heuristic, light, &point, &ray.direction, pdf)
+                    }
+                    _ => 1.0,
+                };
+                radiance = radiance + throughput * emitted * weight;
+                break;
+            }
+            let Some(hit) = hit else {
+                let weight = match previous {
+                    Some((_, pdf)) if self.next_event_estimation => {
+                        background_weight(world, self.heuristic, &ray.direction, pdf)
+                    }
+                    _ => 1.0,
+                };
+                radiance = radiance + throughput * world.background(&ray) * weight;
+                break;
+            };
+            let weight = match previous {
+                Some((point, pdf)) if self.next_event_estimation => {
+                    emission_weight(world, self.heuristic, &hit, &point, pdf)
+                }
+                _ => 1.0,
+            };
+            radiance = radiance + throughput * hit.emission * weight;
+
+            let frame = Frame::from_normal(hit.normal);
+            let wo = frame.to_local(&-ray.direction.normalize());
+            let bsdf = hit.bsdf();
+            if self.next_event_estimation {
+                let direct = sample_lights(
+                    world,
+                    &hit,
+                    &frame,
+                    &wo,
+                    bsdf.as_ref(),
+                    Some(self.heuristic),
+                    rng,
+                );
+                radiance = radiance + throughput * direct;
+            }
+
+            let Some(sample) = bsdf.sample(&wo, rng.gen(), (rng.gen(), rng.gen())) else {
+                break;
+            };
+            previous = (!sample.specular).then_some((hit.point, sample.pdf));
+            throughput = throughput * sample.weight();
+            if depth + 1 >= self.roulette_depth {
+                let survival = survival_probability(&throughput);
+                if rng.gen::<f64>() >= survival {
+                    break;
+                }
+                throughput = throughput * (1.0 / survival);
+            }
+
+            // Leave on the side of the surface the path continues on.
+            let offset = hit.offset().copysign(sample.wi.z());
+            ray = Ray::new(hit.point + hit.normal * offset, frame.to_world(&sample.wi));
+        }
+
+        radiance
+    }
+}
+
+/// Returns the probability of a path with the given throughput surviving
+/// Russian roulette.
+pub(crate) fn survival_probability(throughput: &Color) -> f64 {
+    throughput
+        .r()
+        .max(throughput.g())
+        .max(throughput.b())
+        .clamp(0.05, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Canvas;
+    use crate::core::{Point, Vec3};
+    use crate::integrator::test_worlds::{down_at, Floor};
+    use crate::integrator::{DirectLighting, Hit};
+    use crate::light::{EnvironmentMap, MeshLight};
+    use crate::scene::{LightKind, Material};
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::f64::consts::PI;
+
+    /// The inside of a sphere of radius 1 around the origin, with the same
+    /// material everywhere.
+    struct Furnace {
+        albedo: Color,
+        emission: Color,
+    }
+
+    impl World for Furnace {
+        fn intersect(&self, ray: &Ray) -> Option<Hit> {
+            let origin = ray.origin - Point::new(0.0, 0.0, 0.0);
+            let a = ray.direction.dot(&ray.direction);
+            let b = origin.dot(&ray.direction);
+            let c = origin.dot(&origin) - 1.0;
+            let t = (-b + (b * b - a * c).sqrt()) / a;
+            let point = ray.position(t);
+
+            Some(Hit {
+                t,
+                point,
+                normal: point - Point::new(0.0, 0.0, 0.0),
+                material: Material {
+                    color: self.albedo,
+                    ..Default::default()
+                },
+                emission: self.emission,
+                light: None,
+            })
+        }
+    }
+
+    fn mean_radiance(tracer: &PathTracer, world: &impl World, samples: usize) -> Color {
+        let mut rng = StdRng::seed_from_u64(1);
+        let ray = Ray::new(Point::new(0.0, 0.0, 0.0), Vec3::new(0.3, -0.2, 1.0));
+        let sum = (0..samples).fold(Color::default(), |sum, _| {
+            sum + tracer.radiance(world, &ray, &mut rng)
+        });
+
+        sum * (1.0 / samples as f64)
+    }
+
+    #[test]
+    fn test_furnace_converges_to_geometric_series() {
+        let furnace = Furnace {
+            albedo: Color::new(0.5, 0.5, 0.5),
+            emission: Color::new(1.0, 1.0, 1.0),
+        };
+        let tracer = PathTracer {
+            max_depth: 100,
+            roulette_depth: 2,
+            ..Default::default()
+        };
+
+        let radiance = mean_radiance(&tracer, &furnace, 20_000);
+
+        // 1 + 0.5 + 0.25 + ... = 2
+        assert_abs_diff_eq!(radiance.r(), 2.0, epsilon = 0.05);
+    }
+
+    #[test]
+    fn test_max_depth_limits_bounces() {
+        let furnace = Furnace {
+            albedo: Color::new(1.0, 1.0, 1.0),
+            emission: Color::new(1.0, 0.0, 0.0),
+        };
+        let tracer = PathTracer {
+            max_depth: 3,
+            roulette_depth: 3,
+            ..Default::default()
+        };
+
+        assert_abs_diff_eq!(
+            mean_radiance(&tracer, &furnace, 10),
+            Color::new(3.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_glass_furnace_is_lossless() {
+        // Clear glass neither absorbs nor emits, so the emission of the
+        // walls is seen unchanged through it.
+        struct GlassBall;
+
+        impl World for GlassBall {
+            fn intersect(&self, ray: &Ray) -> Option<Hit> {
+                let origin = ray.origin - Point::new(0.0, 0.0, 0.0);
+                let a = ray.direction.dot(&ray.direction);
+                let b = origin.dot(&ray.direction);
+                let c = origin.dot(&origin) - 1.0;
+                let root = (b * b - a * c).sqrt();
+                let t = [(-b - root) / a, (-b + root) / a]
+                    .into_iter()
+                    .find(|&t| t > 1e-9)?;
+                let point = ray.position(t);
+
+                Some(Hit {
+                    t,
+                    point,
+                    normal: point - Point::new(0.0, 0.0, 0.0),
+                    material: Material {
+                        principled: Some(crate::bsdf::Principled {
+                            base_color: Color::new(1.0, 1.0, 1.0),
+                            transmission: 1.0,
+                            roughness: 0.0,
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    },
+                    emission: Color::default(),
+                    light: None,
+                })
+            }
+
+            fn background(&self, _ray: &Ray) -> Color {
+                Color::new(1.0, 1.0, 1.0)
+            }
+        }
+
+        let tracer = PathTracer {
+            max_depth: 64,
+            roulette_depth: 64,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(2);
+        let ray = Ray::new(Point::new(0.0, 0.0, -3.0), Vec3::new(0.1, 0.2, 1.0));
+        let samples = 2000;
+        let mean = (0..samples)
+            .map(|_| tracer.radiance(&GlassBall, &ray, &mut rng).r())
+            .sum::<f64>()
+            / samples as f64;
+
+        assert_abs_diff_eq!(mean, 1.0, epsilon = 0.02);
+    }
+
+    #[test]
+    fn test_next_event_estimation_finds_point_lights() {
+        let mut world = Floor::new();
+        world.blocker = true;
+        let tracer = PathTracer {
+            max_depth: 1,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let lit = tracer.radiance(&world, &down_at(0.0), &mut rng);
+        let shadowed = tracer.radiance(&world, &down_at(-1.0), &mut rng);
+        let unsampled = PathTracer {
+            next_event_estimation: false,
+            ..tracer
+        }
+        .radiance(&world, &down_at(0.0), &mut rng);
+
+        // Matches the direct lighting of the diffuse floor.
+        assert_abs_diff_eq!(lit.r(), 1.0 / (4.0 * PI), epsilon = 1e-6);
+        assert_eq!(shadowed, Color::default());
+        assert_eq!(unsampled, Color::default());
+    }
+
+    #[test]
+    fn test_light_and_bsdf_sampling_agree_on_area_lights() {
+        let mut world = Floor::new();
+        world.lights[0].kind = LightKind::Rect {
+            u: Vec3::new(2.0, 0.0, 0.0),
+            v: Vec3::new(0.0, 0.0, 2.0),
+        };
+        world.lights[0].samples = 4;
+        let mean = |tracer: PathTracer| {
+            let mut rng = StdRng::seed_from_u64(6);
+            let samples = 20_000;
+            (0..samples)
+                .map(|_| tracer.radiance(&world, &down_at(0.5), &mut rng).r())
+                .sum::<f64>()
+                / samples as f64
+        };
+
+        // The floor sees the light after one bounce, and nothing after
+        // that.
+        let tracer = PathTracer {
+            max_depth: 2,
+            ..Default::default()
+        };
+        let unsampled = mean(PathTracer {
+            next_event_estimation: false,
+            ..tracer
+        });
+        let power = mean(tracer);
+        let balance = mean(PathTracer {
+            heuristic: MisHeuristic::Balance,
+            ..tracer
+        });
+
+        assert_abs_diff_eq!(power, unsampled, epsilon = 0.02);
+        assert_abs_diff_eq!(balance, unsampled, epsilon = 0.02);
+    }
+
+    /// A white floor at y = 0 under an emissive 2 by 2 panel at y = 2,
+    /// which is also sampled as a mesh light.
+    struct PanelRoom {
+        mesh_lights: Vec<MeshLight>,
+    }
+
+    impl PanelRoom {
+        fn new() -> PanelRoom {
+            let corner = |x, z| Point::new(x, 2.0, z);
+            let (a, b, c, d) = (
+                corner(-1.0, -1.0),
+                corner(1.0, -1.0),
+                corner(1.0, 1.0),
+                corner(-1.0, 1.0),
+            );
+
+            PanelRoom {
+                mesh_lights: vec![MeshLight::new(
+                    vec![[a, b, c], [a, c, d]],
+                    Color::new(1.0, 1.0, 1.0),
+                )],
+            }
+        }
+    }
+
+    impl World for PanelRoom {
+        fn intersect(&self, ray: &Ray) -> Option<Hit> {
+            let plane = |height: f64| {
+                let t = (height - ray.origin.y()) / ray.direction.y();
+                (t > 1e-9 && t.is_finite()).then_some(t)
+            };
+            let panel = plane(2.0).filter(|&t| {
+                let point = ray.position(t);
+                point.x().abs() <= 1.0 && point.z().abs() <= 1.0
+            });
+
+            let (t, emission, light) = match (panel, plane(0.0)) {
+                (Some(t), floor) if floor.is_none_or(|floor| t < floor) => {
+                    (t, Color::new(1.0, 1.0, 1.0), Some(0))
+                }
+                (_, Some(t)) => (t, Color::default(), None),
+                _ => return None,
+            };
+            Some(Hit {
+                t,
+                point: ray.position(t),
+                normal: Vec3::new(0.0, 1.0, 0.0),
+                material: Material::default(),
+                emission,
+                light,
+            })
+        }
+
+        fn mesh_lights(&self) -> &[MeshLight] {
+            &self.mesh_lights
+        }
+    }
+
+    #[test]
+    fn test_mesh_lights_are_sampled_without_bias() {
+        let world = PanelRoom::new();
+        let mean = |integrator: &dyn Integrator| {
+            let mut rng = StdRng::seed_from_u64(7);
+            let samples = 20_000;
+            (0..samples)
+                .map(|_| integrator.radiance(&world, &down_at(0.5), &mut rng).r())
+                .sum::<f64>()
+                / samples as f64
+        };
+
+        let tracer = PathTracer {
+            max_depth: 2,
+            ..Default::default()
+        };
+        let sampled = mean(&tracer);
+        let unsampled = mean(&PathTracer {
+            next_event_estimation: false,
+            ..tracer
+        });
+        let direct = mean(&DirectLighting);
+
+        assert!(sampled > 0.1);
+        assert_abs_diff_eq!(sampled, unsampled, epsilon = 0.02);
+        assert_abs_diff_eq!(sampled, direct, epsilon = 0.02);
+    }
+
+    /// A white floor at y = 0 under an environment map.
+    struct Outdoors {
+        environment: EnvironmentMap,
+    }
+
+    impl Outdoors {
+        /// An even sky of the given brightness, with one bright pixel above
+        /// the horizon.
+        fn new(sky: f64, sun: f64) -> Outdoors {
+            let mut canvas = Canvas::new(8, 4);
+            for y in 0..4 {
+                for x in 0..8 {
+                    canvas[(x, y)] = Color::new(sky, sky, sky);
+                }
+            }
+            canvas[(2, 1)] = Color::new(sun, sun, sun);
+
+            Outdoors {
+                environment: EnvironmentMap::new(canvas, 1.0),
+            }
+        }
+    }
+
+    impl World for Outdoors {
+        fn intersect(&self, ray: &Ray) -> Option<Hit> {
+            let t = -ray.origin.y() / ray.direction.y();
+            (t > 0.0 && t.is_finite()).then(|| Hit {
+                t,
+                point: ray.position(t),
+                normal: Vec3::new(0.0, 1.0, 0.0),
+                material: Material::default(),
+                emission: Color::default(),
+                light: None,
+            })
+        }
+
+        fn environment(&self) -> Option<&EnvironmentMap> {
+            Some(&self.environment)
+        }
+    }
+
+    #[test]
+    fn test_environment_lights_the_scene() {
+        let mean = |integrator: &dyn Integrator, world: &Outdoors| {
+            let mut rng = StdRng::seed_from_u64(5);
+            let samples = 20_000;
+            (0..samples)
+                .map(|_| integrator.radiance(world, &down_at(0.0), &mut rng).r())
+                .sum::<f64>()
+                / samples as f64
+        };
+
+        // A white floor reflects all the light of an even sky.
+        let overcast = Outdoors::new(1.0, 1.0);
+        assert_abs_diff_eq!(mean(&PathTracer::default(), &overcast), 1.0, epsilon = 0.01);
+
+        let sunny = Outdoors::new(0.2, 50.0);
+        let sampled = mean(&PathTracer::default(), &sunny);
+        let unsampled = mean(
+            &PathTracer {
+                next_event_estimation: false,
+                ..Default::default()
+            },
+            &sunny,
+        );
+        let direct = mean(&DirectLighting, &sunny);
+
+        assert!(sampled > 1.0);
+        assert_abs_diff_eq!(sampled, unsampled, epsilon = 0.05 * sampled);
+        assert_abs_diff_eq!(sampled, direct, epsilon = 0.05 * sampled);
+    }
+
+    #[test]
+    fn test_survival_probability() {
+        assert_eq!(survival_probability(&Color::new(0.2, 0.6, 0.1)), 0.6);
+        assert_eq!(survival_probability(&Color::new(2.0, 0.0, 0.0)), 1.0);
+        assert_eq!(survival_probability(&Color::default()), 0.05);
+    }
+}
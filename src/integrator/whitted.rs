@@ -0,0 +1,355 @@
+use rand::RngCore;
+
+use super::{light_hit, Hit, Integrator, World};
+use crate::core::{Color, Point, Ray, Vec3};
+use crate::scene::{Light, Material};
+
+/// Recursive ray tracing after Whitted: Phong shading with hard shadows,
+/// plus mirror reflection and refraction.
+///
+/// Area lights are shaded as the average of point lights at stratified
+/// positions on the light, which softens their shadows, and are seen
+/// directly by rays that hit them. Spot lights fade out at the edge of
+/// their cone.
+///
+/// Reflection and refraction are followed for at most `max_depth` levels.
+/// Surfaces that both reflect and refract are blended with Schlick's
+/// approximation of the Fresnel factor. Refraction assumes rays travel
+/// between the material and air, nested transparent objects are not
+/// tracked.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Whitted {
+    pub max_depth: usize,
+}
+
+impl Default for Whitted {
+    fn default() -> Self {
+        Whitted { max_depth: 5 }
+    }
+}
+
+impl Integrator for Whitted {
+    fn radiance(&self, world: &dyn World, ray: &Ray, rng: &mut dyn RngCore) -> Color {
+        color_at(world, ray, self.max_depth, rng)
+    }
+}
+
+fn color_at(world: &dyn World, ray: &Ray, remaining: usize, rng: &mut dyn RngCore) -> Color {
+    let hit = world.intersect(ray);
+    if let Some((_, emitted)) = light_hit(world, ray, hit.map(|hit| hit.t)) {
+        return emitted;
+    }
+
+    match hit {
+        Some(hit) => shade_hit(world, ray, &hit, remaining, rng),
+        None => world.background(ray),
+    }
+}
+
+fn shade_hit(
+    world: &dyn World,
+    ray: &Ray,
+    hit: &Hit,
+    remaining: usize,
+    rng: &mut dyn RngCore,
+) -> Color {
+    let material = &hit.material;
+    let direction = ray.direction.normalize();
+    let eye = -direction;
+    let normal = hit.facing_normal(&direction);
+    let inside = hit.normal.dot(&direction) > 0.0;
+    let over_point = hit.point + normal * hit.offset();
+    let under_point = hit.point - normal * hit.offset();
+
+    let mut surface = hit.emission;
+    for light in world.lights() {
+        surface =
+            surface + light_contribution(world, material, light, over_point, eye, normal, rng);
+    }
+    if remaining == 0 {
+        return surface;
+    }
+
+    let reflected = if material.reflective > 0.0 {
+        let ray = Ray::new(over_point, direction.reflect(&normal));
+        color_at(world, &ray, remaining - 1, rng) * material.reflective
+    } else {
+        Color::default()
+    };
+
+    let (n1, n2) = if inside {
+        (material.refractive_index, 1.0)
+    } else {
+        (1.0, material.refractive_index)
+    };
+    let cos_i = eye.dot(&normal);
+    let refracted = if material.transparency > 0.0 {
+        match refract(eye, normal, n1 / n2) {
+            Some(direction) => {
+                let ray = Ray::new(under_point, direction);
+                color_at(world, &ray, remaining - 1, rng) * material.transparency
+            }
+            None => Color::default(),
+        }
+    } else {
+        Color::default()
+    };
+
+    if material.reflective > 0.0 && material.transparency > 0.0 {
+        let reflectance = schlick(cos_i, n1, n2);
+        surface + reflected * reflectance + refracted * (1.0 - reflectance)
+    } else {
+        surface + reflected + refracted
+    }
+}
+
+/// Phong reflection of a light, averaged over positions on area lights.
+///
+/// Lights do not fall off with distance, as in the Phong model, unless the
+/// scene specifies a falloff, see [`Light::specified_falloff`].
+fn light_contribution(
+    world: &dyn World,
+    material: &Material,
+    light: &Light,
+    point: Point,
+    eye: Vec3,
+    normal: Vec3,
+    mut rng: &mut dyn RngCore,
+) -> Color {
+    let offsets = light.sample_offsets(&mut rng);
+    let count = offsets.len() as f64;
+    let sum = offsets
+        .into_iter()
+        .filter_map(|u| light.sample(&point, u))
+        .fold(Color::default(), |sum, sample| {
+            let shadowed = world.is_occluded(&Ray::new(point, sample.wi), sample.distance);
+            let falloff = light.specified_falloff(sample.distance).unwrap_or(1.0);
+            let intensity = light.emitted(&sample.wi) * falloff;
+            sum + lighting(material, intensity, sample.wi, eye, normal, shadowed)
+        });
+
+    sum * (1.0 / count)
+}
+
+/// Phong reflection of light of the given intensity arriving from the unit
+/// direction `light_direction`.
+fn lighting(
+    material: &Material,
+    intensity: Color,
+    light_direction: Vec3,
+    eye: Vec3,
+    normal: Vec3,
+    shadowed: bool,
+) -> Color {
+    let effective_color = material.color * intensity;
+    let ambient = effective_color * material.ambient;
+    if shadowed {
+        return ambient;
+    }
+
+    let light_dot_normal = light_direction.dot(&normal);
+    if light_dot_normal < 0.0 {
+        return ambient;
+    }
+    let diffuse = effective_color * (material.diffuse * light_dot_normal);
+
+    let reflect_dot_eye = (-light_direction).reflect(&normal).dot(&eye);
+    let specular = if reflect_dot_eye > 0.0 {
+        intensity * (material.specular * reflect_dot_eye.powf(material.shininess))
+    } else {
+        Color::default()
+    };
+
+    ambient + diffuse + specular
+}
+
+/// Returns the direction of a ray refracted by Snell's law, or `None` on
+/// total internal reflection. `eye` points away from the surface on the
+/// side of `normal`, `ratio` is the refractive index on that side divided
+/// by the one on the other side.
+fn refract(eye: Vec3, normal: Vec3, ratio: f64) -> Option<Vec3> {
+    let cos_i = eye.dot(&normal);
+    let sin2_t = ratio * ratio * (1.0 - cos_i * cos_i);
+    if sin2_t > 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+
+    Some(normal * (ratio * cos_i - cos_t) - eye * ratio)
+}
+
+/// Schlick's approximation of the fraction of light reflected at an
+/// interface between refractive indices `n1` and `n2`.
+fn schlick(cos_i: f64, n1: f64, n2: f64) -> f64 {
+    let mut cos = cos_i;
+    if n1 > n2 {
+        let ratio = n1 / n2;
+        let sin2_t = ratio * ratio * (1.0 - cos * cos);
+        if sin2_t > 1.0 {
+            return 1.0;
+        }
+        cos = (1.0 - sin2_t).sqrt();
+    }
+
+    let r0 = ((n1 - n2) / (n1 + n2)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrator::test_worlds::{down_at, Floor};
+    use crate::scene::{Attenuation, LightKind};
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn render(world: &Floor, ray: &Ray) -> Color {
+        Whitted::default().radiance(world, ray, &mut StdRng::seed_from_u64(0))
+    }
+
+    #[test]
+    fn test_lighting_eye_between_light_and_surface() {
+        let color = render(&Floor::new(), &down_at(0.0));
+
+        assert_abs_diff_eq!(color, Color::new(1.9, 1.9, 1.9), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_lighting_in_shadow() {
+        let mut world = Floor::new();
+        world.blocker = true;
+
+        let color = render(&world, &down_at(-1.0));
+
+        assert_abs_diff_eq!(color, Color::new(0.1, 0.1, 0.1), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_reflection_adds_background() {
+        let mut world = Floor::new();
+        world.material.reflective = 0.5;
+        world.background = Color::new(0.0, 0.0, 1.0);
+        world.lights.clear();
+
+        let color = render(&world, &down_at(3.0));
+
+        assert_abs_diff_eq!(color, Color::new(0.0, 0.0, 0.5), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_refraction_passes_through() {
+        let mut world = Floor::new();
+        world.material.transparency = 1.0;
+        world.material.refractive_index = 1.5;
+        world.background = Color::new(0.0, 1.0, 0.0);
+        world.lights.clear();
+
+        let color = render(&world, &down_at(3.0));
+
+        assert_abs_diff_eq!(color, Color::new(0.0, 1.0, 0.0), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_area_light_penumbra() {
+        let mut world = Floor::new();
+        world.blocker = true;
+        world.lights[0].kind = LightKind::Rect {
+            u: Vec3::new(2.0, 0.0, 0.0),
+            v: Vec3::new(0.0, 0.0, 1.0),
+        };
+        world.lights[0].samples = 16;
+
+        let penumbra = render(&world, &down_at(0.0)).r();
+        let umbra = render(&world, &down_at(-3.0)).r();
+        world.blocker = false;
+        let lit = render(&world, &down_at(0.0)).r();
+
+        assert!(
+            umbra < penumbra && penumbra < lit,
+            "{} {} {}",
+            umbra,
+            penumbra,
+            lit
+        );
+        assert_abs_diff_eq!(umbra, 0.1, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_spot_and_directional_lights() {
+        let mut world = Floor::new();
+        world.lights[0].kind = LightKind::Spot {
+            direction: Vec3::new(0.0, -1.0, 0.0),
+            inner_angle: 0.3,
+            outer_angle: 0.5,
+        };
+
+        assert_abs_diff_eq!(
+            render(&world, &down_at(0.0)),
+            Color::new(1.9, 1.9, 1.9),
+            epsilon = 1e-6
+        );
+        // Outside the cone the light adds nothing, not even ambient light.
+        assert_abs_diff_eq!(
+            render(&world, &down_at(3.0)),
+            Color::default(),
+            epsilon = 1e-6
+        );
+
+        world.blocker = true;
+        world.lights[0].kind = LightKind::Directional {
+            direction: Vec3::new(0.0, -1.0, 0.0),
+        };
+
+        assert_abs_diff_eq!(
+            render(&world, &down_at(30.0)),
+            Color::new(1.9, 1.9, 1.9),
+            epsilon = 1e-6
+        );
+        assert_abs_diff_eq!(
+            render(&world, &down_at(-30.0)),
+            Color::new(0.1, 0.1, 0.1),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_specified_falloff() {
+        let mut world = Floor::new();
+        world.lights[0].attenuation = Some(Attenuation {
+            constant: 0.0,
+            linear: 0.0,
+            quadratic: 1.0,
+        });
+
+        // The light is 2 units above the floor.
+        assert_abs_diff_eq!(
+            render(&world, &down_at(0.0)),
+            Color::new(0.475, 0.475, 0.475),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_area_lights_are_visible() {
+        let mut world = Floor::new();
+        world.lights[0].kind = LightKind::Sphere { radius: 0.5 };
+        world.lights[0].intensity = Color::new(3.0, 2.0, 1.0);
+        let up = Ray::new(Point::new(0.0, 0.5, 0.0), Vec3::new(0.0, 1.0, 0.0));
+
+        assert_eq!(render(&world, &up), Color::new(3.0, 2.0, 1.0));
+    }
+
+    #[test]
+    fn test_schlick() {
+        let half = 2.0_f64.sqrt() / 2.0;
+        assert_eq!(schlick(half, 1.5, 1.0), 1.0);
+        assert_abs_diff_eq!(schlick(1.0, 1.0, 1.5), 0.04, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_refract_straight_through() {
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+
+        assert_eq!(refract(normal, normal, 1.0 / 1.5), Some(-normal));
+    }
+}
@@ -1,2 +1,8 @@
+pub mod bsdf;
 pub mod core;
+pub mod geometry;
+pub mod integrator;
+pub mod light;
 pub mod output;
+pub mod render;
+pub mod scene;
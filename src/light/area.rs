@@ -0,0 +1,206 @@
+use std::f64::consts::PI;
+
+use crate::core::{Frame, Point, Ray, Vec3};
+use crate::render::concentric_sample_disk;
+
+/// A point picked uniformly on the surface of a light.
+pub(super) struct AreaSample {
+    pub point: Point,
+    pub normal: Vec3,
+    /// Area of the whole light.
+    pub area: f64,
+}
+
+pub(super) fn sample_rect(center: &Point, u: &Vec3, v: &Vec3, (a, b): (f64, f64)) -> AreaSample {
+    AreaSample {
+        point: *center + *u * (a - 0.5) + *v * (b - 0.5),
+        normal: u.cross(v).normalize(),
+        area: rect_area(u, v),
+    }
+}
+
+pub(super) fn rect_area(u: &Vec3, v: &Vec3) -> f64 {
+    let normal = u.cross(v);
+
+    normal.dot(&normal).sqrt()
+}
+
+/// Returns the distance along `ray` to the rectangle, if it hits it.
+pub(super) fn intersect_rect(center: &Point, u: &Vec3, v: &Vec3, ray: &Ray) -> Option<f64> {
+    let normal = u.cross(v);
+    let t = plane_distance(center, &normal, ray)?;
+    let offset = ray.position(t) - *center;
+
+    // Coordinates along the edges, relative to the center.
+    let (uu, uv, vv) = (u.dot(u), u.dot(v), v.dot(v));
+    let (ou, ov) = (offset.dot(u), offset.dot(v));
+    let determinant = uu * vv - uv * uv;
+    let a = (ou * vv - ov * uv) / determinant;
+    let b = (ov * uu - ou * uv) / determinant;
+
+    (a.abs() <= 0.5 && b.abs() <= 0.5).then_some(t)
+}
+
+pub(super) fn sample_disk(
+    center: &Point,
+    normal: &Vec3,
+    radius: f64,
+    (a, b): (f64, f64),
+) -> AreaSample {
+    let frame = Frame::from_normal(*normal);
+    let (x, y) = concentric_sample_disk(a, b);
+
+    AreaSample {
+        point: *center + (frame.s * x + frame.t * y) * radius,
+        normal: frame.n,
+        area: PI * radius * radius,
+    }
+}
+
+pub(super) fn intersect_disk(center: &Point, normal: &Vec3, radius: f64, ray: &Ray) -> Option<f64> {
+    let t = plane_distance(center, normal, ray)?;
+    let offset = ray.position(t) - *center;
+
+    (offset.dot(&offset) <= radius * radius).then_some(t)
+}
+
+/// Returns the distance along `ray` to the plane through `point`, if the
+/// ray hits it in front of its origin.
+fn plane_distance(point: &Point, normal: &Vec3, ray: &Ray) -> Option<f64> {
+    let t = (*point - ray.origin).dot(normal) / ray.direction.dot(normal);
+
+    (t > 0.0 && t.is_finite()).then_some(t)
+}
+
+pub(super) fn sample_sphere_surface(center: &Point, radius: f64, (a, b): (f64, f64)) -> AreaSample {
+    let z = 1.0 - 2.0 * a;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * b;
+    let normal = Vec3::new(r * phi.cos(), r * phi.sin(), z);
+
+    AreaSample {
+        point: *center + normal * radius,
+        normal,
+        area: 4.0 * PI * radius * radius,
+    }
+}
+
+/// Returns the distance along `ray` to the closest point of the sphere in
+/// front of its origin.
+pub(super) fn intersect_sphere(center: &Point, radius: f64, ray: &Ray) -> Option<f64> {
+    let origin = ray.origin - *center;
+    let a = ray.direction.dot(&ray.direction);
+    let b = origin.dot(&ray.direction);
+    let c = origin.dot(&origin) - radius * radius;
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+    [(-b - root) / a, (-b + root) / a]
+        .into_iter()
+        .find(|&t| t > 0.0)
+}
+
+/// The cone of directions in which a sphere is seen from a point outside
+/// of it.
+pub(super) struct SphereCone {
+    pub frame: Frame,
+    pub distance: f64,
+    pub cos_max: f64,
+}
+
+impl SphereCone {
+    /// Returns the cone, or `None` when `point` is inside the sphere.
+    pub fn new(center: &Point, radius: f64, point: &Point) -> Option<SphereCone> {
+        let axis = *center - *point;
+        let distance = axis.dot(&axis).sqrt();
+        if distance <= radius {
+            return None;
+        }
+
+        let sin_max = radius / distance;
+        Some(SphereCone {
+            frame: Frame::from_normal(axis),
+            distance,
+            cos_max: (1.0 - sin_max * sin_max).max(0.0).sqrt(),
+        })
+    }
+
+    /// Returns the density of directions sampled uniformly in the cone.
+    pub fn pdf(&self) -> f64 {
+        1.0 / (2.0 * PI * (1.0 - self.cos_max))
+    }
+
+    /// Samples a direction uniformly in the cone, returning it with the
+    /// distance to the near side of the sphere.
+    pub fn sample(&self, radius: f64, (a, b): (f64, f64)) -> (Vec3, f64) {
+        let cos = 1.0 - a + a * self.cos_max;
+        let sin = (1.0 - cos * cos).max(0.0).sqrt();
+        let phi = 2.0 * PI * b;
+        let wi = self
+            .frame
+            .to_world(&Vec3::new(sin * phi.cos(), sin * phi.sin(), cos));
+
+        let distance = self.distance * cos
+            - (radius * radius - self.distance * self.distance * sin * sin)
+                .max(0.0)
+                .sqrt();
+        (wi, distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_rect_samples_hit_the_rect() {
+        let center = Point::new(0.0, 2.0, 0.0);
+        let (u, v) = (Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+
+        for uv in [(0.0, 0.0), (0.3, 0.9), (0.99, 0.5)] {
+            let sample = sample_rect(&center, &u, &v, uv);
+            let ray = Ray::new(
+                Point::new(0.1, 0.0, 0.2),
+                sample.point - Point::new(0.1, 0.0, 0.2),
+            );
+
+            assert_abs_diff_eq!(
+                intersect_rect(&center, &u, &v, &ray).unwrap(),
+                1.0,
+                epsilon = 1e-9
+            );
+            assert_eq!(sample.area, 2.0);
+        }
+        let outside = Ray::new(Point::new(1.5, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(intersect_rect(&center, &u, &v, &outside), None);
+    }
+
+    #[test]
+    fn test_disk_intersection() {
+        let center = Point::new(0.0, 0.0, 3.0);
+        let normal = Vec3::new(0.0, 0.0, -1.0);
+        let ray = |x| Ray::new(Point::new(x, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+
+        assert_eq!(intersect_disk(&center, &normal, 0.5, &ray(0.4)), Some(3.0));
+        assert_eq!(intersect_disk(&center, &normal, 0.5, &ray(0.6)), None);
+    }
+
+    #[test]
+    fn test_sphere_cone_samples_hit_the_sphere() {
+        let center = Point::new(0.0, 0.0, 4.0);
+        let point = Point::new(0.5, 0.0, 0.0);
+        let cone = SphereCone::new(&center, 1.0, &point).unwrap();
+
+        for uv in [(0.0, 0.0), (0.5, 0.25), (0.99, 0.75)] {
+            let (wi, distance) = cone.sample(1.0, uv);
+            let t = intersect_sphere(&center, 1.0, &Ray::new(point, wi)).unwrap();
+
+            assert_abs_diff_eq!(t, distance, epsilon = 1e-6);
+        }
+        assert!(SphereCone::new(&center, 1.0, &center).is_none());
+    }
+}
@@ -0,0 +1,307 @@
+use std::f64::consts::PI;
+
+use crate::core::{Canvas, Color, Vec3};
+
+use super::LightSample;
+
+/// Light arriving from infinitely far away in every direction, given by an
+/// equirectangular image.
+///
+/// The top row of the image is straight up (+y) and the bottom row straight
+/// down, columns go around the y axis starting at +x towards +z. Directions
+/// are sampled in proportion to the luminance of the pixels, weighted by
+/// the solid angle they cover, so small bright areas such as the sun are
+/// found by light sampling.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::{Canvas, Color, Vec3};
+/// use raytracing::light::EnvironmentMap;
+///
+/// let mut canvas = Canvas::new(4, 2);
+/// for x in 0..4 {
+///     canvas[(x, 0)] = Color::new(0.5, 0.7, 1.0);
+/// }
+/// let sky = EnvironmentMap::new(canvas, 2.0);
+///
+/// assert_eq!(sky.radiance(&Vec3::new(0.0, 1.0, 0.0)), Color::new(1.0, 1.4, 2.0));
+/// assert_eq!(sky.radiance(&Vec3::new(0.0, -1.0, 0.0)), Color::default());
+///
+/// // Only the sky is sampled.
+/// let sample = sky.sample((0.3, 0.8)).unwrap();
+/// assert!(sample.wi.y() > 0.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvironmentMap {
+    canvas: Canvas,
+    /// Factor the pixels are scaled by.
+    intensity: f64,
+    /// Distribution of the rows.
+    rows: Distribution,
+    /// Distribution of the columns in every row.
+    columns: Vec<Distribution>,
+}
+
+impl EnvironmentMap {
+    pub fn new(canvas: Canvas, intensity: f64) -> EnvironmentMap {
+        let columns: Vec<_> = (0..canvas.height)
+            .map(|y| {
+                // Rows near the poles cover less solid angle.
+                let sin_theta = (PI * (y as f64 + 0.5) / canvas.height as f64).sin();
+                Distribution::new(
+                    (0..canvas.width)
+                        .map(|x| canvas[(x, y)].luminance().max(0.0) * sin_theta)
+                        .collect(),
+                )
+            })
+            .collect();
+        let rows = Distribution::new(columns.iter().map(|row| row.integral).collect());
+
+        EnvironmentMap {
+            canvas,
+            intensity,
+            rows,
+            columns,
+        }
+    }
+
+    /// Returns the light arriving from `direction`, which does not need to
+    /// be normalized.
+    pub fn radiance(&self, direction: &Vec3) -> Color {
+        let (u, v) = direction_to_uv(&direction.normalize());
+        let (x, y) = self.pixel(u, v);
+
+        self.canvas[(x, y)] * self.intensity
+    }
+
+    /// Samples a direction towards the environment, using the uniform
+    /// random numbers `u`.
+    pub fn sample(&self, (a, b): (f64, f64)) -> Option<LightSample> {
+        if self.canvas.width == 0 || self.canvas.height == 0 {
+            return None;
+        }
+
+        let (v, row_pdf) = self.rows.sample(b);
+        let (_, y) = self.pixel(0.0, v);
+        let (u, column_pdf) = self.columns[y].sample(a);
+
+        let wi = uv_to_direction(u, v);
+        let sin_theta = (PI * v).sin();
+        let pdf = row_pdf * column_pdf / (2.0 * PI * PI * sin_theta);
+        if !(pdf > 0.0 && pdf.is_finite()) {
+            return None;
+        }
+
+        Some(LightSample {
+            wi,
+            distance: f64::INFINITY,
+            radiance: self.radiance(&wi),
+            pdf,
+        })
+    }
+
+    /// Returns the density with which [`EnvironmentMap::sample`] picks
+    /// `direction`, with respect to solid angle.
+    pub fn pdf(&self, direction: &Vec3) -> f64 {
+        if self.canvas.width == 0 || self.canvas.height == 0 {
+            return 0.0;
+        }
+
+        let (u, v) = direction_to_uv(&direction.normalize());
+        let sin_theta = (PI * v).sin();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+
+        let (x, y) = self.pixel(u, v);
+        self.rows.pdf(y) * self.columns[y].pdf(x) / (2.0 * PI * PI * sin_theta)
+    }
+
+    /// Returns the pixel containing the image coordinates `u` and `v`.
+    fn pixel(&self, u: f64, v: f64) -> (usize, usize) {
+        let x = (u * self.canvas.width as f64) as usize;
+        let y = (v * self.canvas.height as f64) as usize;
+
+        (
+            x.min(self.canvas.width.saturating_sub(1)),
+            y.min(self.canvas.height.saturating_sub(1)),
+        )
+    }
+}
+
+/// Maps a unit direction to image coordinates in [0, 1].
+fn direction_to_uv(direction: &Vec3) -> (f64, f64) {
+    let phi = direction.z().atan2(direction.x()).rem_euclid(2.0 * PI);
+    let theta = direction.y().clamp(-1.0, 1.0).acos();
+
+    (phi / (2.0 * PI), theta / PI)
+}
+
+fn uv_to_direction(u: f64, v: f64) -> Vec3 {
+    let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+    let (sin_theta, cos_theta) = (PI * v).sin_cos();
+
+    Vec3::new(sin_theta * cos_phi, cos_theta, sin_theta * sin_phi)
+}
+
+/// A piecewise constant distribution over [0, 1), with one piece per
+/// value.
+#[derive(Debug, Clone, PartialEq)]
+struct Distribution {
+    values: Vec<f64>,
+    /// Running integral of the values, normalized to end at 1.
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution {
+    fn new(values: Vec<f64>) -> Distribution {
+        let count = values.len() as f64;
+        let mut cdf = Vec::with_capacity(values.len() + 1);
+        cdf.push(0.0);
+        for value in &values {
+            cdf.push(cdf[cdf.len() - 1] + value / count);
+        }
+
+        let integral = cdf[cdf.len() - 1];
+        for (i, entry) in cdf.iter_mut().enumerate() {
+            // Without any weight every piece is equally likely.
+            *entry = if integral > 0.0 {
+                *entry / integral
+            } else {
+                i as f64 / count
+            };
+        }
+
+        Distribution {
+            values,
+            cdf,
+            integral,
+        }
+    }
+
+    /// Maps the uniform random number `u` to a point in [0, 1), returning
+    /// it with its density.
+    fn sample(&self, u: f64) -> (f64, f64) {
+        let count = self.values.len();
+        let piece = self
+            .cdf
+            .partition_point(|&entry| entry <= u)
+            .saturating_sub(1)
+            .min(count - 1);
+
+        let width = self.cdf[piece + 1] - self.cdf[piece];
+        let offset = if width > 0.0 {
+            (u - self.cdf[piece]) / width
+        } else {
+            0.0
+        };
+
+        ((piece as f64 + offset) / count as f64, self.pdf(piece))
+    }
+
+    /// Returns the density of points in the given piece.
+    fn pdf(&self, piece: usize) -> f64 {
+        if self.integral > 0.0 {
+            self.values[piece] / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn uniform(color: Color) -> EnvironmentMap {
+        let mut canvas = Canvas::new(8, 4);
+        for y in 0..4 {
+            for x in 0..8 {
+                canvas[(x, y)] = color;
+            }
+        }
+
+        EnvironmentMap::new(canvas, 1.0)
+    }
+
+    #[test]
+    fn test_uv_round_trip() {
+        for (u, v) in [(0.1, 0.2), (0.5, 0.5), (0.9, 0.7)] {
+            let (u2, v2) = direction_to_uv(&uv_to_direction(u, v));
+
+            assert_abs_diff_eq!(u2, u, epsilon = 1e-12);
+            assert_abs_diff_eq!(v2, v, epsilon = 1e-12);
+        }
+        assert_abs_diff_eq!(
+            uv_to_direction(0.0, 0.5),
+            Vec3::new(1.0, 0.0, 0.0),
+            epsilon = 1e-12
+        );
+        assert_abs_diff_eq!(
+            uv_to_direction(0.25, 0.5),
+            Vec3::new(0.0, 0.0, 1.0),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        let mut canvas = Canvas::new(16, 8);
+        canvas[(3, 2)] = Color::new(10.0, 10.0, 10.0);
+        canvas[(12, 6)] = Color::new(0.0, 1.0, 0.0);
+        let maps = [
+            uniform(Color::new(1.0, 1.0, 1.0)),
+            EnvironmentMap::new(canvas, 1.0),
+        ];
+        let mut rng = StdRng::seed_from_u64(8);
+
+        for map in maps {
+            let count = 200_000;
+            let integral = (0..count)
+                .map(|_| {
+                    let z = 1.0 - 2.0 * rng.gen::<f64>();
+                    let r = (1.0 - z * z).sqrt();
+                    let phi = 2.0 * PI * rng.gen::<f64>();
+                    map.pdf(&Vec3::new(r * phi.cos(), z, r * phi.sin()))
+                })
+                .sum::<f64>()
+                * 4.0
+                * PI
+                / count as f64;
+            assert_abs_diff_eq!(integral, 1.0, epsilon = 0.05);
+
+            for _ in 0..100 {
+                let sample = map.sample((rng.gen(), rng.gen())).unwrap();
+
+                assert_abs_diff_eq!(sample.pdf, map.pdf(&sample.wi), epsilon = 1e-6 * sample.pdf);
+                assert_eq!(sample.radiance, map.radiance(&sample.wi));
+            }
+        }
+    }
+
+    #[test]
+    fn test_samples_favour_bright_pixels() {
+        let mut canvas = Canvas::new(16, 8);
+        for y in 0..8 {
+            for x in 0..16 {
+                canvas[(x, y)] = Color::new(0.01, 0.01, 0.01);
+            }
+        }
+        canvas[(5, 3)] = Color::new(100.0, 100.0, 100.0);
+        let map = EnvironmentMap::new(canvas, 1.0);
+        let mut rng = StdRng::seed_from_u64(9);
+
+        let bright = (0..1000)
+            .filter(|_| {
+                let sample = map.sample((rng.gen(), rng.gen())).unwrap();
+                sample.radiance.r() == 100.0
+            })
+            .count();
+
+        assert!(bright > 950, "{}", bright);
+    }
+}
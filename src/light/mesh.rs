@@ -0,0 +1,201 @@
+use crate::core::{Color, Point, Vec3};
+
+use super::{solid_angle_pdf, LightSample};
+
+/// Emissive triangles sampled as one light source, in proportion to their
+/// area.
+///
+/// Worlds report the triangles they built from emissive geometry through
+/// [`World::mesh_lights`](crate::integrator::World::mesh_lights), and the
+/// index of the mesh light in [`Hit::light`](crate::integrator::Hit::light)
+/// when a ray hits one of them, so light found by following the BSDF can
+/// be weighted against sampling the light.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::{Color, Point};
+/// use raytracing::light::MeshLight;
+///
+/// // A 1 by 1 panel in the y = 2 plane.
+/// let a = Point::new(0.0, 2.0, 0.0);
+/// let b = Point::new(1.0, 2.0, 0.0);
+/// let c = Point::new(1.0, 2.0, 1.0);
+/// let d = Point::new(0.0, 2.0, 1.0);
+/// let panel = MeshLight::new(vec![[a, b, c], [a, c, d]], Color::new(5.0, 5.0, 5.0));
+///
+/// assert_eq!(panel.area(), 1.0);
+/// let sample = panel.sample(&Point::new(0.5, 0.0, 0.5), (0.3, 0.6)).unwrap();
+/// assert_eq!(sample.radiance, Color::new(5.0, 5.0, 5.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshLight {
+    triangles: Vec<[Point; 3]>,
+    /// Radiance emitted from both sides of the triangles.
+    emission: Color,
+    /// Running sum of the triangle areas.
+    cumulative_areas: Vec<f64>,
+}
+
+impl MeshLight {
+    pub fn new(triangles: Vec<[Point; 3]>, emission: Color) -> MeshLight {
+        let cumulative_areas = triangles
+            .iter()
+            .scan(0.0, |sum, triangle| {
+                *sum += triangle_area(triangle);
+                Some(*sum)
+            })
+            .collect();
+
+        MeshLight {
+            triangles,
+            emission,
+            cumulative_areas,
+        }
+    }
+
+    pub fn emission(&self) -> Color {
+        self.emission
+    }
+
+    /// Returns the total area of the triangles.
+    pub fn area(&self) -> f64 {
+        self.cumulative_areas.last().copied().unwrap_or(0.0)
+    }
+
+    /// Samples a point uniformly over the area of the mesh, returning the
+    /// direction towards it from `point`.
+    pub fn sample(&self, point: &Point, (a, b): (f64, f64)) -> Option<LightSample> {
+        let area = self.area();
+        if area == 0.0 {
+            return None;
+        }
+
+        // Pick a triangle by area, then reuse `a` within its share.
+        let target = a * area;
+        let index = self
+            .cumulative_areas
+            .partition_point(|&sum| sum <= target)
+            .min(self.triangles.len() - 1);
+        let start = if index == 0 {
+            0.0
+        } else {
+            self.cumulative_areas[index - 1]
+        };
+        let share = self.cumulative_areas[index] - start;
+        let a = ((target - start) / share).clamp(0.0, 1.0);
+
+        let [p0, p1, p2] = self.triangles[index];
+        let (b0, b1) = uniform_barycentrics(a, b);
+        let sampled = p0 + (p1 - p0) * b0 + (p2 - p0) * b1;
+
+        let to_light = sampled - *point;
+        let distance = to_light.dot(&to_light).sqrt();
+        let wi = to_light / distance;
+        let pdf = solid_angle_pdf(
+            distance,
+            &triangle_normal(&self.triangles[index]),
+            &wi,
+            area,
+        );
+        (pdf > 0.0 && pdf.is_finite()).then_some(LightSample {
+            wi,
+            distance,
+            radiance: self.emission,
+            pdf,
+        })
+    }
+
+    /// Returns the density with which [`MeshLight::sample`] picks the
+    /// direction from `from` towards `point` on the mesh, where the mesh
+    /// has the given `normal`.
+    pub fn pdf(&self, from: &Point, point: &Point, normal: &Vec3) -> f64 {
+        let to_light = *point - *from;
+        let distance = to_light.dot(&to_light).sqrt();
+        if distance == 0.0 || self.area() == 0.0 {
+            return 0.0;
+        }
+
+        solid_angle_pdf(
+            distance,
+            &normal.normalize(),
+            &(to_light / distance),
+            self.area(),
+        )
+    }
+}
+
+fn triangle_area([p0, p1, p2]: &[Point; 3]) -> f64 {
+    let normal = (*p1 - *p0).cross(&(*p2 - *p0));
+
+    0.5 * normal.dot(&normal).sqrt()
+}
+
+fn triangle_normal([p0, p1, p2]: &[Point; 3]) -> Vec3 {
+    (*p1 - *p0).cross(&(*p2 - *p0)).normalize()
+}
+
+/// Maps the unit square to barycentric coordinates distributed uniformly
+/// over a triangle.
+fn uniform_barycentrics(a: f64, b: f64) -> (f64, f64) {
+    let root = a.sqrt();
+
+    (1.0 - root, b * root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_triangles_are_picked_by_area() {
+        let small = [
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 0.0, 1.0),
+        ];
+        let large = [
+            Point::new(10.0, 0.0, 0.0),
+            Point::new(13.0, 0.0, 0.0),
+            Point::new(10.0, 0.0, 1.0),
+        ];
+        let light = MeshLight::new(vec![small, large], Color::new(1.0, 1.0, 1.0));
+        let from = Point::new(5.0, 5.0, 0.0);
+
+        assert_eq!(light.area(), 2.0);
+        let count = 1000;
+        let picked_large = (0..count)
+            .map(|i| (i as f64 + 0.5) / count as f64)
+            .filter(|&a| {
+                let sample = light.sample(&from, (a, 0.5)).unwrap();
+                (from + sample.wi * sample.distance).x() >= 10.0
+            })
+            .count();
+        assert_eq!(picked_large, 750);
+    }
+
+    #[test]
+    fn test_sample_pdf_matches_pdf() {
+        let triangle = [
+            Point::new(0.0, 2.0, 0.0),
+            Point::new(1.0, 2.0, 0.0),
+            Point::new(0.0, 2.0, 1.0),
+        ];
+        let light = MeshLight::new(vec![triangle], Color::new(1.0, 1.0, 1.0));
+        let from = Point::new(0.2, 0.0, 0.1);
+
+        for uv in [(0.1, 0.2), (0.5, 0.5), (0.9, 0.7)] {
+            let sample = light.sample(&from, uv).unwrap();
+            let point = from + sample.wi * sample.distance;
+
+            assert_abs_diff_eq!(point.y(), 2.0, epsilon = 1e-12);
+            assert!(point.x() + point.z() <= 1.0 + 1e-12);
+            assert_abs_diff_eq!(
+                sample.pdf,
+                light.pdf(&from, &point, &Vec3::new(0.0, -1.0, 0.0)),
+                epsilon = 1e-9
+            );
+        }
+    }
+}
@@ -0,0 +1,571 @@
+//! Sampling of the lights of a scene, for integrators that estimate direct
+//! lighting.
+//!
+//! Lights are sampled from the point being shaded: a sample gives the
+//! direction and distance to a point on the light, the light arriving from
+//! it and the density with which the direction was chosen, so estimates can
+//! be combined with BSDF sampling, see [`MisHeuristic`].
+
+mod area;
+mod environment;
+mod mesh;
+
+pub use environment::EnvironmentMap;
+pub use mesh::MeshLight;
+
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::core::{Color, Point, Ray, Vec3};
+use crate::render::stratified_offsets;
+use crate::scene::{Light, LightKind};
+use area::SphereCone;
+
+/// A direction towards a light, seen from the point being shaded.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LightSample {
+    /// Unit direction towards the light.
+    pub wi: Vec3,
+    /// Distance to the sampled point on the light.
+    pub distance: f64,
+    /// Light arriving from the sampled point.
+    pub radiance: Color,
+    /// Density of `wi` with respect to solid angle. Lights at a single
+    /// point are sampled with a density of 1, see [`Light::is_delta`].
+    pub pdf: f64,
+}
+
+impl Light {
+    /// Returns whether the light can only be reached by sampling it, so
+    /// BSDF sampling never finds it.
+    pub fn is_delta(&self) -> bool {
+        matches!(
+            self.kind,
+            LightKind::Point | LightKind::Spot { .. } | LightKind::Directional { .. }
+        )
+    }
+
+    /// Returns the light sent towards a point that sees the light in the
+    /// unit direction `wi`, before it falls off with distance.
+    ///
+    /// Spot lights fade out smoothly between their inner and outer cones.
+    /// Lights with a `power` emit their color scaled to that power.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::core::{Color, Vec3};
+    /// use raytracing::scene::{Light, LightKind};
+    ///
+    /// let spot = Light {
+    ///     kind: LightKind::Spot {
+    ///         direction: Vec3::new(0.0, -1.0, 0.0),
+    ///         inner_angle: 0.2,
+    ///         outer_angle: 0.4,
+    ///     },
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(spot.emitted(&Vec3::new(0.0, 1.0, 0.0)), Color::new(1.0, 1.0, 1.0));
+    /// assert_eq!(spot.emitted(&Vec3::new(1.0, 0.0, 0.0)), Color::default());
+    /// ```
+    pub fn emitted(&self, wi: &Vec3) -> Color {
+        let intensity = match self.power {
+            Some(power) => self.intensity * (power / self.power_per_intensity()),
+            None => self.intensity,
+        };
+
+        match self.kind {
+            LightKind::Spot {
+                direction,
+                inner_angle,
+                outer_angle,
+            } => {
+                let cos = -wi.dot(&direction.normalize());
+                intensity * smoothstep(outer_angle.cos(), inner_angle.cos(), cos)
+            }
+            _ => intensity,
+        }
+    }
+
+    /// Returns the power emitted by the light per unit of intensity.
+    fn power_per_intensity(&self) -> f64 {
+        match self.kind {
+            LightKind::Point => 4.0 * PI,
+            // Approximates the smooth edge by the cone halfway through it.
+            LightKind::Spot {
+                inner_angle,
+                outer_angle,
+                ..
+            } => 2.0 * PI * (1.0 - 0.5 * (inner_angle.cos() + outer_angle.cos())),
+            // Rects and disks emit from both sides.
+            LightKind::Rect { u, v } => 2.0 * PI * area::rect_area(&u, &v),
+            LightKind::Disk { radius, .. } => 2.0 * PI * PI * radius * radius,
+            LightKind::Sphere { radius } => 4.0 * PI * PI * radius * radius,
+            LightKind::Directional { .. } => 1.0,
+        }
+    }
+
+    /// Returns the factor by which the light falls off at `distance`, when
+    /// the scene specifies it: from the `attenuation` of point and spot
+    /// lights, or with the squared distance for point and spot lights
+    /// given in physical units.
+    pub fn specified_falloff(&self, distance: f64) -> Option<f64> {
+        if !matches!(self.kind, LightKind::Point | LightKind::Spot { .. }) {
+            return None;
+        }
+
+        match (self.attenuation, self.power) {
+            (Some(attenuation), _) => Some(attenuation.factor(distance)),
+            (None, Some(_)) => Some(1.0 / (distance * distance)),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the factor by which the light falls off at `distance` in
+    /// physically based integrators: [`Light::specified_falloff`], or the
+    /// squared distance for point and spot lights. Directional lights and
+    /// the radiance of area lights do not fall off.
+    pub fn falloff(&self, distance: f64) -> f64 {
+        match self.kind {
+            LightKind::Point | LightKind::Spot { .. } => self
+                .specified_falloff(distance)
+                .unwrap_or(1.0 / (distance * distance)),
+            _ => 1.0,
+        }
+    }
+
+    /// Returns the number of samples taken from a shading point: one for
+    /// point, spot and directional lights, `samples` for area lights.
+    pub fn sample_count(&self) -> usize {
+        if self.is_delta() {
+            1
+        } else {
+            self.samples.max(1)
+        }
+    }
+
+    /// Returns [`Light::sample_count`] pairs of random numbers to sample
+    /// the light with, stratified over the unit square.
+    pub fn sample_offsets(&self, rng: &mut impl Rng) -> Vec<(f64, f64)> {
+        stratified_offsets(self.sample_count(), true, rng)
+    }
+
+    /// Samples a direction from `point` towards the light, using the
+    /// uniform random numbers `u`.
+    ///
+    /// The light arriving is [`Light::emitted`] scaled by
+    /// [`Light::falloff`]. Directional lights are infinitely far away.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::core::{Color, Point};
+    /// use raytracing::scene::Light;
+    ///
+    /// let light = Light {
+    ///     position: Point::new(0.0, 2.0, 0.0),
+    ///     intensity: Color::new(1.0, 1.0, 1.0),
+    ///     ..Default::default()
+    /// };
+    /// let sample = light.sample(&Point::new(0.0, 0.0, 0.0), (0.5, 0.5)).unwrap();
+    ///
+    /// assert_eq!(sample.distance, 2.0);
+    /// assert_eq!(sample.radiance, Color::new(0.25, 0.25, 0.25));
+    /// ```
+    pub fn sample(&self, point: &Point, u: (f64, f64)) -> Option<LightSample> {
+        let area_sample = match self.kind {
+            LightKind::Point | LightKind::Spot { .. } => {
+                let to_light = self.position - *point;
+                let distance = to_light.dot(&to_light).sqrt();
+                if distance == 0.0 {
+                    return None;
+                }
+
+                let wi = to_light / distance;
+                return Some(LightSample {
+                    wi,
+                    distance,
+                    radiance: self.emitted(&wi) * self.falloff(distance),
+                    pdf: 1.0,
+                });
+            }
+            LightKind::Directional { direction } => {
+                let wi = -direction.normalize();
+                return Some(LightSample {
+                    wi,
+                    distance: f64::INFINITY,
+                    radiance: self.emitted(&wi),
+                    pdf: 1.0,
+                });
+            }
+            LightKind::Rect {
+                u: edge_u,
+                v: edge_v,
+            } => area::sample_rect(&self.position, &edge_u, &edge_v, u),
+            LightKind::Disk { normal, radius } => {
+                area::sample_disk(&self.position, &normal, radius, u)
+            }
+            LightKind::Sphere { radius } => match SphereCone::new(&self.position, radius, point) {
+                Some(cone) => {
+                    let (wi, distance) = cone.sample(radius, u);
+                    return Some(LightSample {
+                        wi,
+                        distance,
+                        radiance: self.emitted(&wi),
+                        pdf: cone.pdf(),
+                    });
+                }
+                None => area::sample_sphere_surface(&self.position, radius, u),
+            },
+        };
+
+        let to_light = area_sample.point - *point;
+        let distance = to_light.dot(&to_light).sqrt();
+        let wi = to_light / distance;
+        let pdf = solid_angle_pdf(distance, &area_sample.normal, &wi, area_sample.area);
+        (pdf > 0.0 && pdf.is_finite()).then_some(LightSample {
+            wi,
+            distance,
+            radiance: self.emitted(&wi),
+            pdf,
+        })
+    }
+
+    /// Returns the density with which [`Light::sample`] picks the unit
+    /// direction `wi` from `point`, with respect to solid angle.
+    pub fn pdf(&self, point: &Point, wi: &Vec3) -> f64 {
+        let ray = Ray::new(*point, *wi);
+        let (t, normal, area) = match self.kind {
+            LightKind::Point | LightKind::Spot { .. } | LightKind::Directional { .. } => {
+                return 0.0
+            }
+            LightKind::Rect { u, v } => match area::intersect_rect(&self.position, &u, &v, &ray) {
+                Some(t) => (t, u.cross(&v).normalize(), area::rect_area(&u, &v)),
+                None => return 0.0,
+            },
+            LightKind::Disk { normal, radius } => {
+                match area::intersect_disk(&self.position, &normal, radius, &ray) {
+                    Some(t) => (t, normal, PI * radius * radius),
+                    None => return 0.0,
+                }
+            }
+            LightKind::Sphere { radius } => {
+                let Some(t) = area::intersect_sphere(&self.position, radius, &ray) else {
+                    return 0.0;
+                };
+                if let Some(cone) = SphereCone::new(&self.position, radius, point) {
+                    return cone.pdf();
+                }
+                let normal = ray.position(t) - self.position;
+                (t, normal, 4.0 * PI * radius * radius)
+            }
+        };
+
+        solid_angle_pdf(t, &normal.normalize(), wi, area)
+    }
+
+    /// Returns the distance along `ray` to the light and the light it
+    /// emits back along the ray, when the ray hits it.
+    pub fn intersect(&self, ray: &Ray) -> Option<(f64, Color)> {
+        let t = match self.kind {
+            LightKind::Point | LightKind::Spot { .. } | LightKind::Directional { .. } => None,
+            LightKind::Rect { u, v } => area::intersect_rect(&self.position, &u, &v, ray),
+            LightKind::Disk { normal, radius } => {
+                area::intersect_disk(&self.position, &normal, radius, ray)
+            }
+            LightKind::Sphere { radius } => area::intersect_sphere(&self.position, radius, ray),
+        }?;
+
+        Some((t, self.emitted(&ray.direction.normalize())))
+    }
+}
+
+/// Hermite interpolation from 0 at `edge0` to 1 at `edge1`.
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    if edge0 >= edge1 {
+        return if x >= edge1 { 1.0 } else { 0.0 };
+    }
+
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Converts the density of a point picked uniformly on a surface of the
+/// given area to the density of the direction `wi` towards it.
+fn solid_angle_pdf(distance: f64, normal: &Vec3, wi: &Vec3, area: f64) -> f64 {
+    let cos = normal.dot(wi).abs();
+    if cos == 0.0 {
+        return 0.0;
+    }
+
+    distance * distance / (area * cos)
+}
+
+/// How estimates from light sampling and BSDF sampling are weighted against
+/// each other (Veach, "Optimally Combining Sampling Techniques for Monte
+/// Carlo Rendering").
+///
+/// Each technique is weighted by how likely it was to produce the sample,
+/// so light sampling dominates for small lights and diffuse surfaces and
+/// BSDF sampling for large lights and glossy surfaces.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum MisHeuristic {
+    /// Weights proportional to the densities.
+    Balance,
+    /// Weights proportional to the squared densities, which favour the
+    /// better technique more strongly.
+    #[default]
+    Power,
+}
+
+impl MisHeuristic {
+    /// Returns the weight of a sample taken with density `pdf`, when the
+    /// other technique would have taken it with density `other_pdf`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::light::MisHeuristic;
+    ///
+    /// assert_eq!(MisHeuristic::Balance.weight(3.0, 1.0), 0.75);
+    /// assert_eq!(MisHeuristic::Power.weight(3.0, 1.0), 0.9);
+    /// ```
+    pub fn weight(&self, pdf: f64, other_pdf: f64) -> f64 {
+        let (a, b) = match self {
+            MisHeuristic::Balance => (pdf, other_pdf),
+            MisHeuristic::Power => (pdf * pdf, other_pdf * other_pdf),
+        };
+
+        if a.is_infinite() {
+            1.0
+        } else if a + b == 0.0 {
+            0.0
+        } else {
+            a / (a + b)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::Attenuation;
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_point_light_sample() {
+        let light = Light {
+            position: Point::new(3.0, 4.0, 0.0),
+            intensity: Color::new(1.0, 0.5, 0.0),
+            ..Default::default()
+        };
+
+        let sample = light
+            .sample(&Point::new(0.0, 0.0, 0.0), (0.1, 0.9))
+            .unwrap();
+
+        assert!(light.is_delta());
+        assert_abs_diff_eq!(sample.wi, Vec3::new(0.6, 0.8, 0.0), epsilon = 1e-12);
+        assert_eq!(sample.distance, 5.0);
+        assert_abs_diff_eq!(
+            sample.radiance,
+            Color::new(0.04, 0.02, 0.0),
+            epsilon = 1e-12
+        );
+        assert!(light.sample(&light.position, (0.5, 0.5)).is_none());
+    }
+
+    /// Integrates the density of every area light kind over the sphere of
+    /// directions, and checks samples against it.
+    #[test]
+    fn test_area_light_pdfs() {
+        let point = Point::new(0.2, -0.1, 0.3);
+        let kinds = [
+            LightKind::Rect {
+                u: Vec3::new(1.0, 0.0, 0.2),
+                v: Vec3::new(0.0, 0.0, 1.5),
+            },
+            LightKind::Disk {
+                normal: Vec3::new(0.3, -1.0, 0.0),
+                radius: 0.8,
+            },
+            LightKind::Sphere { radius: 0.5 },
+            LightKind::Sphere { radius: 3.0 },
+        ];
+        let mut rng = StdRng::seed_from_u64(4);
+
+        for kind in kinds {
+            let light = Light {
+                position: Point::new(0.0, 1.0, 0.0),
+                kind,
+                ..Default::default()
+            };
+
+            let count = 200_000;
+            let integral = (0..count)
+                .map(|_| {
+                    let z = 1.0 - 2.0 * rng.gen::<f64>();
+                    let r = (1.0 - z * z).sqrt();
+                    let phi = 2.0 * PI * rng.gen::<f64>();
+                    let wi = Vec3::new(r * phi.cos(), r * phi.sin(), z);
+                    light.pdf(&point, &wi)
+                })
+                .sum::<f64>()
+                * 4.0
+                * PI
+                / count as f64;
+            assert_abs_diff_eq!(integral, 1.0, epsilon = 0.05);
+
+            for _ in 0..100 {
+                let sample = light.sample(&point, (rng.gen(), rng.gen())).unwrap();
+                let (t, radiance) = light.intersect(&Ray::new(point, sample.wi)).unwrap();
+
+                assert!(!light.is_delta());
+                assert_abs_diff_eq!(t, sample.distance, epsilon = 1e-6);
+                assert_eq!(radiance, light.intensity);
+                assert_abs_diff_eq!(
+                    sample.pdf,
+                    light.pdf(&point, &sample.wi),
+                    epsilon = 1e-6 * sample.pdf
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample_offsets_are_stratified_for_area_lights() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut light = Light {
+            samples: 4,
+            ..Default::default()
+        };
+        assert_eq!(light.sample_offsets(&mut rng).len(), 1);
+
+        light.kind = LightKind::Sphere { radius: 1.0 };
+        let offsets = light.sample_offsets(&mut rng);
+        assert_eq!(offsets.len(), 4);
+        assert!(offsets[0].0 < 0.5 && offsets[0].1 < 0.5);
+        assert!(offsets[3].0 >= 0.5 && offsets[3].1 >= 0.5);
+    }
+
+    #[test]
+    fn test_spot_light_cone() {
+        let light = Light {
+            position: Point::new(0.0, 2.0, 0.0),
+            intensity: Color::new(4.0, 4.0, 4.0),
+            kind: LightKind::Spot {
+                direction: Vec3::new(0.0, -1.0, 0.0),
+                inner_angle: PI / 8.0,
+                outer_angle: PI / 4.0,
+            },
+            ..Default::default()
+        };
+        let radiance = |x: f64| {
+            light
+                .sample(&Point::new(x, 0.0, 0.0), (0.5, 0.5))
+                .unwrap()
+                .radiance
+                .r()
+        };
+
+        assert!(light.is_delta());
+        assert_eq!(radiance(0.0), 1.0);
+        // tan(π / 4) * 2 = 2 is the edge of the outer cone.
+        assert_eq!(radiance(2.5), 0.0);
+        let edge = radiance(1.2);
+        assert!(edge > 0.0 && edge < 4.0 / (1.2 * 1.2 + 4.0));
+    }
+
+    #[test]
+    fn test_directional_light_does_not_fall_off() {
+        let light = Light {
+            intensity: Color::new(0.5, 0.5, 0.5),
+            kind: LightKind::Directional {
+                direction: Vec3::new(0.0, -2.0, 0.0),
+            },
+            ..Default::default()
+        };
+
+        for point in [Point::new(0.0, 0.0, 0.0), Point::new(100.0, -50.0, 3.0)] {
+            let sample = light.sample(&point, (0.5, 0.5)).unwrap();
+
+            assert_eq!(sample.wi, Vec3::new(0.0, 1.0, 0.0));
+            assert_eq!(sample.distance, f64::INFINITY);
+            assert_eq!(sample.radiance, Color::new(0.5, 0.5, 0.5));
+        }
+        assert!(light
+            .intersect(&Ray::new(
+                Point::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0)
+            ))
+            .is_none());
+    }
+
+    #[test]
+    fn test_power_sets_the_emitted_light() {
+        let mut light = Light {
+            intensity: Color::new(1.0, 0.5, 0.25),
+            power: Some(8.0 * PI),
+            ..Default::default()
+        };
+        let wi = Vec3::new(0.0, 1.0, 0.0);
+
+        // A point light spreads its power over the whole sphere.
+        assert_abs_diff_eq!(
+            light.emitted(&wi),
+            Color::new(2.0, 1.0, 0.5),
+            epsilon = 1e-12
+        );
+
+        // Both sides of a rect emit into a hemisphere each.
+        light.kind = LightKind::Rect {
+            u: Vec3::new(2.0, 0.0, 0.0),
+            v: Vec3::new(0.0, 0.0, 2.0),
+        };
+        assert_abs_diff_eq!(
+            light.emitted(&wi),
+            Color::new(1.0, 0.5, 0.25),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_falloff() {
+        let mut light = Light::default();
+        assert_eq!(light.specified_falloff(2.0), None);
+        assert_eq!(light.falloff(2.0), 0.25);
+
+        light.attenuation = Some(Attenuation {
+            constant: 1.0,
+            linear: 1.0,
+            quadratic: 0.0,
+        });
+        assert_eq!(light.specified_falloff(2.0), Some(1.0 / 3.0));
+        assert_eq!(light.falloff(2.0), 1.0 / 3.0);
+
+        light.attenuation = None;
+        light.power = Some(1.0);
+        assert_eq!(light.specified_falloff(2.0), Some(0.25));
+
+        light.kind = LightKind::Directional {
+            direction: Vec3::new(0.0, -1.0, 0.0),
+        };
+        assert_eq!(light.specified_falloff(2.0), None);
+        assert_eq!(light.falloff(2.0), 1.0);
+    }
+
+    #[test]
+    fn test_heuristic_weights_sum_to_one() {
+        for heuristic in [MisHeuristic::Balance, MisHeuristic::Power] {
+            for (a, b) in [(1.0, 1.0), (0.2, 5.0), (10.0, 0.0)] {
+                let sum = heuristic.weight(a, b) + heuristic.weight(b, a);
+                assert_abs_diff_eq!(sum, 1.0, epsilon = 1e-12);
+            }
+            assert_eq!(heuristic.weight(0.0, 0.0), 0.0);
+            assert_eq!(heuristic.weight(f64::INFINITY, 1.0), 1.0);
+        }
+    }
+}
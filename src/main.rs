@@ -1,6 +1,1079 @@
-use raytracing::core::Point;
+use std::{
+    collections::BTreeMap,
+    fs,
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, SystemTime},
+};
 
-fn main() {
-    let p = Point::default();
-    println!("{} {} {}", p.x(), p.y(), p.z());
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::{prelude::*, ThreadPool};
+use raytracing::{
+    core::{Canvas, Color},
+    integrator::IntegratorKind,
+    output::{save_canvas, save_passes, PreviewServer},
+    render::{
+        render_adaptive, render_distributed, render_progressive, render_supersampled, render_tiles,
+        serve_coordinator, stratified_offsets, Accumulator, AdaptiveRender, AdaptiveSampling,
+        Checkpoint, Filter, Job, PixelShader, RenderPasses, Supersampling, TileSettings,
+    },
+    scene::{load_scene, Object, Projection, Scene, SceneWorld, Shape},
+};
+use serde::{Deserialize, Serialize};
+
+/// Renders scene files with a recursive ray tracer.
+#[derive(Debug, Parser)]
+#[command(name = "raytracing", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Render a scene file to an image.
+    Render(Box<RenderArgs>),
+    /// Print statistics about a scene file.
+    Info {
+        /// Scene file (.yml, .yaml, .json or .toml).
+        scene: PathBuf,
+    },
+    /// Render tiles for `render --workers` coordinators.
+    Worker {
+        /// Address to listen on. Only local coordinators can connect to the
+        /// default, use e.g. `0.0.0.0:7878` to accept them from the network.
+        #[arg(long, default_value = "127.0.0.1:7878")]
+        listen: String,
+        /// Number of render threads, defaults to the number of cores.
+        #[arg(long)]
+        threads: Option<usize>,
+    },
+}
+
+#[derive(Debug, Args)]
+struct RenderArgs {
+    /// Scene file (.yml, .yaml, .json or .toml).
+    scene: PathBuf,
+    /// Output image; the format follows from the extension.
+    #[arg(short, long, default_value = "out.png")]
+    output: PathBuf,
+    /// Image width in pixels, overriding the scene camera.
+    #[arg(long)]
+    width: Option<usize>,
+    /// Image height in pixels, overriding the scene camera.
+    #[arg(long)]
+    height: Option<usize>,
+    /// Samples per pixel.
+    #[arg(long, default_value_t = 1)]
+    samples: usize,
+    /// Pixel filter the samples are weighed with: box, tent, gaussian or
+    /// mitchell, optionally followed by a radius in pixels as in `tent:1.5`.
+    #[arg(long, default_value_t = Filter::default(), conflicts_with_all = ["checkpoint", "workers", "serve"])]
+    filter: Filter,
+    /// Keep sampling each pixel until the standard error of its luminance
+    /// drops below this threshold, starting from --samples.
+    #[arg(long, value_name = "THRESHOLD", conflicts_with_all = ["filter", "checkpoint", "workers", "serve"])]
+    adaptive: Option<f64>,
+    /// Upper bound of samples for a single pixel with --adaptive.
+    #[arg(long, default_value_t = 256, requires = "adaptive")]
+    max_samples: usize,
+    /// Save the number of samples taken per pixel with --adaptive as a
+    /// grayscale image to this file.
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "adaptive",
+        conflicts_with = "watch"
+    )]
+    heatmap: Option<PathBuf>,
+    /// Number of render threads, defaults to the number of cores.
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Maximum number of surfaces a ray is followed through.
+    #[arg(long, default_value_t = 5)]
+    depth: usize,
+    /// How rays are shaded: whitted, path, ambient_occlusion, normals or
+    /// direct.
+    #[arg(long, default_value_t = IntegratorKind::Whitted)]
+    integrator: IntegratorKind,
+    /// Keep running and re-render whenever the scene or its meshes change.
+    #[arg(long, conflicts_with = "checkpoint")]
+    watch: bool,
+    /// Render progressively and save the state to this file, resuming from
+    /// it if it exists.
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+    /// Seconds between checkpoint saves.
+    #[arg(long, default_value_t = 60)]
+    checkpoint_interval: u64,
+    /// Render on worker processes at these comma separated addresses
+    /// instead of locally.
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["watch", "checkpoint"])]
+    workers: Vec<String>,
+    /// Serve a live preview of the render over HTTP, on 127.0.0.1:8080
+    /// unless another address is given.
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        num_args = 0..=1,
+        default_missing_value = "127.0.0.1:8080",
+        conflicts_with_all = ["watch", "checkpoint"]
+    )]
+    serve: Option<String>,
+    /// Also save the depth, normal, albedo, object id, material id and
+    /// intersection test passes next to the output.
+    #[arg(long, conflicts_with_all = ["watch", "checkpoint", "workers", "serve"])]
+    passes: bool,
+}
+
+/// How often watch mode checks for changed files.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Resolution divisors of the successive renders in watch mode.
+const WATCH_REFINEMENTS: [usize; 3] = [8, 2, 1];
+
+/// Seconds a worker may take for a single tile before it is considered dead.
+const WORKER_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Seconds a worker waits for the next tile before dropping the
+/// coordinator. Coordinators wait for tiles on other workers for up to
+/// [`WORKER_TIMEOUT`] before handing them out again.
+const COORDINATOR_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Everything a render needs besides the scene.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+struct RenderSettings {
+    width: usize,
+    height: usize,
+    samples: usize,
+    filter: Filter,
+    /// Sample pixels adaptively instead of taking `samples` each.
+    adaptive: Option<AdaptiveSampling>,
+    depth: usize,
+    integrator: IntegratorKind,
+}
+
+/// The payload of a distributed render job.
+#[derive(Debug, Serialize, Deserialize)]
+struct WorkerJob {
+    scene: Scene,
+    /// Directory the files referenced by the scene are relative to.
+    directory: PathBuf,
+    settings: RenderSettings,
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Render(args) => render_command(&args),
+        Command::Info { scene } => {
+            print!("{}", info(&load_scene(&scene)?));
+            Ok(())
+        }
+        Command::Worker { listen, threads } => worker_command(&listen, threads),
+    }
+}
+
+fn thread_pool(threads: Option<usize>) -> Result<ThreadPool> {
+    let mut pool = rayon::ThreadPoolBuilder::new();
+    if let Some(threads) = threads {
+        pool = pool.num_threads(threads);
+    }
+
+    Ok(pool.build()?)
+}
+
+fn render_command(args: &RenderArgs) -> Result<()> {
+    let pool = thread_pool(args.threads)?;
+
+    if !args.workers.is_empty() {
+        render_on_workers(args)
+    } else if args.watch {
+        watch(args, &pool)
+    } else if let Some(checkpoint) = &args.checkpoint {
+        render_with_checkpoint(args, &pool, checkpoint)
+    } else if let Some(address) = &args.serve {
+        render_with_preview(args, &pool, address)
+    } else {
+        let scene = load_scene(&args.scene)?;
+        let settings = render_settings(&scene, args)?;
+        let directory = scene_directory(&args.scene);
+        let canvas = match (&args.heatmap, &settings.adaptive) {
+            (Some(heatmap), Some(adaptive)) => {
+                let render =
+                    pool.install(|| render_adaptively(&scene, directory, &settings, adaptive))?;
+                let heatmap = heatmap.to_str().context("Invalid heatmap filename")?;
+                save_canvas(&render.sample_heatmap(), heatmap)?;
+
+                render.canvas
+            }
+            _ => pool.install(|| render(&scene, directory, &settings))?,
+        };
+
+        if args.passes {
+            let passes = pool.install(|| render_passes(&scene, directory, &settings, canvas))?;
+            save_passes(&passes, output_filename(args)?)
+        } else {
+            save_canvas(&canvas, output_filename(args)?)
+        }
+    }
+}
+
+/// Renders tile by tile, serving the tiles finished so far over HTTP.
+fn render_with_preview(args: &RenderArgs, pool: &ThreadPool, address: &str) -> Result<()> {
+    let scene = load_scene(&args.scene)?;
+    let settings = render_settings(&scene, args)?;
+    let shade = pixel_shader(&scene, scene_directory(&args.scene), &settings)?;
+    let preview = start_preview(address, &settings)?;
+
+    let canvas = pool.install(|| {
+        render_tiles(
+            settings.width,
+            settings.height,
+            &TileSettings::default(),
+            &AtomicBool::new(false),
+            |rendered, progress| preview.update(rendered, progress),
+            shade,
+        )
+    })?;
+
+    save_canvas(&canvas, output_filename(args)?)
+}
+
+fn start_preview(address: &str, settings: &RenderSettings) -> Result<PreviewServer> {
+    let preview = PreviewServer::start(address, settings.width, settings.height)?;
+    eprintln!("Serving the preview at http://{}/", preview.address());
+
+    Ok(preview)
+}
+
+/// Renders one sample per pixel per pass, saving the accumulated samples to
+/// the checkpoint file regularly so an interrupted render can be resumed by
+/// running the same command again. Ctrl-C stops the render after the
+/// current pass and saves a final checkpoint.
+fn render_with_checkpoint(args: &RenderArgs, pool: &ThreadPool, path: &Path) -> Result<()> {
+    let scene = load_scene(&args.scene)?;
+    let settings = render_settings(&scene, args)?;
+    let fingerprint = render_fingerprint(&scene, &settings)?;
+
+    let mut accumulator = if path.exists() {
+        let accumulator = Accumulator::load(path)?;
+        if (accumulator.width, accumulator.height) != (settings.width, settings.height) {
+            bail!(
+                "{} holds a {}x{} render, remove it to start over",
+                path.display(),
+                accumulator.width,
+                accumulator.height
+            );
+        }
+        if accumulator.fingerprint() != fingerprint {
+            bail!(
+                "{} holds a render of another scene or with other settings, remove it to start over",
+                path.display()
+            );
+        }
+        eprintln!("Resuming after {} samples per pixel", accumulator.passes());
+        accumulator
+    } else {
+        Accumulator::new(settings.width, settings.height, 0).with_fingerprint(fingerprint)
+    };
+
+    let shade = shader(&scene, scene_directory(&args.scene), &settings)?;
+    let checkpoint = Checkpoint {
+        path,
+        interval: Duration::from_secs(args.checkpoint_interval),
+    };
+    handle_interrupts();
+    let done = pool.install(|| {
+        render_progressive(
+            &mut accumulator,
+            settings.samples as u64,
+            Some(&checkpoint),
+            &INTERRUPTED,
+            shade,
+        )
+    })?;
+    if !done {
+        eprintln!(
+            "Interrupted after {} samples per pixel, run the same command to resume",
+            accumulator.passes()
+        );
+    }
+
+    save_canvas(&accumulator.to_canvas(), output_filename(args)?)
+}
+
+/// Hashes the scene and the settings that change the samples of a render.
+/// The number of samples is left out, so a finished render can be resumed
+/// to take more.
+fn render_fingerprint(scene: &Scene, settings: &RenderSettings) -> Result<u64> {
+    let settings = RenderSettings {
+        samples: 0,
+        ..*settings
+    };
+    let bytes = serde_json::to_vec(&(scene, settings))?;
+
+    // FNV-1a, which unlike the standard library hasher is stable across
+    // builds.
+    Ok(bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    }))
+}
+
+/// Set by Ctrl-C while a checkpointed render is running.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Makes the first Ctrl-C set [`INTERRUPTED`] instead of terminating the
+/// process. A second Ctrl-C terminates it as usual.
+#[cfg(unix)]
+fn handle_interrupts() {
+    extern "C" fn interrupt(_: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::Relaxed);
+        // SAFETY: signal is async-signal-safe.
+        unsafe { libc::signal(libc::SIGINT, libc::SIG_DFL) };
+    }
+
+    let handler: extern "C" fn(libc::c_int) = interrupt;
+    // SAFETY: the handler only stores to an atomic and restores the default
+    // handler, both of which are async-signal-safe.
+    unsafe { libc::signal(libc::SIGINT, handler as libc::sighandler_t) };
+}
+
+#[cfg(not(unix))]
+fn handle_interrupts() {}
+
+/// Splits the frame into tiles and renders them on the workers. The scene
+/// is sent along, but the files it references must be readable by the
+/// workers at the same absolute path.
+fn render_on_workers(args: &RenderArgs) -> Result<()> {
+    let scene = load_scene(&args.scene)?;
+    let settings = render_settings(&scene, args)?;
+    let directory = fs::canonicalize(&args.scene)?
+        .parent()
+        .context("The scene file has no directory")?
+        .to_path_buf();
+    let job = Job {
+        width: settings.width,
+        height: settings.height,
+        payload: serde_json::to_string(&WorkerJob {
+            scene,
+            directory,
+            settings,
+        })?,
+    };
+
+    let preview = match &args.serve {
+        Some(address) => Some(start_preview(address, &settings)?),
+        None => None,
+    };
+
+    let canvas = render_distributed(
+        &args.workers,
+        &job,
+        &TileSettings::default(),
+        WORKER_TIMEOUT,
+        |rendered, progress| {
+            if let Some(preview) = &preview {
+                preview.update(rendered, progress);
+            }
+            eprint!("\rRendered {:5.1}%", progress.fraction() * 100.0);
+        },
+    )?;
+    eprintln!();
+
+    save_canvas(&canvas, output_filename(args)?)
+}
+
+/// Accepts coordinators forever, serving each on its own thread.
+fn worker_command(listen: &str, threads: Option<usize>) -> Result<()> {
+    let pool = thread_pool(threads)?;
+    let listener = TcpListener::bind(listen)
+        .with_context(|| format!("Error while listening on {}", listen))?;
+    eprintln!("Listening on {}", listener.local_addr()?);
+
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(error) => {
+                    eprintln!("Error: {}", error);
+                    continue;
+                }
+            };
+            let peer = stream.peer_addr().map(|peer| peer.to_string());
+            let pool = &pool;
+            scope.spawn(move || {
+                let peer = peer.unwrap_or_default();
+                eprintln!("Rendering for {}", peer);
+                match pool.install(|| serve_coordinator(stream, COORDINATOR_TIMEOUT, prepare_job)) {
+                    Ok(()) => eprintln!("Finished rendering for {}", peer),
+                    Err(error) => eprintln!("Error while rendering for {}: {:#}", peer, error),
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+/// Builds the pixel shader for a job received from a coordinator.
+fn prepare_job(job: &Job) -> Result<PixelShader> {
+    let WorkerJob {
+        scene,
+        directory,
+        settings,
+    } = serde_json::from_str(&job.payload)?;
+
+    pixel_shader(&scene, &directory, &settings)
+}
+
+/// Returns the function computing the color of a whole pixel, averaging
+/// stratified samples when more than one sample per pixel is requested.
+fn pixel_shader(scene: &Scene, directory: &Path, settings: &RenderSettings) -> Result<PixelShader> {
+    let shade = shader(scene, directory, settings)?;
+    let settings = *settings;
+
+    Ok(Box::new(move |x, y| {
+        let mut rng = StdRng::seed_from_u64((y * settings.width + x) as u64);
+        let offsets = stratified_offsets(settings.samples, settings.samples > 1, &mut rng);
+        let sum = offsets.iter().fold(Color::default(), |sum, (dx, dy)| {
+            sum + shade(x as f64 + dx, y as f64 + dy, &mut rng)
+        });
+
+        sum * (1.0 / offsets.len() as f64)
+    }))
+}
+
+/// Polls the scene file and the files it references, and renders the scene
+/// again whenever one of them changes. Errors are reported and watching
+/// continues.
+fn watch(args: &RenderArgs, pool: &ThreadPool) -> Result<()> {
+    let mut rendered = None;
+
+    loop {
+        render_if_changed(args, pool, &mut rendered);
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Renders the scene when its files changed since the modification times
+/// in `rendered`, which are updated. Returns whether it rendered.
+fn render_if_changed(
+    args: &RenderArgs,
+    pool: &ThreadPool,
+    rendered: &mut Option<Vec<(PathBuf, Option<SystemTime>)>>,
+) -> bool {
+    let current = modification_times(&args.scene);
+    if rendered.as_ref() == Some(&current) {
+        return false;
+    }
+
+    if let Err(error) = render_progressively(args, pool, &current) {
+        eprintln!("Error: {:#}", error);
+    }
+    *rendered = Some(current);
+
+    true
+}
+
+/// Renders at increasing resolutions, overwriting the output after every
+/// step, and stops early when the files change in the meantime.
+fn render_progressively(
+    args: &RenderArgs,
+    pool: &ThreadPool,
+    files: &[(PathBuf, Option<SystemTime>)],
+) -> Result<()> {
+    let scene = load_scene(&args.scene)?;
+    let settings = render_settings(&scene, args)?;
+
+    for divisor in WATCH_REFINEMENTS {
+        if modification_times(&args.scene) != files {
+            return Ok(());
+        }
+
+        let preview = RenderSettings {
+            width: (settings.width / divisor).max(1),
+            height: (settings.height / divisor).max(1),
+            ..settings
+        };
+        let canvas = pool
+            .install(|| render(&scene, scene_directory(&args.scene), &preview))?
+            .resize_nearest(settings.width, settings.height);
+
+        save_canvas(&canvas, output_filename(args)?)?;
+        eprintln!(
+            "Rendered {}x{} to {}",
+            preview.width,
+            preview.height,
+            args.output.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the modification times of the scene file and the files it
+/// references; files that cannot be read have no time.
+fn modification_times(scene_path: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let mut paths = vec![scene_path.to_path_buf()];
+    if let Ok(scene) = load_scene(scene_path) {
+        let base = scene_directory(scene_path);
+        paths.extend(
+            scene
+                .referenced_files()
+                .into_iter()
+                .map(|file| base.join(file)),
+        );
+    }
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let modified = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+fn output_filename(args: &RenderArgs) -> Result<&str> {
+    args.output.to_str().context("Invalid output filename")
+}
+
+fn render_settings(scene: &Scene, args: &RenderArgs) -> Result<RenderSettings> {
+    let camera = scene.camera.as_ref();
+    let width = args.width.or(camera.map(|camera| camera.width));
+    let height = args.height.or(camera.map(|camera| camera.height));
+
+    let (Some(width), Some(height)) = (width, height) else {
+        bail!("The scene has no camera, pass --width and --height");
+    };
+    if width == 0 || height == 0 || args.samples == 0 {
+        bail!("Width, height and samples must be positive");
+    }
+
+    let adaptive = match args.adaptive {
+        Some(threshold) if !(threshold >= 0.0 && threshold.is_finite()) => {
+            bail!("The adaptive threshold must be a finite, non-negative number")
+        }
+        Some(_) if args.max_samples < args.samples => {
+            bail!("--max-samples must be at least --samples")
+        }
+        Some(threshold) => Some(AdaptiveSampling {
+            min_samples: args.samples,
+            max_samples: args.max_samples,
+            threshold,
+            ..Default::default()
+        }),
+        None => None,
+    };
+
+    Ok(RenderSettings {
+        width,
+        height,
+        samples: args.samples,
+        filter: args.filter,
+        adaptive,
+        depth: args.depth,
+        integrator: args.integrator,
+    })
+}
+
+/// Returns the directory the files referenced by a scene are relative to.
+fn scene_directory(scene_path: &Path) -> &Path {
+    scene_path.parent().unwrap_or(Path::new(""))
+}
+
+fn render(scene: &Scene, directory: &Path, settings: &RenderSettings) -> Result<Canvas> {
+    if let Some(adaptive) = &settings.adaptive {
+        return Ok(render_adaptively(scene, directory, settings, adaptive)?.canvas);
+    }
+
+    let shade = shader(scene, directory, settings)?;
+    let supersampling = Supersampling {
+        samples_per_pixel: settings.samples,
+        jitter: settings.samples > 1,
+        filter: settings.filter,
+    };
+
+    Ok(render_supersampled(
+        settings.width,
+        settings.height,
+        &supersampling,
+        0,
+        shade,
+    ))
+}
+
+/// Renders with adaptive sampling, keeping the sample counts per pixel.
+fn render_adaptively(
+    scene: &Scene,
+    directory: &Path,
+    settings: &RenderSettings,
+    adaptive: &AdaptiveSampling,
+) -> Result<AdaptiveRender> {
+    let shade = shader(scene, directory, settings)?;
+
+    Ok(render_adaptive(
+        settings.width,
+        settings.height,
+        adaptive,
+        0,
+        shade,
+    ))
+}
+
+/// Fills the auxiliary passes of a render from the first hit of a ray
+/// through the center of each pixel.
+fn render_passes(
+    scene: &Scene,
+    directory: &Path,
+    settings: &RenderSettings,
+    beauty: Canvas,
+) -> Result<RenderPasses> {
+    let world = SceneWorld::load(scene, directory)?;
+    let camera = scene.camera.unwrap_or_default();
+    let projection = Projection::new(&camera, settings.width, settings.height);
+    let samples: Vec<_> = (0..settings.width * settings.height)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = (i % settings.width, i / settings.width);
+            world.surface_sample(&projection.ray(x as f64 + 0.5, y as f64 + 0.5))
+        })
+        .collect();
+
+    let mut passes = RenderPasses::new(settings.width, settings.height);
+    passes.beauty = beauty;
+    for (i, (sample, tests)) in samples.iter().enumerate() {
+        let (x, y) = (i % settings.width, i / settings.width);
+        if let Some(sample) = sample {
+            passes.record(x, y, sample);
+        }
+        passes.add_hits(x, y, *tests);
+    }
+
+    Ok(passes)
+}
+
+/// Computes the color seen through a raster position.
+type SampleShader = Box<dyn Fn(f64, f64, &mut StdRng) -> Color + Send + Sync>;
+
+/// Returns the function computing the color seen through a raster position,
+/// using the random number generator for the integrator's decisions.
+fn shader(scene: &Scene, directory: &Path, settings: &RenderSettings) -> Result<SampleShader> {
+    let world = SceneWorld::load(scene, directory)?;
+    let camera = scene.camera.unwrap_or_default();
+    let projection = Projection::new(&camera, settings.width, settings.height);
+    let integrator = settings.integrator.build(settings.depth);
+
+    Ok(Box::new(move |x, y, rng| {
+        integrator.radiance(&world, &projection.ray(x, y), rng)
+    }))
+}
+
+fn info(scene: &Scene) -> String {
+    let mut shapes = BTreeMap::new();
+    count_shapes(&scene.objects, &mut shapes);
+
+    let mut lines = Vec::new();
+    match &scene.camera {
+        Some(camera) => {
+            lines.push(format!(
+                "Camera:   {}x{}, field of view {:.3} rad",
+                camera.width, camera.height, camera.field_of_view
+            ));
+            lines.push(format!(
+                "          from ({}, {}, {}) to ({}, {}, {})",
+                camera.from.x(),
+                camera.from.y(),
+                camera.from.z(),
+                camera.to.x(),
+                camera.to.y(),
+                camera.to.z()
+            ));
+        }
+        None => lines.push("Camera:   none".to_string()),
+    }
+    if let Some(environment) = &scene.environment {
+        lines.push(format!(
+            "Sky:      {}, intensity {}",
+            environment.file, environment.intensity
+        ));
+    }
+    lines.push(format!("Lights:   {}", scene.lights.len()));
+    lines.push(format!("Objects:  {}", scene.object_count()));
+    for (shape, count) in shapes {
+        lines.push(format!("  {:<8}{}", shape, count));
+    }
+    for file in scene.referenced_files() {
+        lines.push(format!("File:     {}", file));
+    }
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+fn count_shapes(objects: &[Object], shapes: &mut BTreeMap<&'static str, usize>) {
+    for object in objects {
+        let name = match &object.shape {
+            Shape::Sphere => "sphere",
+            Shape::Plane => "plane",
+            Shape::Cube => "cube",
+            Shape::Cylinder { .. } => "cylinder",
+            Shape::Cone { .. } => "cone",
+            Shape::Group { children } => {
+                count_shapes(children, shapes);
+                "group"
+            }
+            Shape::Obj { .. } => "obj",
+        };
+        *shapes.entry(name).or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raytracing::{output::load_canvas, scene::parse_yaml};
+
+    /// An object straight in front of the camera, lit from behind it.
+    const SCENE: &str = "
+- add: camera
+  width: 9
+  height: 9
+  field-of-view: 0.5
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: light
+  at: [0, 0, -10]
+  intensity: [100, 100, 100]
+- add: obj
+  file: sphere.obj
+";
+
+    /// An octahedron, so the mesh is loaded relative to the scene file.
+    const MESH: &str = "
+v 1 0 0
+v -1 0 0
+v 0 1 0
+v 0 -1 0
+v 0 0 1
+v 0 0 -1
+f 1 3 5
+f 3 2 5
+f 2 4 5
+f 4 1 5
+f 3 1 6
+f 2 3 6
+f 4 2 6
+f 1 4 6
+";
+
+    fn temporary_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("raytracing-cli-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    fn render_args(arguments: &[&str]) -> RenderArgs {
+        let cli = Cli::try_parse_from(["raytracing", "render"].iter().chain(arguments)).unwrap();
+        let Command::Render(args) = cli.command else {
+            unreachable!()
+        };
+
+        *args
+    }
+
+    #[test]
+    fn test_watch_renders_again_after_a_change() {
+        let directory = temporary_directory("watch");
+        let scene = directory.join("scene.yml");
+        let mesh = directory.join("sphere.obj");
+        let output = directory.join("out.png");
+        fs::write(&scene, SCENE).unwrap();
+        fs::write(&mesh, MESH).unwrap();
+        let args = render_args(&[
+            scene.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+            "--watch",
+        ]);
+        let pool = thread_pool(Some(1)).unwrap();
+        let mut rendered = None;
+
+        assert!(render_if_changed(&args, &pool, &mut rendered));
+        let first = load_canvas(output.to_str().unwrap()).unwrap();
+        assert!(!render_if_changed(&args, &pool, &mut rendered));
+
+        // Changing a referenced file counts too: stretching the mesh
+        // towards the camera changes the image.
+        fs::write(&mesh, MESH.replace("v 0 0 -1", "v 0 0 -9")).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&mesh)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+
+        assert!(render_if_changed(&args, &pool, &mut rendered));
+        let second = load_canvas(output.to_str().unwrap()).unwrap();
+        assert!(!render_if_changed(&args, &pool, &mut rendered));
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_serve_defaults_to_localhost() {
+        assert_eq!(
+            render_args(&["scene.yml", "--serve"]).serve.as_deref(),
+            Some("127.0.0.1:8080")
+        );
+        assert_eq!(
+            render_args(&["scene.yml", "--serve", "0.0.0.0:9000"])
+                .serve
+                .as_deref(),
+            Some("0.0.0.0:9000")
+        );
+        assert_eq!(render_args(&["scene.yml"]).serve, None);
+    }
+
+    #[test]
+    fn test_render_on_local_worker() {
+        let directory = temporary_directory("worker");
+        fs::write(directory.join("scene.yml"), SCENE).unwrap();
+        fs::write(directory.join("sphere.obj"), MESH).unwrap();
+        let scene = directory.join("scene.yml");
+        let output = directory.join("out.png");
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let worker = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            serve_coordinator(stream, Duration::from_secs(30), prepare_job)
+        });
+
+        render_command(&render_args(&[
+            scene.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+            "--integrator",
+            "normals",
+            "--workers",
+            &address,
+        ]))
+        .unwrap();
+        worker.join().unwrap().unwrap();
+        let canvas = load_canvas(output.to_str().unwrap()).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!((canvas.width, canvas.height), (9, 9));
+        assert_ne!(canvas[(4, 4)], Color::default());
+        assert_eq!(canvas[(0, 0)], Color::default());
+    }
+
+    #[test]
+    fn test_render_passes() {
+        let directory = temporary_directory("passes");
+        fs::write(directory.join("scene.yml"), SCENE).unwrap();
+        fs::write(directory.join("sphere.obj"), MESH).unwrap();
+        let scene = directory.join("scene.yml");
+        let output = directory.join("out.exr");
+
+        render_command(&render_args(&[
+            scene.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+            "--passes",
+        ]))
+        .unwrap();
+        let load = |pass: &str| load_canvas(directory.join(pass).to_str().unwrap()).unwrap();
+        let beauty = load("out.exr");
+        let depth = load("out.depth.exr");
+        let normal = load("out.normal.exr");
+        let ids = load("out.object_id.exr");
+        let hits = load("out.hit_count.exr");
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_ne!(beauty[(4, 4)], Color::default());
+        // The camera is 5 units from the center of the octahedron.
+        assert!((4.0..5.0).contains(&depth[(4, 4)].r()));
+        assert_eq!(depth[(0, 0)].r(), f64::INFINITY);
+        assert_ne!(normal[(4, 4)], Color::default());
+        assert_eq!(normal[(0, 0)], Color::default());
+        assert_eq!(ids[(4, 4)], Color::new(0.0, 0.0, 0.0));
+        assert_eq!(ids[(0, 0)], Color::new(-1.0, -1.0, -1.0));
+        assert!(hits[(4, 4)].r() >= 1.0);
+    }
+
+    #[test]
+    fn test_render_scene_end_to_end() {
+        let directory = temporary_directory("render");
+        fs::write(directory.join("scene.yml"), SCENE).unwrap();
+        fs::write(directory.join("sphere.obj"), MESH).unwrap();
+        let scene = directory.join("scene.yml");
+        let output = directory.join("out.png");
+
+        for integrator in IntegratorKind::ALL {
+            render_command(&render_args(&[
+                scene.to_str().unwrap(),
+                "--output",
+                output.to_str().unwrap(),
+                "--integrator",
+                integrator.name(),
+            ]))
+            .unwrap();
+            let canvas = load_canvas(output.to_str().unwrap()).unwrap();
+
+            assert_eq!((canvas.width, canvas.height), (9, 9));
+            assert_ne!(canvas[(4, 4)], Color::default(), "{}", integrator);
+            // Ambient occlusion sees unoccluded misses as white.
+            let background = match integrator {
+                IntegratorKind::AmbientOcclusion => Color::new(1.0, 1.0, 1.0),
+                _ => Color::default(),
+            };
+            assert_eq!(canvas[(0, 0)], background, "{}", integrator);
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_render_with_filter() {
+        let directory = temporary_directory("filter");
+        fs::write(directory.join("scene.yml"), SCENE).unwrap();
+        fs::write(directory.join("sphere.obj"), MESH).unwrap();
+        let scene = load_scene(directory.join("scene.yml")).unwrap();
+        let args = render_args(&["scene.yml", "--samples", "4"]);
+        let settings = render_settings(&scene, &args).unwrap();
+
+        let boxed = render(&scene, &directory, &settings).unwrap();
+        let tent = RenderSettings {
+            filter: "tent:2".parse().unwrap(),
+            ..settings
+        };
+        let filtered = render(&scene, &directory, &tent).unwrap();
+
+        assert_eq!(settings.filter, Filter::default());
+        assert_ne!(filtered, boxed);
+        // The wider filter spreads the object into the neighboring pixels.
+        assert_ne!(filtered[(2, 4)], boxed[(2, 4)]);
+        assert!(
+            Cli::try_parse_from(["raytracing", "render", "scene.yml", "--filter", "tent:0"])
+                .is_err()
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_render_adaptive_with_heatmap() {
+        let directory = temporary_directory("adaptive");
+        fs::write(directory.join("scene.yml"), SCENE).unwrap();
+        fs::write(directory.join("sphere.obj"), MESH).unwrap();
+        let scene = directory.join("scene.yml");
+        let output = directory.join("out.png");
+        let heatmap = directory.join("heatmap.png");
+
+        render_command(&render_args(&[
+            scene.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+            "--samples",
+            "2",
+            "--adaptive",
+            "0.001",
+            "--max-samples",
+            "32",
+            "--heatmap",
+            heatmap.to_str().unwrap(),
+        ]))
+        .unwrap();
+        let canvas = load_canvas(output.to_str().unwrap()).unwrap();
+        let heatmap = load_canvas(heatmap.to_str().unwrap()).unwrap();
+
+        assert_eq!((heatmap.width, heatmap.height), (9, 9));
+        assert_ne!(canvas[(4, 4)], Color::default());
+        // Flat background pixels stop early, the edges of the object do not.
+        let busiest = (0..9)
+            .flat_map(|y| (0..9).map(move |x| (x, y)))
+            .map(|pixel| heatmap[pixel].luminance())
+            .fold(0.0, f64::max);
+        assert!(heatmap[(0, 0)].luminance() < busiest);
+        assert_eq!(busiest, 1.0);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_adaptive_options_are_validated() {
+        let scene = parse_yaml(SCENE).unwrap();
+
+        for arguments in [
+            ["--adaptive=-1", "--samples", "1"],
+            ["--adaptive=NaN", "--samples", "1"],
+            ["--adaptive=0.1", "--samples", "300"],
+        ] {
+            let args = render_args(&[&["scene.yml"], &arguments[..]].concat());
+            assert!(render_settings(&scene, &args).is_err(), "{:?}", arguments);
+        }
+
+        for arguments in [
+            &["scene.yml", "--max-samples", "8"][..],
+            &["scene.yml", "--heatmap", "heatmap.png"],
+            &["scene.yml", "--adaptive", "0.1", "--filter", "tent"],
+        ] {
+            let arguments = ["raytracing", "render"].iter().chain(arguments);
+            assert!(Cli::try_parse_from(arguments).is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_checkpoint_interrupt_and_resume() {
+        let directory = temporary_directory("checkpoint");
+        fs::write(directory.join("scene.yml"), SCENE).unwrap();
+        fs::write(directory.join("sphere.obj"), MESH).unwrap();
+        let scene = directory.join("scene.yml");
+        let output = directory.join("out.png");
+        let checkpoint = directory.join("render.ckpt");
+        let args = |samples: &str, extra: &[&str]| {
+            let arguments = [
+                scene.to_str().unwrap(),
+                "--output",
+                output.to_str().unwrap(),
+                "--checkpoint",
+                checkpoint.to_str().unwrap(),
+                "--samples",
+                samples,
+            ];
+            render_args(&[&arguments[..], extra].concat())
+        };
+
+        // Ctrl-C before the first pass leaves an empty checkpoint behind.
+        handle_interrupts();
+        unsafe { libc::raise(libc::SIGINT) };
+        assert!(INTERRUPTED.load(Ordering::Relaxed));
+        render_command(&args("3", &[])).unwrap();
+        assert_eq!(Accumulator::load(&checkpoint).unwrap().passes(), 0);
+
+        INTERRUPTED.store(false, Ordering::Relaxed);
+        render_command(&args("3", &[])).unwrap();
+        assert_eq!(Accumulator::load(&checkpoint).unwrap().passes(), 3);
+
+        // More samples continue the render, other settings do not.
+        render_command(&args("5", &[])).unwrap();
+        assert_eq!(Accumulator::load(&checkpoint).unwrap().passes(), 5);
+        let error = render_command(&args("5", &["--depth", "2"])).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("another scene or with other settings"));
+
+        fs::write(&scene, SCENE.replace("100, 100, 100", "50, 50, 50")).unwrap();
+        assert!(render_command(&args("3", &[])).is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
 }
@@ -1,6 +1,13 @@
+use std::{io::Cursor, path::Path};
+
 use crate::core::{Canvas, Color};
+use crate::render::{Pass, RenderPasses};
 use anyhow::{Context, Result};
-use image::{Rgb, RgbImage};
+use image::{ImageOutputFormat, Rgb, Rgb32FImage, RgbImage};
+
+mod preview;
+
+pub use preview::PreviewServer;
 
 impl From<Color> for Rgb<u8> {
     fn from(color: Color) -> Self {
@@ -12,15 +19,117 @@ impl From<Color> for Rgb<u8> {
     }
 }
 
+impl From<Color> for Rgb<f32> {
+    fn from(color: Color) -> Self {
+        Rgb([color.r() as f32, color.g() as f32, color.b() as f32])
+    }
+}
+
 pub fn save_canvas(canvas: &Canvas, filename: &str) -> Result<()> {
+    rgb_image(canvas)?.save(filename)?;
+
+    Ok(())
+}
+
+/// Encodes a canvas as a PNG file in memory.
+pub fn encode_png(canvas: &Canvas) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    rgb_image(canvas)?.write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)?;
+
+    Ok(bytes)
+}
+
+fn rgb_image(canvas: &Canvas) -> Result<RgbImage> {
     let pixels: Vec<u8> = canvas
         .iter_pixels()
         .flat_map(|&color| Rgb::from(color).0)
         .collect::<Vec<_>>();
-    let image = RgbImage::from_vec(canvas.width as u32, canvas.height as u32, pixels)
+
+    RgbImage::from_vec(canvas.width as u32, canvas.height as u32, pixels)
+        .context("Error while reading pixels")
+}
+
+/// Saves a canvas without clamping its values, e.g. as an EXR file.
+pub fn save_canvas_hdr(canvas: &Canvas, filename: &str) -> Result<()> {
+    let pixels: Vec<f32> = canvas
+        .iter_pixels()
+        .flat_map(|&color| Rgb::<f32>::from(color).0)
+        .collect::<Vec<_>>();
+    let image = Rgb32FImage::from_vec(canvas.width as u32, canvas.height as u32, pixels)
         .context("Error while reading pixels")?;
 
     image.save(filename)?;
 
     Ok(())
 }
+
+/// Loads an image into a canvas, keeping the values of HDR and EXR files
+/// above 1.
+pub fn load_canvas(filename: &str) -> Result<Canvas> {
+    let image = image::open(filename)
+        .with_context(|| format!("Error while loading {}", filename))?
+        .to_rgb32f();
+
+    let mut canvas = Canvas::new(image.width() as usize, image.height() as usize);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let [r, g, b] = pixel.0;
+        canvas[(x as usize, y as usize)] = Color::new(r as f64, g as f64, b as f64);
+    }
+
+    Ok(canvas)
+}
+
+/// Saves every pass as a separate image next to `filename`.
+///
+/// The beauty pass is written to `filename` itself, the other passes get
+/// the pass name inserted before the extension (`out.png` becomes
+/// `out.depth.png`). EXR files receive the raw pass values, all other
+/// formats a normalized preview.
+pub fn save_passes(passes: &RenderPasses, filename: &str) -> Result<()> {
+    let path = Path::new(filename);
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .context("Invalid filename")?;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .context("Missing file extension")?;
+    let hdr = extension.eq_ignore_ascii_case("exr");
+
+    for pass in Pass::ALL {
+        let pass_filename = match pass {
+            Pass::Beauty => path.to_path_buf(),
+            _ => path.with_file_name(format!("{}.{}.{}", stem, pass.name(), extension)),
+        };
+        let pass_filename = pass_filename.to_str().context("Invalid filename")?;
+
+        if hdr {
+            save_canvas_hdr(&passes.canvas(pass), pass_filename)?;
+        } else {
+            save_canvas(&passes.preview(pass), pass_filename)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hdr_canvas_round_trip() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas[(0, 0)] = Color::new(0.25, 0.5, 4.0);
+        canvas[(2, 1)] = Color::new(100.0, 0.0, 1.5);
+        let path = std::env::temp_dir().join(format!("raytracing-{}-sky.exr", std::process::id()));
+        let filename = path.to_str().unwrap();
+
+        save_canvas_hdr(&canvas, filename).unwrap();
+        let loaded = load_canvas(filename);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), canvas);
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+
+use super::encode_png;
+use crate::core::Canvas;
+use crate::render::{Progress, RenderedTile};
+
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Reloads the image and progress once per second.
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Render preview</title></head>
+<body style="background: #222; color: #ddd; font-family: sans-serif">
+<p id="progress">Waiting for the first tile</p>
+<img id="image" src="image.png" style="image-rendering: pixelated">
+<script>
+setInterval(async () => {
+  const progress = await (await fetch("progress")).json();
+  document.getElementById("progress").textContent =
+    `${progress.completed} of ${progress.total} tiles (${(progress.fraction * 100).toFixed(1)}%)`;
+  document.getElementById("image").src = `image.png?${Date.now()}`;
+}, 1000);
+</script>
+</body>
+</html>
+"#;
+
+struct State {
+    canvas: Canvas,
+    progress: Progress,
+}
+
+/// Serves an in-progress render over HTTP, so it can be watched from a
+/// browser.
+///
+/// `/` shows a page that refreshes by itself, `/image.png` returns the
+/// canvas and `/progress` the [`Progress`] as JSON. The server stops when
+/// it is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::Color;
+/// use raytracing::output::PreviewServer;
+/// use raytracing::render::{Progress, RenderedTile, Tile};
+///
+/// let server = PreviewServer::start("127.0.0.1:0", 4, 4).unwrap();
+/// println!("Watch at http://{}/", server.address());
+///
+/// let tile = Tile { x: 0, y: 0, width: 2, height: 1 };
+/// server.update(
+///     &RenderedTile { tile, pixels: vec![Color::new(1.0, 0.0, 0.0); 2] },
+///     Progress { completed: 1, total: 8 },
+/// );
+/// ```
+pub struct PreviewServer {
+    address: SocketAddr,
+    state: Arc<Mutex<State>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PreviewServer {
+    /// Starts serving a black canvas of the given size on `address`.
+    pub fn start(address: impl ToSocketAddrs, width: usize, height: usize) -> Result<Self> {
+        let listener = TcpListener::bind(address).context("Error while starting preview server")?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State {
+            canvas: Canvas::new(width, height),
+            progress: Progress {
+                completed: 0,
+                total: 0,
+            },
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let (state, stop) = (state.clone(), stop.clone());
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    let state = state.clone();
+                    thread::spawn(move || {
+                        // The client may have gone away, there is no one
+                        // to report the error to.
+                        let _ = handle_connection(stream, &state);
+                    });
+                }
+            })
+        };
+
+        Ok(PreviewServer {
+            address,
+            state,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Copies a finished tile into the served canvas.
+    pub fn update(&self, rendered: &RenderedTile, progress: Progress) {
+        let mut state = self.state.lock().unwrap();
+        for ((x, y), &color) in rendered.tile.pixels().zip(&rendered.pixels) {
+            state.canvas[(x, y)] = color;
+        }
+        state.progress = progress;
+    }
+}
+
+impl Drop for PreviewServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        // Wake up the listener so it notices the stop flag.
+        let mut address = self.address;
+        if address.ip().is_unspecified() {
+            address.set_ip(match address.ip() {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            });
+        }
+        if TcpStream::connect(address).is_ok() {
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, state: &Mutex<State>) -> Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = target.split('?').next().unwrap_or("");
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/") => ("200 OK", "text/html", INDEX_HTML.as_bytes().to_vec()),
+        ("GET", "/image.png") => {
+            let canvas = state.lock().unwrap().canvas.clone();
+            ("200 OK", "image/png", encode_png(&canvas)?)
+        }
+        ("GET", "/progress") => {
+            let progress = state.lock().unwrap().progress;
+            let json = serde_json::json!({
+                "completed": progress.completed,
+                "total": progress.total,
+                "fraction": progress.fraction(),
+            });
+            ("200 OK", "application/json", json.to_string().into_bytes())
+        }
+        ("GET", _) => ("404 Not Found", "text/plain", b"Not found\n".to_vec()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method not allowed\n".to_vec(),
+        ),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Color;
+    use crate::render::Tile;
+    use std::io::Read;
+
+    fn get(server: &PreviewServer, target: &str) -> (String, Vec<u8>) {
+        let mut stream = TcpStream::connect(server.address()).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+
+        let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8(response[..split].to_vec()).unwrap();
+        (head, response[split + 4..].to_vec())
+    }
+
+    #[test]
+    fn test_serves_progress_and_image() {
+        let server = PreviewServer::start("127.0.0.1:0", 3, 2).unwrap();
+        server.update(
+            &RenderedTile {
+                tile: Tile {
+                    x: 1,
+                    y: 1,
+                    width: 2,
+                    height: 1,
+                },
+                pixels: vec![Color::new(1.0, 0.0, 0.0); 2],
+            },
+            Progress {
+                completed: 1,
+                total: 4,
+            },
+        );
+
+        let (head, body) = get(&server, "/progress");
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        let progress: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(progress["completed"], 1);
+        assert_eq!(progress["fraction"], 0.25);
+
+        let (head, body) = get(&server, "/image.png?123");
+        assert!(head.contains("Content-Type: image/png"));
+        let image = image::load_from_memory(&body).unwrap().to_rgb8();
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(2, 1).0, [255, 0, 0]);
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_unknown_path() {
+        let server = PreviewServer::start("127.0.0.1:0", 1, 1).unwrap();
+
+        let (head, _) = get(&server, "/missing");
+        assert!(head.starts_with("HTTP/1.1 404 Not Found"));
+    }
+}
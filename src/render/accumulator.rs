@@ -0,0 +1,367 @@
+use std::{
+    fs,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use rand::{rngs::StdRng, Rng};
+
+use super::parallel::map_rows;
+use crate::core::{Canvas, Color};
+
+const MAGIC: &[u8; 4] = b"RTAC";
+const VERSION: u32 = 2;
+/// Largest number of pixels read from a checkpoint, so a corrupt header
+/// cannot make the reader allocate without bound.
+const MAX_PIXELS: usize = 1 << 28;
+
+/// Sums of samples and sample counts per pixel for renders that are built
+/// up in passes and can be stopped and resumed.
+///
+/// Every pass adds one jittered sample to every pixel. The random numbers
+/// of a pass are derived from the seed and the pass number, so the seed and
+/// the number of finished passes are the complete random state: a resumed
+/// render produces exactly the image an uninterrupted render would.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::Color;
+/// use raytracing::render::Accumulator;
+///
+/// let mut accumulator = Accumulator::new(2, 2, 42);
+/// accumulator.render_pass(|_, _, _| Color::new(0.5, 0.5, 0.5));
+/// accumulator.render_pass(|_, _, _| Color::new(1.0, 1.0, 1.0));
+///
+/// assert_eq!(accumulator.passes(), 2);
+/// assert_eq!(accumulator.to_canvas()[(1, 1)], Color::new(0.75, 0.75, 0.75));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Accumulator {
+    pub width: usize,
+    pub height: usize,
+    seed: u64,
+    passes: u64,
+    fingerprint: u64,
+    sums: Vec<Color>,
+    counts: Vec<u32>,
+}
+
+impl Accumulator {
+    /// Creates an empty accumulator.
+    pub fn new(width: usize, height: usize, seed: u64) -> Accumulator {
+        Accumulator {
+            width,
+            height,
+            seed,
+            passes: 0,
+            fingerprint: 0,
+            sums: vec![Color::default(); width * height],
+            counts: vec![0; width * height],
+        }
+    }
+
+    /// Sets the fingerprint stored with the samples, which identifies what
+    /// is being rendered so a checkpoint is not resumed for another scene.
+    pub fn with_fingerprint(mut self, fingerprint: u64) -> Accumulator {
+        self.fingerprint = fingerprint;
+        self
+    }
+
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Returns the number of finished passes.
+    pub fn passes(&self) -> u64 {
+        self.passes
+    }
+
+    pub fn count_at(&self, x: usize, y: usize) -> u32 {
+        self.counts[y * self.width + x]
+    }
+
+    /// Adds a sample to the given pixel.
+    pub fn add_sample(&mut self, x: usize, y: usize, color: Color) {
+        let index = y * self.width + x;
+        self.sums[index] = self.sums[index] + color;
+        self.counts[index] += 1;
+    }
+
+    /// Renders one sample for every pixel in parallel and adds it.
+    ///
+    /// `shade` receives the raster position of a sample and the random
+    /// number generator of its row, which is the random state for the
+    /// integrator, and returns the sample's color.
+    pub fn render_pass(&mut self, shade: impl Fn(f64, f64, &mut StdRng) -> Color + Sync) {
+        let width = self.width;
+        let seed = self.seed ^ self.passes.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        let rows = map_rows(0..self.height, seed, |y, rng| {
+            (0..width)
+                .map(|x| {
+                    let (dx, dy) = rng.gen::<(f64, f64)>();
+                    shade(x as f64 + dx, y as f64 + dy, rng)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        for (y, row) in rows.into_iter().enumerate() {
+            for (x, color) in row.into_iter().enumerate() {
+                self.add_sample(x, y, color);
+            }
+        }
+        self.passes += 1;
+    }
+
+    /// Returns the mean of the samples of every pixel.
+    pub fn to_canvas(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let index = y * self.width + x;
+                if self.counts[index] > 0 {
+                    canvas[(x, y)] = self.sums[index] * (1.0 / self.counts[index] as f64);
+                }
+            }
+        }
+
+        canvas
+    }
+
+    /// Writes the accumulator to `path`.
+    ///
+    /// The data is written to a temporary file that replaces `path` when
+    /// complete, so an interrupted save leaves the previous checkpoint
+    /// intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let temporary = path.with_extension("partial");
+
+        let mut writer = BufWriter::new(
+            fs::File::create(&temporary)
+                .with_context(|| format!("Error while creating {}", temporary.display()))?,
+        );
+        self.write(&mut writer)
+            .and_then(|_| writer.flush())
+            .with_context(|| format!("Error while writing {}", temporary.display()))?;
+        drop(writer);
+
+        fs::rename(&temporary, path)
+            .with_context(|| format!("Error while writing {}", path.display()))
+    }
+
+    /// Reads an accumulator written by [`Accumulator::save`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Accumulator> {
+        let path = path.as_ref();
+        let file = fs::File::open(path)
+            .with_context(|| format!("Error while reading {}", path.display()))?;
+
+        Accumulator::read(&mut BufReader::new(file))
+            .with_context(|| format!("Invalid checkpoint {}", path.display()))
+    }
+
+    fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        for value in [
+            self.width as u64,
+            self.height as u64,
+            self.seed,
+            self.passes,
+            self.fingerprint,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for (sum, count) in self.sums.iter().zip(&self.counts) {
+            for channel in [sum.r(), sum.g(), sum.b()] {
+                writer.write_all(&channel.to_le_bytes())?;
+            }
+            writer.write_all(&count.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn read(reader: &mut impl Read) -> Result<Accumulator> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("Not a checkpoint file");
+        }
+        let version = u32::from_le_bytes(read_bytes(reader)?);
+        if version != VERSION {
+            bail!("Unsupported checkpoint version {}", version);
+        }
+
+        let width = u64::from_le_bytes(read_bytes(reader)?) as usize;
+        let height = u64::from_le_bytes(read_bytes(reader)?) as usize;
+        match width.checked_mul(height) {
+            Some(pixels) if pixels <= MAX_PIXELS => {}
+            _ => bail!("Invalid checkpoint size {}x{}", width, height),
+        }
+        let mut accumulator =
+            Accumulator::new(width, height, u64::from_le_bytes(read_bytes(reader)?));
+        accumulator.passes = u64::from_le_bytes(read_bytes(reader)?);
+        accumulator.fingerprint = u64::from_le_bytes(read_bytes(reader)?);
+
+        for index in 0..width * height {
+            let [r, g, b] = [0; 3].map(|_| read_bytes(reader).map(f64::from_le_bytes));
+            accumulator.sums[index] = Color::new(r?, g?, b?);
+            accumulator.counts[index] = u32::from_le_bytes(read_bytes(reader)?);
+        }
+
+        Ok(accumulator)
+    }
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+/// Where and how often a progressive render saves its state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint<'a> {
+    pub path: &'a Path,
+    pub interval: Duration,
+}
+
+/// Adds passes to the accumulator until it has `passes` passes or `cancel`
+/// is set, saving a checkpoint at the given interval and when stopping.
+///
+/// Returns whether all passes were rendered.
+pub fn render_progressive(
+    accumulator: &mut Accumulator,
+    passes: u64,
+    checkpoint: Option<&Checkpoint>,
+    cancel: &AtomicBool,
+    shade: impl Fn(f64, f64, &mut StdRng) -> Color + Sync,
+) -> Result<bool> {
+    let mut last_save = Instant::now();
+
+    while accumulator.passes() < passes && !cancel.load(Ordering::Relaxed) {
+        accumulator.render_pass(&shade);
+
+        if let Some(checkpoint) = checkpoint {
+            if last_save.elapsed() >= checkpoint.interval {
+                accumulator.save(checkpoint.path)?;
+                last_save = Instant::now();
+            }
+        }
+    }
+
+    if let Some(checkpoint) = checkpoint {
+        accumulator.save(checkpoint.path)?;
+    }
+
+    Ok(accumulator.passes() >= passes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noisy(x: f64, y: f64, rng: &mut StdRng) -> Color {
+        Color::new(x.fract(), y.fract(), rng.gen())
+    }
+
+    fn temporary_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("raytracing-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_accumulator_save_load() {
+        let path = temporary_path("save-load.ckpt");
+        let mut accumulator = Accumulator::new(3, 2, 1).with_fingerprint(0xfeed);
+        accumulator.render_pass(noisy);
+        accumulator.add_sample(2, 1, Color::new(1.0, 2.0, 3.0));
+
+        accumulator.save(&path).unwrap();
+        let loaded = Accumulator::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, accumulator);
+        assert_eq!(loaded.count_at(2, 1), 2);
+        assert_eq!(loaded.fingerprint(), 0xfeed);
+    }
+
+    #[test]
+    fn test_accumulator_load_invalid() {
+        let path = temporary_path("invalid.ckpt");
+        fs::write(&path, b"not a checkpoint").unwrap();
+
+        let error = Accumulator::load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            format!("{:#}", error).split(": ").last(),
+            Some("Not a checkpoint file")
+        );
+    }
+
+    #[test]
+    fn test_accumulator_load_oversized() {
+        let path = temporary_path("oversized.ckpt");
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        for value in [u64::MAX, 2, 0, 0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        fs::write(&path, bytes).unwrap();
+
+        let error = Accumulator::load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert!(format!("{:#}", error).contains("Invalid checkpoint size"));
+    }
+
+    #[test]
+    fn test_render_progressive_resume_matches_uninterrupted() {
+        let path = temporary_path("resume.ckpt");
+        let checkpoint = Checkpoint {
+            path: &path,
+            interval: Duration::from_secs(3600),
+        };
+
+        let mut uninterrupted = Accumulator::new(4, 3, 9);
+        render_progressive(&mut uninterrupted, 6, None, &AtomicBool::new(false), noisy).unwrap();
+
+        let mut first = Accumulator::new(4, 3, 9);
+        render_progressive(
+            &mut first,
+            2,
+            Some(&checkpoint),
+            &AtomicBool::new(false),
+            noisy,
+        )
+        .unwrap();
+        let mut resumed = Accumulator::load(&path).unwrap();
+        let done = render_progressive(
+            &mut resumed,
+            6,
+            Some(&checkpoint),
+            &AtomicBool::new(false),
+            noisy,
+        )
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(done);
+        assert_eq!(resumed, uninterrupted);
+    }
+
+    #[test]
+    fn test_render_progressive_cancel() {
+        let mut accumulator = Accumulator::new(2, 2, 0);
+        let done =
+            render_progressive(&mut accumulator, 10, None, &AtomicBool::new(true), noisy).unwrap();
+
+        assert!(!done);
+        assert_eq!(accumulator.passes(), 0);
+    }
+}
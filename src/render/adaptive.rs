@@ -0,0 +1,262 @@
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+use super::{parallel::map_rows, stratified_offsets};
+use crate::core::{Canvas, Color};
+
+/// Settings for sampling pixels until their error estimate is low enough.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdaptiveSampling {
+    /// Number of stratified samples every pixel receives.
+    pub min_samples: usize,
+    /// Upper bound of samples for a single pixel.
+    pub max_samples: usize,
+    /// Number of extra samples taken per refinement step.
+    pub batch_size: usize,
+    /// Largest acceptable standard error of the pixel luminance.
+    pub threshold: f64,
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        AdaptiveSampling {
+            min_samples: 4,
+            max_samples: 256,
+            batch_size: 4,
+            threshold: 0.01,
+        }
+    }
+}
+
+/// Running mean and variance of the samples of a single pixel.
+///
+/// Uses Welford's algorithm so the statistics can be updated one sample at
+/// a time without storing the samples.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::Color;
+/// use raytracing::render::PixelStats;
+///
+/// let mut stats = PixelStats::default();
+/// stats.add(Color::new(0.0, 0.0, 0.0));
+/// stats.add(Color::new(1.0, 1.0, 1.0));
+///
+/// assert_eq!(stats.mean(), Color::new(0.5, 0.5, 0.5));
+/// assert_eq!(stats.variance(), 0.5);
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct PixelStats {
+    count: usize,
+    mean: Color,
+    mean_luminance: f64,
+    m2: f64,
+}
+
+impl PixelStats {
+    /// Adds a sample to the statistics.
+    pub fn add(&mut self, color: Color) {
+        self.count += 1;
+        let n = self.count as f64;
+
+        self.mean = self.mean + (color - self.mean) * (1.0 / n);
+
+        let luminance = color.luminance();
+        let delta = luminance - self.mean_luminance;
+        self.mean_luminance += delta / n;
+        self.m2 += delta * (luminance - self.mean_luminance);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> Color {
+        self.mean
+    }
+
+    /// Returns the sample variance of the luminance.
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.m2 / (self.count - 1) as f64
+        }
+    }
+
+    /// Returns the estimated standard error of the mean luminance.
+    pub fn error(&self) -> f64 {
+        if self.count < 2 {
+            f64::INFINITY
+        } else {
+            (self.variance() / self.count as f64).sqrt()
+        }
+    }
+}
+
+/// The result of an adaptive render.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveRender {
+    pub canvas: Canvas,
+    stats: Vec<PixelStats>,
+    max_samples: usize,
+}
+
+impl AdaptiveRender {
+    /// Returns the statistics of the given pixel.
+    pub fn stats_at(&self, x: usize, y: usize) -> &PixelStats {
+        &self.stats[y * self.canvas.width + x]
+    }
+
+    /// Returns the number of samples per pixel relative to the maximum as a
+    /// grayscale canvas.
+    ///
+    /// Pixels can receive more than `max_samples` when `min_samples` is
+    /// larger, so the largest count taken is used when it is higher.
+    pub fn sample_heatmap(&self) -> Canvas {
+        let max = self
+            .stats
+            .iter()
+            .map(PixelStats::count)
+            .fold(self.max_samples, usize::max);
+        let mut heatmap = Canvas::new(self.canvas.width, self.canvas.height);
+        if max == 0 {
+            return heatmap;
+        }
+
+        for y in 0..heatmap.height {
+            for x in 0..heatmap.width {
+                let value = self.stats_at(x, y).count() as f64 / max as f64;
+                heatmap[(x, y)] = Color::new(value, value, value);
+            }
+        }
+
+        heatmap
+    }
+}
+
+/// Renders a canvas, sampling every pixel until its error estimate drops
+/// below the threshold or it reached the maximum number of samples.
+///
+/// `shade` receives the raster position of a sample and a random number
+/// generator, and returns its color. Rows are rendered in parallel; the result only depends on `seed`.
+pub fn render_adaptive(
+    width: usize,
+    height: usize,
+    settings: &AdaptiveSampling,
+    seed: u64,
+    shade: impl Fn(f64, f64, &mut StdRng) -> Color + Sync,
+) -> AdaptiveRender {
+    let rows = map_rows(0..height, seed, |y, rng| {
+        (0..width)
+            .map(|x| {
+                let mut pixel = PixelStats::default();
+                let sample = |pixel: &mut PixelStats, (dx, dy): (f64, f64), rng: &mut StdRng| {
+                    pixel.add(shade(x as f64 + dx, y as f64 + dy, rng));
+                };
+
+                for offset in stratified_offsets(settings.min_samples, true, rng) {
+                    sample(&mut pixel, offset, rng);
+                }
+
+                while pixel.count() < settings.max_samples && pixel.error() > settings.threshold {
+                    let batch = settings
+                        .batch_size
+                        .max(1)
+                        .min(settings.max_samples - pixel.count());
+                    for offset in stratified_offsets(batch, true, rng) {
+                        sample(&mut pixel, offset, rng);
+                    }
+                }
+
+                pixel
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let stats: Vec<_> = rows.into_iter().flatten().collect();
+    let mut canvas = Canvas::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            canvas[(x, y)] = stats[y * width + x].mean();
+        }
+    }
+
+    AdaptiveRender {
+        canvas,
+        stats,
+        max_samples: settings.max_samples,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_pixel_stats_error_decreases() {
+        let mut stats = PixelStats::default();
+        assert_eq!(stats.error(), f64::INFINITY);
+
+        for i in 0..10 {
+            stats.add(Color::new(1.0, 1.0, 1.0) * (i % 2) as f64);
+        }
+        let error = stats.error();
+        for i in 0..10 {
+            stats.add(Color::new(1.0, 1.0, 1.0) * (i % 2) as f64);
+        }
+
+        assert!(stats.error() < error);
+        assert_abs_diff_eq!(stats.mean(), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_render_adaptive_flat_pixels_use_min_samples() {
+        let settings = AdaptiveSampling::default();
+        let render = render_adaptive(2, 2, &settings, 0, |_, _, _| Color::new(0.2, 0.2, 0.2));
+
+        assert_eq!(render.stats_at(1, 1).count(), settings.min_samples);
+        assert_eq!(render.canvas[(1, 1)], Color::new(0.2, 0.2, 0.2));
+    }
+
+    #[test]
+    fn test_render_adaptive_edges_get_more_samples() {
+        let settings = AdaptiveSampling {
+            max_samples: 64,
+            ..Default::default()
+        };
+        let render = render_adaptive(2, 1, &settings, 0, |x, _, _| {
+            if x < 1.5 {
+                Color::new(1.0, 1.0, 1.0)
+            } else {
+                Color::default()
+            }
+        });
+
+        assert_eq!(render.stats_at(0, 0).count(), settings.min_samples);
+        assert_eq!(render.stats_at(1, 0).count(), settings.max_samples);
+        assert_eq!(render.sample_heatmap()[(1, 0)], Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_sample_heatmap_without_samples() {
+        let settings = AdaptiveSampling {
+            min_samples: 0,
+            max_samples: 0,
+            ..Default::default()
+        };
+        let render = render_adaptive(2, 1, &settings, 0, |_, _, _| Color::new(1.0, 1.0, 1.0));
+
+        assert_eq!(render.sample_heatmap()[(0, 0)], Color::default());
+
+        let settings = AdaptiveSampling {
+            max_samples: 0,
+            ..Default::default()
+        };
+        let render = render_adaptive(2, 1, &settings, 0, |_, _, _| Color::new(1.0, 1.0, 1.0));
+
+        assert_eq!(render.sample_heatmap()[(0, 0)], Color::new(1.0, 1.0, 1.0));
+    }
+}
@@ -0,0 +1,463 @@
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{tiles, Progress, RenderedTile, Tile, TileSettings};
+use crate::core::{Canvas, Color};
+
+/// Largest job accepted by a worker, to reject garbage early.
+const MAX_JOB_SIZE: usize = 1 << 26;
+/// Largest message besides jobs and rendered tiles.
+const MAX_CONTROL_SIZE: usize = 1 << 16;
+/// Upper bound of the JSON size of one rendered pixel, three numbers of at
+/// most 24 characters each with separators.
+const MAX_PIXEL_SIZE: usize = 80;
+
+/// Returns the color of a pixel, as prepared by a worker for a job.
+pub type PixelShader = Box<dyn Fn(usize, usize) -> Color + Send + Sync>;
+
+/// A frame to be rendered by workers.
+///
+/// The payload is opaque to the coordinator and workers, and describes what
+/// to render, e.g. a serialized scene and render settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub width: usize,
+    pub height: usize,
+    pub payload: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Message {
+    Job(Job),
+    Tile(Tile),
+    Rendered(RenderedTile),
+    Ready,
+    Error(String),
+    Done,
+}
+
+struct State {
+    pending: VecDeque<Tile>,
+    canvas: Canvas,
+    completed: usize,
+    errors: Vec<String>,
+}
+
+/// Renders a frame by handing out tiles to the workers at the given
+/// addresses and merging the rendered tiles into one canvas.
+///
+/// Every worker gets one tile at a time. When a worker fails, or does not
+/// answer within `timeout`, its tile is handed to the remaining workers.
+/// The timeout covers rendering a whole tile on the worker, so it has to be
+/// longer than the slowest tile takes.
+/// Fails when no worker is left while tiles are still missing.
+///
+/// `on_tile` is called for every merged tile, from the connection threads.
+pub fn render_distributed<A: ToSocketAddrs + Sync>(
+    workers: &[A],
+    job: &Job,
+    settings: &TileSettings,
+    timeout: Duration,
+    on_tile: impl Fn(&RenderedTile, Progress) + Sync,
+) -> Result<Canvas> {
+    let tiles = tiles(job.width, job.height, settings.tile_size, settings.order);
+    let total = tiles.len();
+    let state = Mutex::new(State {
+        pending: tiles.into(),
+        canvas: Canvas::new(job.width, job.height),
+        completed: 0,
+        errors: Vec::new(),
+    });
+    let changed = Condvar::new();
+
+    thread::scope(|scope| {
+        for address in workers {
+            let (state, changed, on_tile) = (&state, &changed, &on_tile);
+            scope.spawn(move || {
+                if let Err(error) =
+                    coordinate_worker(address, job, timeout, total, state, changed, on_tile)
+                {
+                    state.lock().unwrap().errors.push(format!("{:#}", error));
+                }
+                changed.notify_all();
+            });
+        }
+    });
+
+    let state = state.into_inner().unwrap();
+    if state.completed < total {
+        bail!(
+            "{} of {} tiles could not be rendered: {}",
+            total - state.completed,
+            total,
+            state.errors.join("; ")
+        );
+    }
+
+    Ok(state.canvas)
+}
+
+fn coordinate_worker<A: ToSocketAddrs>(
+    address: &A,
+    job: &Job,
+    timeout: Duration,
+    total: usize,
+    state: &Mutex<State>,
+    changed: &Condvar,
+    on_tile: &(impl Fn(&RenderedTile, Progress) + Sync),
+) -> Result<()> {
+    let address = address
+        .to_socket_addrs()?
+        .next()
+        .context("Worker address did not resolve")?;
+    let mut stream = TcpStream::connect_timeout(&address, timeout)
+        .with_context(|| format!("Error while connecting to worker {}", address))?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    send(&mut stream, &Message::Job(job.clone()))?;
+    match receive(&mut stream, MAX_CONTROL_SIZE)? {
+        Message::Ready => {}
+        Message::Error(error) => bail!("Worker {} rejected the job: {}", address, error),
+        message => bail!("Unexpected answer {:?} from worker {}", message, address),
+    }
+
+    loop {
+        let tile = {
+            let mut state = state.lock().unwrap();
+            loop {
+                if let Some(tile) = state.pending.pop_front() {
+                    break tile;
+                }
+                if state.completed == total {
+                    drop(state);
+                    return send(&mut stream, &Message::Done);
+                }
+                // Tiles are in flight on other workers and may come back.
+                state = changed.wait(state).unwrap();
+            }
+        };
+
+        let size = tile.width * tile.height * MAX_PIXEL_SIZE + MAX_CONTROL_SIZE;
+        let rendered = send(&mut stream, &Message::Tile(tile))
+            .and_then(|_| receive(&mut stream, size))
+            .and_then(|message| match message {
+                Message::Rendered(rendered)
+                    if rendered.tile == tile
+                        && rendered.pixels.len() == tile.width * tile.height =>
+                {
+                    Ok(rendered)
+                }
+                Message::Error(error) => Err(anyhow!(error)),
+                _ => Err(anyhow!("Unexpected answer for tile {:?}", tile)),
+            });
+
+        let rendered = match rendered {
+            Ok(rendered) => rendered,
+            Err(error) => {
+                state.lock().unwrap().pending.push_front(tile);
+                changed.notify_all();
+                return Err(error.context(format!("Worker {} failed", address)));
+            }
+        };
+
+        let progress = {
+            let mut state = state.lock().unwrap();
+            for ((x, y), &color) in tile.pixels().zip(&rendered.pixels) {
+                state.canvas[(x, y)] = color;
+            }
+            state.completed += 1;
+            Progress {
+                completed: state.completed,
+                total,
+            }
+        };
+        changed.notify_all();
+        on_tile(&rendered, progress);
+    }
+}
+
+/// Serves a coordinator connected to a worker: receives the job, prepares
+/// a shader for it with `prepare` and renders tiles until the coordinator
+/// is done.
+///
+/// Gives up when the coordinator does not send anything within `timeout`,
+/// which should be at least the time the coordinator waits for a tile.
+/// Tiles reaching outside the frame of the job are rejected.
+pub fn serve_coordinator(
+    mut stream: TcpStream,
+    timeout: Duration,
+    prepare: impl Fn(&Job) -> Result<PixelShader>,
+) -> Result<()> {
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let Message::Job(job) = receive(&mut stream, MAX_JOB_SIZE)? else {
+        bail!("Expected a job");
+    };
+    let shade = match prepare(&job) {
+        Ok(shade) => shade,
+        Err(error) => {
+            send(&mut stream, &Message::Error(format!("{:#}", error)))?;
+            return Err(error);
+        }
+    };
+    send(&mut stream, &Message::Ready)?;
+
+    loop {
+        match receive(&mut stream, MAX_CONTROL_SIZE)? {
+            Message::Tile(tile) if !inside(&tile, &job) => {
+                let error = format!(
+                    "Tile {:?} is outside the {}x{} frame",
+                    tile, job.width, job.height
+                );
+                send(&mut stream, &Message::Error(error.clone()))?;
+                bail!(error);
+            }
+            Message::Tile(tile) => {
+                let pixels = tile
+                    .pixels()
+                    .collect::<Vec<_>>()
+                    .into_par_iter()
+                    .map(|(x, y)| shade(x, y))
+                    .collect();
+                send(
+                    &mut stream,
+                    &Message::Rendered(RenderedTile { tile, pixels }),
+                )?;
+            }
+            Message::Done => return Ok(()),
+            message => bail!("Unexpected message {:?}", message),
+        }
+    }
+}
+
+fn inside(tile: &Tile, job: &Job) -> bool {
+    tile.x
+        .checked_add(tile.width)
+        .is_some_and(|right| right <= job.width)
+        && tile
+            .y
+            .checked_add(tile.height)
+            .is_some_and(|bottom| bottom <= job.height)
+}
+
+/// Writes a message as a little endian length followed by JSON.
+fn send(stream: &mut TcpStream, message: &Message) -> Result<()> {
+    let bytes = serde_json::to_vec(message)?;
+    stream.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    stream.write_all(&bytes)?;
+
+    Ok(())
+}
+
+/// Reads a message of at most `max_size` bytes.
+fn receive(stream: &mut TcpStream, max_size: usize) -> Result<Message> {
+    let mut length = [0; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as usize;
+    if length > max_size {
+        bail!("Message of {} bytes is too large", length);
+    }
+
+    let mut bytes = vec![0; length];
+    stream.read_exact(&mut bytes)?;
+
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{SocketAddr, TcpListener};
+
+    fn shade(job: &Job) -> Result<PixelShader> {
+        let scale: f64 = job.payload.parse()?;
+        Ok(Box::new(move |x, y| {
+            Color::new(x as f64 * scale, y as f64, 0.0)
+        }))
+    }
+
+    /// Starts a worker serving `connections` coordinators.
+    fn worker(connections: usize) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(connections) {
+                let _ = serve_coordinator(stream.unwrap(), Duration::from_secs(5), shade);
+            }
+        });
+
+        address
+    }
+
+    /// Starts a worker that dies after receiving its first tile.
+    fn failing_worker() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            receive(&mut stream, MAX_JOB_SIZE).unwrap();
+            send(&mut stream, &Message::Ready).unwrap();
+            receive(&mut stream, MAX_CONTROL_SIZE).unwrap();
+        });
+
+        address
+    }
+
+    fn job() -> Job {
+        Job {
+            width: 30,
+            height: 20,
+            payload: "2".to_string(),
+        }
+    }
+
+    fn settings() -> TileSettings {
+        TileSettings {
+            tile_size: 8,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_distributed() {
+        let workers = [worker(1), worker(1)];
+        let progress = Mutex::new(Vec::new());
+
+        let canvas = render_distributed(
+            &workers,
+            &job(),
+            &settings(),
+            Duration::from_secs(5),
+            |_, p| progress.lock().unwrap().push(p.completed),
+        )
+        .unwrap();
+
+        assert_eq!(canvas[(29, 19)], Color::new(58.0, 19.0, 0.0));
+        assert_eq!(progress.into_inner().unwrap().len(), 12);
+    }
+
+    #[test]
+    fn test_render_distributed_reassigns_tiles_of_dead_workers() {
+        let workers = [failing_worker(), worker(1)];
+
+        let canvas = render_distributed(
+            &workers,
+            &job(),
+            &settings(),
+            Duration::from_secs(5),
+            |_, _| {},
+        )
+        .unwrap();
+
+        for (x, y) in [(0, 0), (8, 0), (29, 19)] {
+            assert_eq!(canvas[(x, y)], Color::new(x as f64 * 2.0, y as f64, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_render_distributed_without_workers_left() {
+        let workers = [failing_worker()];
+
+        let error = render_distributed(
+            &workers,
+            &job(),
+            &settings(),
+            Duration::from_secs(5),
+            |_, _| {},
+        )
+        .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("12 of 12 tiles could not be rendered"));
+    }
+
+    #[test]
+    fn test_render_distributed_reports_worker_errors() {
+        let workers = [worker(1)];
+        let job = Job {
+            payload: "not a number".to_string(),
+            ..job()
+        };
+
+        let error = render_distributed(
+            &workers,
+            &job,
+            &settings(),
+            Duration::from_secs(5),
+            |_, _| {},
+        )
+        .unwrap_err();
+
+        assert!(error.to_string().contains("invalid float literal"));
+    }
+
+    #[test]
+    fn test_serve_coordinator_times_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _coordinator = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        let error = serve_coordinator(stream, Duration::from_millis(50), shade).unwrap_err();
+
+        let kind = error.downcast_ref::<std::io::Error>().unwrap().kind();
+        assert!(matches!(
+            kind,
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+        ));
+    }
+
+    #[test]
+    fn test_oversized_messages_are_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut coordinator = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        coordinator
+            .write_all(&((MAX_JOB_SIZE + 1) as u32).to_le_bytes())
+            .unwrap();
+
+        let error = serve_coordinator(stream, Duration::from_secs(5), shade).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            format!("Message of {} bytes is too large", MAX_JOB_SIZE + 1)
+        );
+    }
+
+    #[test]
+    fn test_serve_coordinator_rejects_tiles_outside_the_frame() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut coordinator = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let worker =
+            thread::spawn(move || serve_coordinator(stream, Duration::from_secs(5), shade));
+
+        send(&mut coordinator, &Message::Job(job())).unwrap();
+        receive(&mut coordinator, MAX_CONTROL_SIZE).unwrap();
+        let tile = Tile {
+            x: 24,
+            y: 0,
+            width: 8,
+            height: usize::MAX,
+        };
+        send(&mut coordinator, &Message::Tile(tile)).unwrap();
+
+        let Message::Error(error) = receive(&mut coordinator, MAX_CONTROL_SIZE).unwrap() else {
+            panic!("Expected an error");
+        };
+        assert!(error.contains("is outside the 30x20 frame"));
+        assert!(worker.join().unwrap().is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use rand::rngs::StdRng;
+
+use super::{parallel::map_rows, stratified_offsets, Filter};
+use crate::core::{Canvas, Color};
+
+/// Settings for rendering multiple samples per pixel.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Supersampling {
+    pub samples_per_pixel: usize,
+    /// Whether samples are jittered inside their stratum.
+    pub jitter: bool,
+    pub filter: Filter,
+}
+
+impl Default for Supersampling {
+    /// One sample through the center of every pixel.
+    fn default() -> Self {
+        Supersampling {
+            samples_per_pixel: 1,
+            jitter: false,
+            filter: Filter::default(),
+        }
+    }
+}
+
+/// Accumulates filtered samples and resolves them into a [`Canvas`].
+///
+/// Sample positions are given in continuous raster coordinates, where pixel
+/// `(x, y)` covers `[x, x + 1) × [y, y + 1)` and has its center at
+/// `(x + 0.5, y + 0.5)`.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::Color;
+/// use raytracing::render::{Film, Filter};
+///
+/// let mut film = Film::new(2, 1, Filter::default());
+///
+/// film.add_sample(0.25, 0.5, Color::new(1.0, 0.0, 0.0));
+/// film.add_sample(0.75, 0.5, Color::new(0.0, 0.0, 1.0));
+///
+/// assert_eq!(film.to_canvas()[(0, 0)], Color::new(0.5, 0.0, 0.5));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Film {
+    pub width: usize,
+    pub height: usize,
+    filter: Filter,
+    sums: Vec<Color>,
+    weights: Vec<f64>,
+}
+
+impl Film {
+    /// Creates an empty film of the given width and height.
+    pub fn new(width: usize, height: usize, filter: Filter) -> Film {
+        Film {
+            width,
+            height,
+            filter,
+            sums: vec![Color::default(); width * height],
+            weights: vec![0.0; width * height],
+        }
+    }
+
+    /// Adds a sample at raster position `(x, y)` to every pixel within the
+    /// filter radius.
+    pub fn add_sample(&mut self, x: f64, y: f64, color: Color) {
+        let radius = self.filter.radius();
+        let x_min = (x - 0.5 - radius).ceil().max(0.0) as usize;
+        let y_min = (y - 0.5 - radius).ceil().max(0.0) as usize;
+        let x_max = ((x - 0.5 + radius).floor() as isize).min(self.width as isize - 1);
+        let y_max = ((y - 0.5 + radius).floor() as isize).min(self.height as isize - 1);
+
+        for py in y_min as isize..=y_max {
+            for px in x_min as isize..=x_max {
+                let weight = self
+                    .filter
+                    .evaluate(x - (px as f64 + 0.5), y - (py as f64 + 0.5));
+                if weight == 0.0 {
+                    continue;
+                }
+
+                let index = py as usize * self.width + px as usize;
+                self.sums[index] = self.sums[index] + color * weight;
+                self.weights[index] += weight;
+            }
+        }
+    }
+
+    /// Returns the weighted average of the samples for every pixel.
+    ///
+    /// Pixels without any (non-zero weighted) sample are black.
+    pub fn to_canvas(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let index = y * self.width + x;
+                if self.weights[index] != 0.0 {
+                    canvas[(x, y)] = self.sums[index] * (1.0 / self.weights[index]);
+                }
+            }
+        }
+
+        canvas
+    }
+}
+
+/// Renders a canvas by sampling `shade` at sub-pixel raster positions.
+///
+/// `shade` receives the raster position of a sample and a random number
+/// generator for the sample's own random decisions, and returns its color,
+/// e.g. by tracing a camera ray through that position. Samples are taken in
+/// parallel, a band of rows at a time, and splatted in order, so the result
+/// only depends on `seed`.
+pub fn render_supersampled(
+    width: usize,
+    height: usize,
+    settings: &Supersampling,
+    seed: u64,
+    shade: impl Fn(f64, f64, &mut StdRng) -> Color + Sync,
+) -> Canvas {
+    let mut film = Film::new(width, height, settings.filter);
+    let band = rayon::current_num_threads() * 2;
+
+    for start in (0..height).step_by(band) {
+        let rows = map_rows(start..(start + band).min(height), seed, |y, rng| {
+            let mut samples = Vec::with_capacity(width * settings.samples_per_pixel);
+            for x in 0..width {
+                for (dx, dy) in stratified_offsets(settings.samples_per_pixel, settings.jitter, rng)
+                {
+                    let (sx, sy) = (x as f64 + dx, y as f64 + dy);
+                    samples.push((sx, sy, shade(sx, sy, rng)));
+                }
+            }
+
+            samples
+        });
+
+        for (x, y, color) in rows.into_iter().flatten() {
+            film.add_sample(x, y, color);
+        }
+    }
+
+    film.to_canvas()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_film_tent_filter_spreads_samples() {
+        let mut film = Film::new(3, 1, Filter::Tent { radius: 1.5 });
+        film.add_sample(1.5, 0.5, Color::new(1.0, 1.0, 1.0));
+
+        assert_abs_diff_eq!(film.weights[0], 1.0 / 3.0);
+        assert_abs_diff_eq!(film.weights[1], 1.0);
+        assert_abs_diff_eq!(film.weights[2], 1.0 / 3.0);
+        assert_abs_diff_eq!(film.to_canvas()[(0, 0)], Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_film_empty_pixels_black() {
+        let film = Film::new(2, 2, Filter::default());
+
+        assert_eq!(film.to_canvas(), Canvas::new(2, 2));
+    }
+
+    #[test]
+    fn test_render_supersampled_default_samples_centers() {
+        let positions = Mutex::new(Vec::new());
+        render_supersampled(2, 1, &Supersampling::default(), 0, |x, y, _| {
+            positions.lock().unwrap().push((x, y));
+            Color::default()
+        });
+
+        assert_eq!(
+            positions.into_inner().unwrap(),
+            vec![(0.5, 0.5), (1.5, 0.5)]
+        );
+    }
+
+    #[test]
+    fn test_render_supersampled_antialiases_edge() {
+        let settings = Supersampling {
+            samples_per_pixel: 16,
+            jitter: false,
+            filter: Filter::default(),
+        };
+        let canvas = render_supersampled(2, 1, &settings, 0, |x, _, _| {
+            if x < 1.5 {
+                Color::new(1.0, 1.0, 1.0)
+            } else {
+                Color::default()
+            }
+        });
+
+        assert_eq!(canvas[(0, 0)], Color::new(1.0, 1.0, 1.0));
+        assert_eq!(canvas[(1, 0)], Color::new(0.5, 0.5, 0.5));
+    }
+}
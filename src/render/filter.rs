@@ -0,0 +1,254 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Context, Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// A pixel reconstruction filter.
+///
+/// Filters weigh the contribution of a sample to the pixels around it based
+/// on the offset between the sample and the pixel center. Every filter is
+/// separable and has a finite `radius` in pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Filter {
+    /// Equal weight for every sample inside the radius.
+    Box { radius: f64 },
+    /// Weight falls off linearly towards the radius.
+    Tent { radius: f64 },
+    /// Gaussian falloff, shifted so the weight reaches zero at the radius.
+    Gaussian { radius: f64, alpha: f64 },
+    /// The Mitchell-Netravali cubic with parameters `b` and `c`.
+    Mitchell { radius: f64, b: f64, c: f64 },
+}
+
+impl Filter {
+    /// Returns the radius of the filter in pixels.
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Filter::Box { radius }
+            | Filter::Tent { radius }
+            | Filter::Gaussian { radius, .. }
+            | Filter::Mitchell { radius, .. } => radius,
+        }
+    }
+
+    /// Returns the name used to select the filter.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Filter::Box { .. } => "box",
+            Filter::Tent { .. } => "tent",
+            Filter::Gaussian { .. } => "gaussian",
+            Filter::Mitchell { .. } => "mitchell",
+        }
+    }
+
+    /// Checks that the radius is positive and finite, which the weights
+    /// divide by.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::render::Filter;
+    ///
+    /// assert!(Filter::Tent { radius: 1.0 }.validate().is_ok());
+    /// assert!(Filter::Tent { radius: 0.0 }.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<()> {
+        let radius = self.radius();
+        if !(radius > 0.0 && radius.is_finite()) {
+            bail!(
+                "The {} filter radius must be positive, got {}",
+                self.name(),
+                radius
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the weight of a sample at offset `(dx, dy)` from a pixel
+    /// center.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::render::Filter;
+    ///
+    /// let filter = Filter::Tent { radius: 1.0 };
+    ///
+    /// assert_eq!(filter.evaluate(0.0, 0.0), 1.0);
+    /// assert_eq!(filter.evaluate(0.5, 0.0), 0.5);
+    /// assert_eq!(filter.evaluate(1.0, 0.0), 0.0);
+    /// ```
+    pub fn evaluate(&self, dx: f64, dy: f64) -> f64 {
+        self.evaluate_1d(dx) * self.evaluate_1d(dy)
+    }
+
+    fn evaluate_1d(&self, d: f64) -> f64 {
+        let d = d.abs();
+        if d > self.radius() {
+            return 0.0;
+        }
+
+        match *self {
+            Filter::Box { .. } => 1.0,
+            Filter::Tent { radius } => (1.0 - d / radius).max(0.0),
+            Filter::Gaussian { radius, alpha } => {
+                ((-alpha * d * d).exp() - (-alpha * radius * radius).exp()).max(0.0)
+            }
+            Filter::Mitchell { radius, b, c } => mitchell_1d(2.0 * d / radius, b, c),
+        }
+    }
+}
+
+impl Default for Filter {
+    /// A box filter covering exactly one pixel.
+    fn default() -> Self {
+        Filter::Box { radius: 0.5 }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.name(), self.radius())
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Error;
+
+    /// Parses a filter name with an optional radius, such as `tent` or
+    /// `gaussian:2`. Filters without a radius use their usual width.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::render::Filter;
+    ///
+    /// assert_eq!("tent:1.5".parse::<Filter>().unwrap(), Filter::Tent { radius: 1.5 });
+    /// assert_eq!("box".parse::<Filter>().unwrap(), Filter::default());
+    /// assert!("mitchell:0".parse::<Filter>().is_err());
+    /// ```
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, radius) = match value.split_once(':') {
+            Some((name, radius)) => {
+                let radius = radius
+                    .parse()
+                    .with_context(|| format!("Invalid filter radius {:?}", radius))?;
+                (name, Some(radius))
+            }
+            None => (value, None),
+        };
+
+        let filter = match name {
+            "box" => Filter::Box {
+                radius: radius.unwrap_or(0.5),
+            },
+            "tent" => Filter::Tent {
+                radius: radius.unwrap_or(1.0),
+            },
+            "gaussian" => Filter::Gaussian {
+                radius: radius.unwrap_or(1.5),
+                alpha: 2.0,
+            },
+            "mitchell" => Filter::Mitchell {
+                radius: radius.unwrap_or(2.0),
+                b: 1.0 / 3.0,
+                c: 1.0 / 3.0,
+            },
+            _ => {
+                return Err(anyhow!(
+                    "Unknown filter {:?}, expected one of box, tent, gaussian, mitchell",
+                    name
+                ))
+            }
+        };
+        filter.validate()?;
+
+        Ok(filter)
+    }
+}
+
+fn mitchell_1d(x: f64, b: f64, c: f64) -> f64 {
+    let x = x.abs();
+    let value = if x < 1.0 {
+        (12.0 - 9.0 * b - 6.0 * c) * x.powi(3)
+            + (-18.0 + 12.0 * b + 6.0 * c) * x.powi(2)
+            + (6.0 - 2.0 * b)
+    } else if x < 2.0 {
+        (-b - 6.0 * c) * x.powi(3)
+            + (6.0 * b + 30.0 * c) * x.powi(2)
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c)
+    } else {
+        0.0
+    };
+
+    value / 6.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_box_filter() {
+        let filter = Filter::Box { radius: 0.5 };
+
+        assert_eq!(filter.evaluate(0.4, -0.4), 1.0);
+        assert_eq!(filter.evaluate(0.6, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_gaussian_filter() {
+        let filter = Filter::Gaussian {
+            radius: 1.5,
+            alpha: 2.0,
+        };
+
+        assert!(filter.evaluate(0.0, 0.0) > filter.evaluate(0.5, 0.0));
+        assert_abs_diff_eq!(filter.evaluate(1.5, 0.0), 0.0);
+        assert_eq!(filter.evaluate(0.5, 0.2), filter.evaluate(-0.5, -0.2));
+    }
+
+    #[test]
+    fn test_mitchell_filter() {
+        let filter = Filter::Mitchell {
+            radius: 2.0,
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        };
+
+        assert_abs_diff_eq!(filter.evaluate_1d(0.0), 8.0 / 9.0);
+        assert_abs_diff_eq!(filter.evaluate_1d(2.0), 0.0);
+        // The Mitchell filter has negative lobes.
+        assert!(filter.evaluate_1d(1.5) < 0.0);
+    }
+
+    #[test]
+    fn test_filter_rejects_invalid_radius() {
+        for radius in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(Filter::Tent { radius }.validate().is_err());
+            assert!(Filter::Mitchell {
+                radius,
+                b: 1.0 / 3.0,
+                c: 1.0 / 3.0
+            }
+            .validate()
+            .is_err());
+        }
+
+        assert!("tent:0".parse::<Filter>().is_err());
+        assert!("gaussian:-1".parse::<Filter>().is_err());
+        assert!("box:wide".parse::<Filter>().is_err());
+        assert!("lanczos".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn test_filter_display_round_trips() {
+        for name in ["box", "tent:1.5", "gaussian", "mitchell:3"] {
+            let filter: Filter = name.parse().unwrap();
+
+            assert_eq!(filter.to_string().parse::<Filter>().unwrap(), filter);
+        }
+    }
+}
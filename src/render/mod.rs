@@ -0,0 +1,23 @@
+mod accumulator;
+mod adaptive;
+mod distributed;
+mod film;
+mod filter;
+mod parallel;
+mod passes;
+mod sampler;
+mod tiles;
+
+pub use accumulator::{render_progressive, Accumulator, Checkpoint};
+pub use adaptive::{render_adaptive, AdaptiveRender, AdaptiveSampling, PixelStats};
+pub use distributed::{render_distributed, serve_coordinator, Job, PixelShader};
+pub use film::{render_supersampled, Film, Supersampling};
+pub use filter::Filter;
+pub use parallel::{render_parallel, render_sampled};
+pub use passes::{Pass, RenderPasses, SurfaceSample};
+pub use sampler::{
+    concentric_sample_disk, cosine_hemisphere_pdf, cosine_sample_hemisphere, stratified_offsets,
+};
+pub use tiles::{
+    render_tiles, tiles, Cancelled, Progress, RenderedTile, Tile, TileOrder, TileSettings,
+};
@@ -0,0 +1,150 @@
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::prelude::*;
+
+use super::stratified_offsets;
+use crate::core::{Canvas, Color};
+
+/// Renders a canvas by calling `shade` for every pixel, spreading the rows
+/// over the rayon thread pool.
+///
+/// Run inside [`rayon::ThreadPool::install`] to limit the number of threads.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::Color;
+/// use raytracing::render::render_parallel;
+///
+/// let canvas = render_parallel(4, 3, |x, y| Color::new(x as f64, y as f64, 0.0));
+///
+/// assert_eq!(canvas[(3, 2)], Color::new(3.0, 2.0, 0.0));
+/// ```
+pub fn render_parallel(
+    width: usize,
+    height: usize,
+    shade: impl Fn(usize, usize) -> Color + Sync,
+) -> Canvas {
+    let rows = map_rows(0..height, 0, |y, _| {
+        (0..width).map(|x| shade(x, y)).collect::<Vec<_>>()
+    });
+
+    let mut canvas = Canvas::new(width, height);
+    for (y, row) in rows.into_iter().enumerate() {
+        for (x, color) in row.into_iter().enumerate() {
+            canvas[(x, y)] = color;
+        }
+    }
+
+    canvas
+}
+
+/// Renders a canvas with `samples_per_pixel` stochastic samples per pixel,
+/// spreading the rows over the rayon thread pool.
+///
+/// `shade` receives the raster position of a sample, jittered within its
+/// stratum, and a random number generator for the sample's own random
+/// decisions. The mean of the samples is stored in the canvas. The image
+/// only depends on `seed`, not on the number of threads.
+///
+/// # Examples
+///
+/// ```
+/// use rand::Rng;
+/// use raytracing::core::Color;
+/// use raytracing::render::render_sampled;
+///
+/// let canvas = render_sampled(4, 3, 16, 0, |x, _, rng| {
+///     let coin = if rng.gen::<bool>() { 1.0 } else { 0.0 };
+///     Color::new(x.floor(), coin, 0.0)
+/// });
+///
+/// assert_eq!(canvas[(3, 2)].r(), 3.0);
+/// assert!((0.0..=1.0).contains(&canvas[(3, 2)].g()));
+/// ```
+pub fn render_sampled(
+    width: usize,
+    height: usize,
+    samples_per_pixel: usize,
+    seed: u64,
+    shade: impl Fn(f64, f64, &mut StdRng) -> Color + Sync,
+) -> Canvas {
+    let samples_per_pixel = samples_per_pixel.max(1);
+    let rows = map_rows(0..height, seed, |y, rng| {
+        (0..width)
+            .map(|x| {
+                let offsets = stratified_offsets(samples_per_pixel, true, rng);
+                let sum = offsets.iter().fold(Color::default(), |sum, (dx, dy)| {
+                    sum + shade(x as f64 + dx, y as f64 + dy, rng)
+                });
+                sum * (1.0 / samples_per_pixel as f64)
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut canvas = Canvas::new(width, height);
+    for (y, row) in rows.into_iter().enumerate() {
+        for (x, color) in row.into_iter().enumerate() {
+            canvas[(x, y)] = color;
+        }
+    }
+
+    canvas
+}
+
+/// Maps every row in parallel, preserving the order of the rows.
+///
+/// Every row gets its own random number generator derived from `seed` and
+/// the row index, so results do not depend on the number of threads.
+pub(crate) fn map_rows<T: Send>(
+    rows: std::ops::Range<usize>,
+    seed: u64,
+    f: impl Fn(usize, &mut StdRng) -> T + Sync,
+) -> Vec<T> {
+    rows.into_par_iter()
+        .map(|y| f(y, &mut row_rng(seed, y)))
+        .collect()
+}
+
+fn row_rng(seed: u64, y: usize) -> StdRng {
+    StdRng::seed_from_u64(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ y as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_map_rows_deterministic() {
+        let a = map_rows(0..64, 7, |_, rng| rng.gen::<u64>());
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let b = pool.install(|| map_rows(0..64, 7, |_, rng| rng.gen::<u64>()));
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_render_sampled_averages_samples() {
+        let canvas = render_sampled(2, 2, 4, 0, |x, y, _| {
+            Color::new(x - x.floor(), y - y.floor(), 1.0)
+        });
+
+        for y in 0..2 {
+            for x in 0..2 {
+                approx::assert_abs_diff_eq!(canvas[(x, y)].b(), 1.0);
+                // Stratified offsets average to roughly the pixel center.
+                approx::assert_abs_diff_eq!(canvas[(x, y)].r(), 0.5, epsilon = 0.25);
+            }
+        }
+    }
+
+    #[test]
+    fn test_map_rows_distinct_rngs() {
+        let values = map_rows(0..2, 0, |_, rng| rng.gen::<u64>());
+
+        assert_ne!(values[0], values[1]);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::core::{Canvas, Color, Vec3};
+
+/// One of the buffers produced by a render.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Pass {
+    /// The shaded image.
+    Beauty,
+    /// Linear distance from the camera to the first hit.
+    Depth,
+    /// World space surface normal at the first hit.
+    Normal,
+    /// Unlit surface color at the first hit.
+    Albedo,
+    /// Identifier of the object at the first hit.
+    ObjectId,
+    /// Identifier of the material at the first hit.
+    MaterialId,
+    /// Number of intersection tests performed for the pixel.
+    HitCount,
+}
+
+impl Pass {
+    /// All passes, in the order they are written to disk.
+    pub const ALL: [Pass; 7] = [
+        Pass::Beauty,
+        Pass::Depth,
+        Pass::Normal,
+        Pass::Albedo,
+        Pass::ObjectId,
+        Pass::MaterialId,
+        Pass::HitCount,
+    ];
+
+    /// Returns the name of the pass, used as file suffix or layer name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::render::Pass;
+    ///
+    /// assert_eq!(Pass::Depth.name(), "depth");
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pass::Beauty => "beauty",
+            Pass::Depth => "depth",
+            Pass::Normal => "normal",
+            Pass::Albedo => "albedo",
+            Pass::ObjectId => "object_id",
+            Pass::MaterialId => "material_id",
+            Pass::HitCount => "hit_count",
+        }
+    }
+}
+
+/// Surface information of the first hit for a camera ray.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SurfaceSample {
+    pub depth: f64,
+    pub normal: Vec3,
+    pub albedo: Color,
+    pub object_id: usize,
+    pub material_id: usize,
+}
+
+/// The beauty canvas together with the auxiliary buffers of a render.
+///
+/// Pixels for which no surface was recorded keep an infinite depth, a zero
+/// normal, a black albedo and no object or material id.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::{Color, Vec3};
+/// use raytracing::render::{RenderPasses, SurfaceSample};
+///
+/// let mut passes = RenderPasses::new(4, 2);
+///
+/// passes.record(
+///     1,
+///     1,
+///     &SurfaceSample {
+///         depth: 2.5,
+///         normal: Vec3::new(0.0, 1.0, 0.0),
+///         albedo: Color::new(1.0, 0.0, 0.0),
+///         object_id: 3,
+///         material_id: 1,
+///     },
+/// );
+///
+/// assert_eq!(passes.depth_at(1, 1), 2.5);
+/// assert_eq!(passes.object_id_at(1, 1), Some(3));
+/// assert_eq!(passes.object_id_at(0, 0), None);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderPasses {
+    pub beauty: Canvas,
+    depth: Vec<f64>,
+    normals: Vec<Vec3>,
+    albedo: Canvas,
+    object_ids: Vec<Option<usize>>,
+    material_ids: Vec<Option<usize>>,
+    hit_counts: Vec<u32>,
+}
+
+impl RenderPasses {
+    /// Creates empty passes of the given width and height.
+    pub fn new(width: usize, height: usize) -> RenderPasses {
+        let size = width * height;
+
+        RenderPasses {
+            beauty: Canvas::new(width, height),
+            depth: vec![f64::INFINITY; size],
+            normals: vec![Vec3::default(); size],
+            albedo: Canvas::new(width, height),
+            object_ids: vec![None; size],
+            material_ids: vec![None; size],
+            hit_counts: vec![0; size],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.beauty.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.beauty.height
+    }
+
+    /// Stores the surface information of the first hit for the given pixel.
+    pub fn record(&mut self, x: usize, y: usize, sample: &SurfaceSample) {
+        let index = self.index(x, y);
+
+        self.depth[index] = sample.depth;
+        self.normals[index] = sample.normal;
+        self.albedo[(x, y)] = sample.albedo;
+        self.object_ids[index] = Some(sample.object_id);
+        self.material_ids[index] = Some(sample.material_id);
+    }
+
+    /// Adds `count` intersection tests to the heatmap of the given pixel.
+    pub fn add_hits(&mut self, x: usize, y: usize, count: u32) {
+        let index = self.index(x, y);
+        self.hit_counts[index] += count;
+    }
+
+    pub fn depth_at(&self, x: usize, y: usize) -> f64 {
+        self.depth[self.index(x, y)]
+    }
+
+    pub fn normal_at(&self, x: usize, y: usize) -> Vec3 {
+        self.normals[self.index(x, y)]
+    }
+
+    pub fn albedo_at(&self, x: usize, y: usize) -> Color {
+        self.albedo[(x, y)]
+    }
+
+    pub fn object_id_at(&self, x: usize, y: usize) -> Option<usize> {
+        self.object_ids[self.index(x, y)]
+    }
+
+    pub fn material_id_at(&self, x: usize, y: usize) -> Option<usize> {
+        self.material_ids[self.index(x, y)]
+    }
+
+    pub fn hit_count_at(&self, x: usize, y: usize) -> u32 {
+        self.hit_counts[self.index(x, y)]
+    }
+
+    /// Returns the raw values of a pass as a canvas.
+    ///
+    /// Scalar passes and ids are stored in all three channels, pixels
+    /// without an id get -1. Use this for lossless formats such as EXR; see
+    /// [`RenderPasses::preview`] for displayable images.
+    pub fn canvas(&self, pass: Pass) -> Canvas {
+        match pass {
+            Pass::Beauty => self.beauty.clone(),
+            Pass::Albedo => self.albedo.clone(),
+            Pass::Depth => self.map(|passes, i| gray(passes.depth[i])),
+            Pass::Normal => self.map(|passes, i| {
+                let n = passes.normals[i];
+                Color::new(n.x(), n.y(), n.z())
+            }),
+            Pass::ObjectId => self.map(|passes, i| raw_id(passes.object_ids[i])),
+            Pass::MaterialId => self.map(|passes, i| raw_id(passes.material_ids[i])),
+            Pass::HitCount => self.map(|passes, i| gray(passes.hit_counts[i] as f64)),
+        }
+    }
+
+    /// Returns a pass with its values remapped to the displayable `[0, 1]`
+    /// range.
+    ///
+    /// Depth and hit counts are normalized by their largest finite value,
+    /// normals are mapped from `[-1, 1]` to `[0, 1]` and ids to a stable,
+    /// distinct color per id.
+    pub fn preview(&self, pass: Pass) -> Canvas {
+        match pass {
+            Pass::Depth => {
+                let max = max_finite(self.depth.iter().copied());
+                self.map(|passes, i| match passes.depth[i] {
+                    d if d.is_finite() && max > 0.0 => gray(d / max),
+                    _ => Color::default(),
+                })
+            }
+            Pass::Normal => self.map(|passes, i| {
+                let n = passes.normals[i];
+                if n == Vec3::default() {
+                    Color::default()
+                } else {
+                    Color::new(n.x() * 0.5 + 0.5, n.y() * 0.5 + 0.5, n.z() * 0.5 + 0.5)
+                }
+            }),
+            Pass::HitCount => {
+                let max = max_finite(self.hit_counts.iter().map(|&c| c as f64));
+                self.map(|passes, i| match max {
+                    max if max > 0.0 => gray(passes.hit_counts[i] as f64 / max),
+                    _ => Color::default(),
+                })
+            }
+            Pass::ObjectId => self.map(|passes, i| id_color(passes.object_ids[i])),
+            Pass::MaterialId => self.map(|passes, i| id_color(passes.material_ids[i])),
+            Pass::Beauty | Pass::Albedo => self.canvas(pass),
+        }
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width() + x
+    }
+
+    fn map(&self, f: impl Fn(&RenderPasses, usize) -> Color) -> Canvas {
+        let mut canvas = Canvas::new(self.width(), self.height());
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                canvas[(x, y)] = f(self, self.index(x, y));
+            }
+        }
+
+        canvas
+    }
+}
+
+fn gray(value: f64) -> Color {
+    Color::new(value, value, value)
+}
+
+fn max_finite(values: impl Iterator<Item = f64>) -> f64 {
+    values.filter(|v| v.is_finite()).fold(0.0, f64::max)
+}
+
+fn raw_id(id: Option<usize>) -> Color {
+    gray(id.map_or(-1.0, |id| id as f64))
+}
+
+/// Maps an id to a color by spreading consecutive ids over the hue circle.
+fn id_color(id: Option<usize>) -> Color {
+    let Some(id) = id else {
+        return Color::default();
+    };
+
+    // Multiplying by the golden ratio conjugate keeps neighbouring ids apart.
+    let hue = ((id as f64 + 1.0) * 0.618_033_988_749_895).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+
+    match hue as usize {
+        0 => Color::new(1.0, x, 0.0),
+        1 => Color::new(x, 1.0, 0.0),
+        2 => Color::new(0.0, 1.0, x),
+        3 => Color::new(0.0, x, 1.0),
+        4 => Color::new(x, 0.0, 1.0),
+        _ => Color::new(1.0, 0.0, x),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(depth: f64, object_id: usize) -> SurfaceSample {
+        SurfaceSample {
+            depth,
+            normal: Vec3::new(0.0, 0.0, -1.0),
+            albedo: Color::new(0.2, 0.4, 0.6),
+            object_id,
+            material_id: 0,
+        }
+    }
+
+    #[test]
+    fn test_passes_empty() {
+        let passes = RenderPasses::new(2, 2);
+
+        assert_eq!(passes.depth_at(1, 0), f64::INFINITY);
+        assert_eq!(passes.normal_at(1, 0), Vec3::default());
+        assert_eq!(passes.hit_count_at(1, 0), 0);
+        assert_eq!(passes.preview(Pass::Depth)[(1, 0)], Color::default());
+    }
+
+    #[test]
+    fn test_passes_depth_preview_normalized() {
+        let mut passes = RenderPasses::new(2, 1);
+        passes.record(0, 0, &sample(2.0, 0));
+        passes.record(1, 0, &sample(4.0, 1));
+
+        let preview = passes.preview(Pass::Depth);
+
+        assert_eq!(preview[(0, 0)], Color::new(0.5, 0.5, 0.5));
+        assert_eq!(preview[(1, 0)], Color::new(1.0, 1.0, 1.0));
+        assert_eq!(
+            passes.canvas(Pass::Depth)[(1, 0)],
+            Color::new(4.0, 4.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn test_passes_normal_preview() {
+        let mut passes = RenderPasses::new(1, 1);
+        passes.record(0, 0, &sample(1.0, 0));
+
+        assert_eq!(
+            passes.preview(Pass::Normal)[(0, 0)],
+            Color::new(0.5, 0.5, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_passes_object_ids_distinct() {
+        let mut passes = RenderPasses::new(2, 1);
+        passes.record(0, 0, &sample(1.0, 0));
+        passes.record(1, 0, &sample(1.0, 1));
+
+        let ids = passes.preview(Pass::ObjectId);
+
+        assert_ne!(ids[(0, 0)], ids[(1, 0)]);
+        assert_eq!(ids[(0, 0)], id_color(Some(0)));
+    }
+
+    #[test]
+    fn test_passes_raw_ids() {
+        let mut passes = RenderPasses::new(2, 1);
+        passes.record(1, 0, &sample(1.0, 7));
+
+        let ids = passes.canvas(Pass::ObjectId);
+
+        assert_eq!(ids[(0, 0)], Color::new(-1.0, -1.0, -1.0));
+        assert_eq!(ids[(1, 0)], Color::new(7.0, 7.0, 7.0));
+    }
+
+    #[test]
+    fn test_passes_hit_count_heatmap() {
+        let mut passes = RenderPasses::new(2, 1);
+        passes.add_hits(0, 0, 1);
+        passes.add_hits(1, 0, 3);
+        passes.add_hits(1, 0, 1);
+
+        assert_eq!(passes.hit_count_at(1, 0), 4);
+        assert_eq!(
+            passes.preview(Pass::HitCount)[(0, 0)],
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::f64::consts::{FRAC_PI_4, PI};
+
+use rand::Rng;
+
+use crate::core::Vec3;
+
+/// Returns `count` sub-pixel offsets in `[0, 1)²`, stratified over a grid.
+///
+/// The pixel is divided into a grid of roughly square cells with one sample
+/// per cell. Without `jitter` samples are placed at the cell centers,
+/// otherwise at a random position inside their cell. Samples that do not
+/// fit the grid are placed uniformly at random.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::render::stratified_offsets;
+///
+/// let offsets = stratified_offsets(4, false, &mut rand::thread_rng());
+///
+/// assert_eq!(offsets, vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]);
+/// ```
+pub fn stratified_offsets(count: usize, jitter: bool, rng: &mut impl Rng) -> Vec<(f64, f64)> {
+    let columns = (count as f64).sqrt().floor().max(1.0) as usize;
+    let rows = count / columns;
+
+    let mut offsets = Vec::with_capacity(count);
+    for row in 0..rows {
+        for column in 0..columns {
+            let (jx, jy) = if jitter {
+                (rng.gen::<f64>(), rng.gen::<f64>())
+            } else {
+                (0.5, 0.5)
+            };
+            offsets.push((
+                (column as f64 + jx) / columns as f64,
+                (row as f64 + jy) / rows as f64,
+            ));
+        }
+    }
+
+    while offsets.len() < count {
+        offsets.push((rng.gen(), rng.gen()));
+    }
+
+    offsets
+}
+
+/// Maps a point of the unit square to the unit disk, keeping strata
+/// intact (Shirley and Chiu's concentric mapping).
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::render::concentric_sample_disk;
+///
+/// assert_eq!(concentric_sample_disk(0.5, 0.5), (0.0, 0.0));
+/// assert_eq!(concentric_sample_disk(1.0, 0.5), (1.0, 0.0));
+/// ```
+pub fn concentric_sample_disk(u: f64, v: f64) -> (f64, f64) {
+    let (x, y) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if x == 0.0 && y == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let (radius, theta) = if x.abs() > y.abs() {
+        (x, FRAC_PI_4 * (y / x))
+    } else {
+        (y, 2.0 * FRAC_PI_4 - FRAC_PI_4 * (x / y))
+    };
+
+    (radius * theta.cos(), radius * theta.sin())
+}
+
+/// Maps a point of the unit square to a direction in the hemisphere around
+/// the z axis, with a density proportional to the cosine of the angle to
+/// the z axis.
+///
+/// The density of a direction `w` is [`cosine_hemisphere_pdf`]`(w.z())`.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::Vec3;
+/// use raytracing::render::cosine_sample_hemisphere;
+///
+/// assert_eq!(cosine_sample_hemisphere(0.5, 0.5), Vec3::new(0.0, 0.0, 1.0));
+/// ```
+pub fn cosine_sample_hemisphere(u: f64, v: f64) -> Vec3 {
+    let (x, y) = concentric_sample_disk(u, v);
+    let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+
+    Vec3::new(x, y, z)
+}
+
+/// Returns the density of [`cosine_sample_hemisphere`] for a direction
+/// with the given cosine to the z axis.
+pub fn cosine_hemisphere_pdf(cos_theta: f64) -> f64 {
+    cos_theta.max(0.0) / PI
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_stratified_offsets_single() {
+        let offsets = stratified_offsets(1, false, &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(offsets, vec![(0.5, 0.5)]);
+    }
+
+    #[test]
+    fn test_stratified_offsets_jittered_stay_in_cell() {
+        let offsets = stratified_offsets(16, true, &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(offsets.len(), 16);
+        for (i, (x, y)) in offsets.into_iter().enumerate() {
+            assert_eq!((x * 4.0) as usize, i % 4);
+            assert_eq!((y * 4.0) as usize, i / 4);
+        }
+    }
+
+    #[test]
+    fn test_cosine_sample_hemisphere_is_normalized() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let w = cosine_sample_hemisphere(rng.gen(), rng.gen());
+
+            assert!(w.z() >= 0.0);
+            approx::assert_abs_diff_eq!(w.dot(&w), 1.0, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_cosine_sample_hemisphere_mean_cosine() {
+        // The mean of cos θ under a cosine weighted density is 2/3.
+        let mut rng = StdRng::seed_from_u64(0);
+        let count = 100_000;
+        let mean = (0..count)
+            .map(|_| cosine_sample_hemisphere(rng.gen(), rng.gen()).z())
+            .sum::<f64>()
+            / count as f64;
+
+        approx::assert_abs_diff_eq!(mean, 2.0 / 3.0, epsilon = 0.01);
+    }
+
+    #[test]
+    fn test_stratified_offsets_remainder() {
+        let offsets = stratified_offsets(7, true, &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(offsets.len(), 7);
+        assert!(offsets
+            .iter()
+            .all(|&(x, y)| (0.0..1.0).contains(&x) && (0.0..1.0).contains(&y)));
+    }
+}
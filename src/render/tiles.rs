@@ -0,0 +1,352 @@
+use std::{
+    error::Error,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{Canvas, Color};
+
+/// A rectangular region of the image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    /// Returns the pixel coordinates covered by the tile, row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+/// The order in which tiles are rendered.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum TileOrder {
+    /// Left to right, top to bottom.
+    #[default]
+    Scanline,
+    /// Outwards from the center of the image.
+    Spiral,
+    /// Along a Hilbert curve, keeping consecutive tiles close together.
+    Hilbert,
+}
+
+/// Settings for rendering an image tile by tile.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TileSettings {
+    pub tile_size: usize,
+    pub order: TileOrder,
+}
+
+impl Default for TileSettings {
+    fn default() -> Self {
+        TileSettings {
+            tile_size: 32,
+            order: TileOrder::default(),
+        }
+    }
+}
+
+/// The pixels of a finished tile, row by row.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderedTile {
+    pub tile: Tile,
+    pub pixels: Vec<Color>,
+}
+
+/// The number of finished tiles of a render.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    /// Returns the finished part of the render in the range `[0, 1]`.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.completed as f64 / self.total as f64
+        }
+    }
+}
+
+/// Returned when a render was cancelled before all tiles were finished.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Render cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Splits an image into tiles of at most `tile_size` pixels square, in the
+/// given order.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::render::{tiles, TileOrder};
+///
+/// let tiles = tiles(100, 50, 32, TileOrder::Scanline);
+///
+/// assert_eq!(tiles.len(), 8);
+/// assert_eq!((tiles[3].x, tiles[3].width), (96, 4));
+/// assert_eq!((tiles[4].y, tiles[4].height), (32, 18));
+/// ```
+pub fn tiles(width: usize, height: usize, tile_size: usize, order: TileOrder) -> Vec<Tile> {
+    let tile_size = tile_size.max(1);
+    let columns = width.div_ceil(tile_size);
+    let rows = height.div_ceil(tile_size);
+
+    let cells = match order {
+        TileOrder::Scanline => (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .collect(),
+        TileOrder::Spiral => spiral(columns, rows),
+        TileOrder::Hilbert => hilbert(columns, rows),
+    };
+
+    cells
+        .into_iter()
+        .map(|(column, row)| {
+            let (x, y) = (column * tile_size, row * tile_size);
+            Tile {
+                x,
+                y,
+                width: tile_size.min(width - x),
+                height: tile_size.min(height - y),
+            }
+        })
+        .collect()
+}
+
+/// Renders a canvas tile by tile on the rayon thread pool.
+///
+/// Tiles are started in the configured order. After every finished tile
+/// `on_tile` is called with its pixels and the progress of the render; it
+/// is called from the worker threads. Setting `cancel` stops the render
+/// once the tiles that are in flight are done.
+pub fn render_tiles(
+    width: usize,
+    height: usize,
+    settings: &TileSettings,
+    cancel: &AtomicBool,
+    on_tile: impl Fn(&RenderedTile, Progress) + Sync,
+    shade: impl Fn(usize, usize) -> Color + Sync,
+) -> Result<Canvas, Cancelled> {
+    let tiles = tiles(width, height, settings.tile_size, settings.order);
+    let next = AtomicUsize::new(0);
+    let completed = AtomicUsize::new(0);
+    let canvas = Mutex::new(Canvas::new(width, height));
+
+    rayon::scope(|scope| {
+        for _ in 0..rayon::current_num_threads() {
+            scope.spawn(|_| loop {
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Some(&tile) = tiles.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                    break;
+                };
+
+                let rendered = RenderedTile {
+                    tile,
+                    pixels: tile.pixels().map(|(x, y)| shade(x, y)).collect(),
+                };
+
+                {
+                    let mut canvas = canvas.lock().unwrap();
+                    for ((x, y), &color) in tile.pixels().zip(&rendered.pixels) {
+                        canvas[(x, y)] = color;
+                    }
+                }
+
+                let progress = Progress {
+                    completed: completed.fetch_add(1, Ordering::Relaxed) + 1,
+                    total: tiles.len(),
+                };
+                on_tile(&rendered, progress);
+            });
+        }
+    });
+
+    if completed.into_inner() < tiles.len() {
+        return Err(Cancelled);
+    }
+
+    Ok(canvas.into_inner().unwrap())
+}
+
+fn spiral(columns: usize, rows: usize) -> Vec<(usize, usize)> {
+    let total = columns * rows;
+    let mut cells = Vec::with_capacity(total);
+    let (mut x, mut y) = ((columns as isize - 1) / 2, (rows as isize - 1) / 2);
+    let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut step = 1;
+    let mut direction = 0;
+
+    let visit = |x: isize, y: isize, cells: &mut Vec<_>| {
+        if (0..columns as isize).contains(&x) && (0..rows as isize).contains(&y) {
+            cells.push((x as usize, y as usize));
+        }
+    };
+
+    visit(x, y, &mut cells);
+    while cells.len() < total {
+        for _ in 0..2 {
+            let (dx, dy) = directions[direction % 4];
+            for _ in 0..step {
+                x += dx;
+                y += dy;
+                visit(x, y, &mut cells);
+            }
+            direction += 1;
+        }
+        step += 1;
+    }
+
+    cells
+}
+
+fn hilbert(columns: usize, rows: usize) -> Vec<(usize, usize)> {
+    let n = columns.max(rows).max(1).next_power_of_two();
+
+    (0..n * n)
+        .map(|d| hilbert_cell(n, d))
+        .filter(|&(x, y)| x < columns && y < rows)
+        .collect()
+}
+
+/// Converts a distance along a Hilbert curve filling an `n` by `n` grid to
+/// grid coordinates.
+fn hilbert_cell(n: usize, d: usize) -> (usize, usize) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn assert_covers(tiles: &[Tile], width: usize, height: usize) {
+        let pixels: Vec<_> = tiles.iter().flat_map(|tile| tile.pixels()).collect();
+        let unique: HashSet<_> = pixels.iter().collect();
+
+        assert_eq!(pixels.len(), width * height);
+        assert_eq!(unique.len(), width * height);
+    }
+
+    #[test]
+    fn test_tiles_cover_image() {
+        for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert] {
+            assert_covers(&tiles(70, 45, 16, order), 70, 45);
+        }
+    }
+
+    #[test]
+    fn test_spiral_starts_in_center() {
+        let tiles = tiles(48, 48, 16, TileOrder::Spiral);
+
+        assert_eq!((tiles[0].x, tiles[0].y), (16, 16));
+        assert_eq!((tiles[1].x, tiles[1].y), (32, 16));
+    }
+
+    #[test]
+    fn test_hilbert_tiles_are_adjacent() {
+        let tiles = tiles(64, 64, 16, TileOrder::Hilbert);
+
+        for pair in tiles.windows(2) {
+            let distance = pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y);
+            assert_eq!(distance, 16);
+        }
+    }
+
+    #[test]
+    fn test_render_tiles_reports_progress() {
+        let reports = Mutex::new(Vec::new());
+        let canvas = render_tiles(
+            20,
+            10,
+            &TileSettings {
+                tile_size: 8,
+                order: TileOrder::Spiral,
+            },
+            &AtomicBool::new(false),
+            |tile, progress| reports.lock().unwrap().push((tile.tile, progress)),
+            |x, y| Color::new(x as f64, y as f64, 0.0),
+        )
+        .unwrap();
+
+        let reports = reports.into_inner().unwrap();
+        assert_eq!(reports.len(), 6);
+        assert!(reports
+            .iter()
+            .any(|(_, progress)| progress.fraction() == 1.0));
+        assert_eq!(canvas[(19, 9)], Color::new(19.0, 9.0, 0.0));
+    }
+
+    #[test]
+    fn test_render_tiles_cancel() {
+        // A single thread finishes the first tile before looking at the next
+        // one, so the cancellation always comes before the last tile.
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let cancel = AtomicBool::new(false);
+        let finished = AtomicUsize::new(0);
+        let result = pool.install(|| {
+            render_tiles(
+                64,
+                64,
+                &TileSettings {
+                    tile_size: 8,
+                    order: TileOrder::Scanline,
+                },
+                &cancel,
+                |_, _| {
+                    finished.fetch_add(1, Ordering::Relaxed);
+                    cancel.store(true, Ordering::Relaxed);
+                },
+                |_, _| Color::default(),
+            )
+        });
+
+        assert_eq!(result, Err(Cancelled));
+        assert_eq!(finished.into_inner(), 1);
+    }
+}
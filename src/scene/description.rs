@@ -0,0 +1,355 @@
+use serde::{Deserialize, Serialize};
+
+use crate::bsdf::Principled;
+use crate::core::{Color, Matrix, Point, Vec3};
+
+/// A scene as described in a scene file.
+///
+/// This is a plain description of the scene contents, which
+/// [`SceneWorld`](super::SceneWorld) turns into something that can be
+/// rendered.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scene {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera: Option<Camera>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Environment>,
+    #[serde(default)]
+    pub lights: Vec<Light>,
+    #[serde(default)]
+    pub objects: Vec<Object>,
+}
+
+impl Scene {
+    /// Returns the number of objects, including objects nested in groups.
+    pub fn object_count(&self) -> usize {
+        fn count(objects: &[Object]) -> usize {
+            objects
+                .iter()
+                .map(|object| match &object.shape {
+                    Shape::Group { children } => 1 + count(children),
+                    _ => 1,
+                })
+                .sum()
+        }
+
+        count(&self.objects)
+    }
+
+    /// Returns the files the scene refers to, such as OBJ meshes and the
+    /// environment image, as they are written in the scene.
+    pub fn referenced_files(&self) -> Vec<&str> {
+        fn collect<'a>(objects: &'a [Object], files: &mut Vec<&'a str>) {
+            for object in objects {
+                match &object.shape {
+                    Shape::Group { children } => collect(children, files),
+                    Shape::Obj { file } => files.push(file),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut files = Vec::new();
+        if let Some(environment) = &self.environment {
+            files.push(environment.file.as_str());
+        }
+        collect(&self.objects, &mut files);
+
+        files
+    }
+}
+
+/// The camera looking at the scene.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Camera {
+    pub width: usize,
+    pub height: usize,
+    /// Field of view in radians, spanning the longer side of the image.
+    pub field_of_view: f64,
+    pub from: Point,
+    pub to: Point,
+    pub up: Vec3,
+}
+
+impl Default for Camera {
+    /// Looks from 5 units along the negative z axis at the origin, for
+    /// scenes without a camera.
+    fn default() -> Self {
+        Camera {
+            width: 100,
+            height: 100,
+            field_of_view: std::f64::consts::FRAC_PI_3,
+            from: Point::new(0.0, 0.0, -5.0),
+            to: Point::default(),
+            up: Vec3::new(0.0, 1.0, 0.0),
+        }
+    }
+}
+
+/// An equirectangular image surrounding the scene, seen by rays that hit
+/// nothing and lighting the scene like an area light at infinity.
+///
+/// HDR and EXR images keep their values above 1, which is what makes an
+/// image useful as a light source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Environment {
+    pub file: String,
+    /// Factor the pixels of the image are scaled by.
+    #[serde(
+        default = "default_intensity",
+        skip_serializing_if = "is_default_intensity"
+    )]
+    pub intensity: f64,
+}
+
+/// A light source, a point light unless another `kind` is given.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Light {
+    /// Position of point and spot lights, center of area lights. Unused by
+    /// directional lights.
+    pub position: Point,
+    /// Intensity of point and spot lights, radiance emitted by area lights,
+    /// irradiance from directional lights. With a `power` only the color
+    /// of the light.
+    pub intensity: Color,
+    #[serde(default, skip_serializing_if = "LightKind::is_point")]
+    pub kind: LightKind,
+    /// Power emitted by the light in watts, irradiance in watts per square
+    /// meter for directional lights. Lights given in physical units fall
+    /// off with the squared distance in every integrator.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power: Option<f64>,
+    /// How point and spot lights fall off with distance, overriding the
+    /// falloff of the integrator.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attenuation: Option<Attenuation>,
+    /// Number of shadow rays towards area lights per shading point,
+    /// stratified over the light.
+    #[serde(
+        default = "default_light_samples",
+        skip_serializing_if = "is_default_light_samples"
+    )]
+    pub samples: usize,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Light {
+            position: Point::new(0.0, 0.0, 0.0),
+            intensity: Color::new(1.0, 1.0, 1.0),
+            kind: LightKind::Point,
+            power: None,
+            attenuation: None,
+            samples: default_light_samples(),
+        }
+    }
+}
+
+/// Attenuation of a light at distance `d` by `1 / (constant + linear * d +
+/// quadratic * d²)`.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Attenuation {
+    pub constant: f64,
+    pub linear: f64,
+    pub quadratic: f64,
+}
+
+impl Attenuation {
+    /// Returns the factor light is scaled by at `distance`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use raytracing::scene::Attenuation;
+    ///
+    /// let attenuation = Attenuation { constant: 1.0, linear: 0.5, quadratic: 0.25 };
+    ///
+    /// assert_eq!(attenuation.factor(2.0), 1.0 / 3.0);
+    /// ```
+    pub fn factor(&self, distance: f64) -> f64 {
+        1.0 / (self.constant + self.linear * distance + self.quadratic * distance * distance)
+    }
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Attenuation {
+            constant: 1.0,
+            linear: 0.0,
+            quadratic: 0.0,
+        }
+    }
+}
+
+/// The kind of a light, with its kind specific parameters.
+///
+/// Area lights emit from both sides and are visible to rays, but do not
+/// cast shadows themselves.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum LightKind {
+    #[default]
+    Point,
+    /// A parallelogram spanned by the edges `u` and `v`.
+    Rect {
+        u: Vec3,
+        v: Vec3,
+    },
+    Disk {
+        normal: Vec3,
+        radius: f64,
+    },
+    Sphere {
+        radius: f64,
+    },
+    /// A point light shining into a cone around `direction`, fading out
+    /// between the inner and outer angles, in radians from the axis.
+    Spot {
+        direction: Vec3,
+        inner_angle: f64,
+        outer_angle: f64,
+    },
+    /// Light arriving from infinitely far away, like sunlight, travelling
+    /// along `direction`.
+    Directional {
+        direction: Vec3,
+    },
+}
+
+impl LightKind {
+    pub fn is_point(&self) -> bool {
+        *self == LightKind::Point
+    }
+}
+
+/// A shape placed in the scene.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Object {
+    pub shape: Shape,
+    /// Object to world transformation.
+    #[serde(default = "Matrix::<4, 4>::identity")]
+    pub transform: Matrix<4, 4>,
+    #[serde(default)]
+    pub material: Material,
+    /// Whether the object casts shadows.
+    #[serde(default = "default_shadow")]
+    pub shadow: bool,
+}
+
+/// The kind of shape of an object, with its shape specific parameters.
+///
+/// Unbounded cylinders and cones leave out `minimum` and `maximum` when
+/// serialized, as infinity cannot be represented in JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Shape {
+    Sphere,
+    Plane,
+    Cube,
+    Cylinder {
+        #[serde(default = "negative_infinity", skip_serializing_if = "is_infinite")]
+        minimum: f64,
+        #[serde(default = "infinity", skip_serializing_if = "is_infinite")]
+        maximum: f64,
+        #[serde(default)]
+        closed: bool,
+    },
+    Cone {
+        #[serde(default = "negative_infinity", skip_serializing_if = "is_infinite")]
+        minimum: f64,
+        #[serde(default = "infinity", skip_serializing_if = "is_infinite")]
+        maximum: f64,
+        #[serde(default)]
+        closed: bool,
+    },
+    Group {
+        #[serde(default)]
+        children: Vec<Object>,
+    },
+    /// A mesh loaded from a Wavefront OBJ file.
+    Obj {
+        file: String,
+    },
+}
+
+/// Surface parameters of the Phong reflection model.
+///
+/// Physically based integrators use the `principled` material when it is
+/// set, and a diffuse surface of the given color otherwise. Surfaces with
+/// an `emission` are light sources, emitting it as radiance from both
+/// sides.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Material {
+    pub color: Color,
+    pub ambient: f64,
+    pub diffuse: f64,
+    pub specular: f64,
+    pub shininess: f64,
+    pub reflective: f64,
+    pub transparency: f64,
+    pub refractive_index: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub principled: Option<Principled>,
+    #[serde(skip_serializing_if = "is_black")]
+    pub emission: Color,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            color: Color::new(1.0, 1.0, 1.0),
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.9,
+            shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            principled: None,
+            emission: Color::default(),
+        }
+    }
+}
+
+fn is_black(color: &Color) -> bool {
+    *color == Color::default()
+}
+
+fn default_intensity() -> f64 {
+    1.0
+}
+
+fn is_default_intensity(intensity: &f64) -> bool {
+    *intensity == default_intensity()
+}
+
+fn default_light_samples() -> usize {
+    1
+}
+
+fn is_default_light_samples(samples: &usize) -> bool {
+    *samples == default_light_samples()
+}
+
+fn default_shadow() -> bool {
+    true
+}
+
+fn infinity() -> f64 {
+    f64::INFINITY
+}
+
+fn negative_infinity() -> f64 {
+    f64::NEG_INFINITY
+}
+
+fn is_infinite(value: &f64) -> bool {
+    value.is_infinite()
+}
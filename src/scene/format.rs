@@ -0,0 +1,597 @@
+use std::{fs, path::Path};
+
+use super::{load_yaml, Light, LightKind, Object, Scene, Shape};
+use crate::core::{Color, Matrix, Point, Vec3};
+use anyhow::{anyhow, bail, Context, Result};
+
+/// Parses a scene from JSON.
+///
+/// Errors point to the offending value, e.g.
+/// `objects[1].material.diffuse: invalid type: string "high", expected f64`.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::scene::{parse_json, Shape};
+///
+/// let scene = parse_json(r#"{ "objects": [{ "shape": { "type": "sphere" } }] }"#).unwrap();
+///
+/// assert_eq!(scene.objects[0].shape, Shape::Sphere);
+/// ```
+pub fn parse_json(source: &str) -> Result<Scene> {
+    deserialize(&mut serde_json::Deserializer::from_str(source))
+}
+
+/// Parses a scene from TOML. Errors point to the offending value.
+pub fn parse_toml(source: &str) -> Result<Scene> {
+    deserialize(toml::Deserializer::new(source))
+}
+
+pub fn to_json(scene: &Scene) -> Result<String> {
+    Ok(serde_json::to_string_pretty(scene)?)
+}
+
+pub fn to_toml(scene: &Scene) -> Result<String> {
+    Ok(toml::to_string(scene)?)
+}
+
+/// Loads a scene file, picking the format from the file extension
+/// (`.yml`/`.yaml`, `.json` or `.toml`).
+pub fn load_scene(path: impl AsRef<Path>) -> Result<Scene> {
+    let path = path.as_ref();
+    let format = extension(path)?;
+    if format == "yml" || format == "yaml" {
+        return load_yaml(path);
+    }
+
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("Error while reading {}", path.display()))?;
+    match format.as_str() {
+        "json" => parse_json(&source),
+        "toml" => parse_toml(&source),
+        _ => bail!("Unsupported scene format `{}`", format),
+    }
+    .with_context(|| format!("Error while parsing {}", path.display()))
+}
+
+/// Saves a scene as JSON or TOML, picking the format from the file
+/// extension.
+pub fn save_scene(scene: &Scene, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let contents = match extension(path)?.as_str() {
+        "json" => to_json(scene)?,
+        "toml" => to_toml(scene)?,
+        format => bail!("Unsupported scene format `{}`", format),
+    };
+
+    fs::write(path, contents).with_context(|| format!("Error while writing {}", path.display()))
+}
+
+impl Scene {
+    /// Checks the values that deserialize fine but cannot be rendered.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(camera) = &self.camera {
+            if camera.width == 0 || camera.height == 0 {
+                bail!("camera: width and height must be positive");
+            }
+            if !(camera.field_of_view > 0.0 && camera.field_of_view < std::f64::consts::PI) {
+                bail!("camera.field_of_view: must be between 0 and π radians");
+            }
+            for (name, value) in [
+                ("from", vector(camera.from)),
+                ("to", vector(camera.to)),
+                ("up", camera.up),
+            ] {
+                if !finite(value) {
+                    bail!("camera.{}: must be finite", name);
+                }
+            }
+            if camera.from == camera.to {
+                bail!("camera.to: must differ from camera.from");
+            }
+            if parallel(camera.up, camera.to - camera.from) {
+                bail!("camera.up: must not be zero or parallel to the view direction");
+            }
+        }
+
+        if let Some(environment) = &self.environment {
+            if !(environment.intensity >= 0.0 && environment.intensity.is_finite()) {
+                bail!("environment.intensity: must be a finite, non-negative number");
+            }
+        }
+
+        for (i, light) in self.lights.iter().enumerate() {
+            validate_light(light, &format!("lights[{}]", i))?;
+        }
+
+        validate_objects(&self.objects, "objects")
+    }
+}
+
+fn validate_light(light: &Light, path: &str) -> Result<()> {
+    if !finite(vector(light.position)) {
+        bail!("{}.position: must be finite", path);
+    }
+    if light.samples == 0 {
+        bail!("{}.samples: must be positive", path);
+    }
+    if light
+        .power
+        .is_some_and(|power| !(power >= 0.0 && power.is_finite()))
+    {
+        bail!("{}.power: must be a finite, non-negative number", path);
+    }
+    if let Some(attenuation) = &light.attenuation {
+        let terms = [
+            attenuation.constant,
+            attenuation.linear,
+            attenuation.quadratic,
+        ];
+        if terms.iter().any(|&term| !(term >= 0.0 && term.is_finite()))
+            || terms.iter().all(|&term| term == 0.0)
+        {
+            bail!(
+                "{}.attenuation: terms must be finite, non-negative and not all zero",
+                path
+            );
+        }
+    }
+
+    if !finite_non_negative(light.intensity) {
+        bail!("{}.intensity: must be finite and non-negative", path);
+    }
+
+    match light.kind {
+        LightKind::Rect { u, .. } if !finite(u) => bail!("{}.kind.u: must be finite", path),
+        LightKind::Rect { v, .. } if !finite(v) => bail!("{}.kind.v: must be finite", path),
+        LightKind::Disk { normal, .. } if !finite(normal) => {
+            bail!("{}.kind.normal: must be finite", path)
+        }
+        LightKind::Spot { direction, .. } | LightKind::Directional { direction }
+            if !finite(direction) =>
+        {
+            bail!("{}.kind.direction: must be finite", path)
+        }
+        LightKind::Point => {}
+        LightKind::Rect { u, v } if u.cross(&v) == Vec3::default() => {
+            bail!(
+                "{}.kind: the edges of a rect light must not be parallel",
+                path
+            )
+        }
+        LightKind::Rect { .. } => {}
+        LightKind::Disk { normal, .. } if normal == Vec3::default() => {
+            bail!("{}.kind.normal: must not be zero", path)
+        }
+        LightKind::Disk { radius, .. } | LightKind::Sphere { radius }
+            if !(radius > 0.0 && radius.is_finite()) =>
+        {
+            bail!("{}.kind.radius: must be a finite, positive number", path)
+        }
+        LightKind::Disk { .. } | LightKind::Sphere { .. } => {}
+        LightKind::Spot { direction, .. } | LightKind::Directional { direction }
+            if direction == Vec3::default() =>
+        {
+            bail!("{}.kind.direction: must not be zero", path)
+        }
+        LightKind::Spot {
+            inner_angle,
+            outer_angle,
+            ..
+        } if !(0.0 <= inner_angle
+            && inner_angle <= outer_angle
+            && outer_angle <= std::f64::consts::PI) =>
+        {
+            bail!(
+                "{}.kind: spot angles must satisfy 0 ≤ inner_angle ≤ outer_angle ≤ π",
+                path
+            )
+        }
+        LightKind::Spot { .. } | LightKind::Directional { .. } => {}
+    }
+
+    Ok(())
+}
+
+fn validate_objects(objects: &[Object], path: &str) -> Result<()> {
+    for (i, object) in objects.iter().enumerate() {
+        let path = format!("{}[{}]", path, i);
+        validate_transform(&object.transform, &path)?;
+        let material = &object.material;
+
+        for (name, value) in [
+            ("ambient", material.ambient),
+            ("diffuse", material.diffuse),
+            ("specular", material.specular),
+            ("shininess", material.shininess),
+            ("reflective", material.reflective),
+            ("transparency", material.transparency),
+        ] {
+            if !(value >= 0.0 && value.is_finite()) {
+                bail!(
+                    "{}.material.{}: must be a finite, non-negative number",
+                    path,
+                    name
+                );
+            }
+        }
+        let refractive_index = material.refractive_index;
+        if !(refractive_index > 0.0 && refractive_index.is_finite()) {
+            bail!(
+                "{}.material.refractive_index: must be a finite, positive number",
+                path
+            );
+        }
+        if !finite_non_negative(material.color) {
+            bail!("{}.material.color: must be finite and non-negative", path);
+        }
+        if !finite_non_negative(material.emission) {
+            bail!(
+                "{}.material.emission: must be finite and non-negative",
+                path
+            );
+        }
+        if let Some(principled) = &material.principled {
+            for (name, value) in [
+                ("metallic", principled.metallic),
+                ("roughness", principled.roughness),
+                ("specular", principled.specular),
+                ("clearcoat", principled.clearcoat),
+                ("clearcoat_roughness", principled.clearcoat_roughness),
+                ("sheen", principled.sheen),
+                ("sheen_tint", principled.sheen_tint),
+                ("transmission", principled.transmission),
+            ] {
+                if !(0.0..=1.0).contains(&value) {
+                    bail!(
+                        "{}.material.principled.{}: must be between 0 and 1",
+                        path,
+                        name
+                    );
+                }
+            }
+        }
+
+        match &object.shape {
+            Shape::Cylinder {
+                minimum, maximum, ..
+            }
+            | Shape::Cone {
+                minimum, maximum, ..
+            } if minimum.is_nan() || maximum.is_nan() || minimum > maximum => {
+                bail!("{}.shape: minimum must not exceed maximum", path)
+            }
+            Shape::Group { children } => {
+                validate_objects(children, &format!("{}.shape.children", path))?
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_transform(transform: &Matrix<4, 4>, path: &str) -> Result<()> {
+    if !transform.elements().all(f64::is_finite) {
+        bail!("{}.transform: must be finite", path);
+    }
+    let determinant = transform.determinant();
+    if determinant == 0.0 || !determinant.is_finite() {
+        bail!("{}.transform: must be invertible", path);
+    }
+
+    Ok(())
+}
+
+/// Returns whether two vectors point along the same line, which includes
+/// either of them being zero.
+fn parallel(a: Vec3, b: Vec3) -> bool {
+    a.cross(&b).magnitude() <= 1e-9 * a.magnitude() * b.magnitude()
+}
+
+fn vector(point: Point) -> Vec3 {
+    point - Point::default()
+}
+
+fn finite(vector: Vec3) -> bool {
+    [vector.x(), vector.y(), vector.z()]
+        .iter()
+        .all(|value| value.is_finite())
+}
+
+fn finite_non_negative(color: Color) -> bool {
+    [color.r(), color.g(), color.b()]
+        .iter()
+        .all(|&channel| channel >= 0.0 && channel.is_finite())
+}
+
+fn deserialize<'de, D>(deserializer: D) -> Result<Scene>
+where
+    D: serde::Deserializer<'de>,
+    D::Error: std::error::Error + Send + Sync + 'static,
+{
+    let scene: Scene = serde_path_to_error::deserialize(deserializer).map_err(|error| {
+        let path = error.path().to_string();
+        anyhow!("{}: {}", path, error.into_inner())
+    })?;
+    scene.validate()?;
+
+    Ok(scene)
+}
+
+fn extension(path: &Path) -> Result<String> {
+    Ok(path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .context("Scene files need an extension")?
+        .to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsdf::Principled;
+    use crate::core::{
+        transformations::{scale, translate},
+        Color, Matrix, Point, Vec3,
+    };
+    use crate::scene::{Attenuation, Camera, Environment, Light, Material};
+
+    fn scene() -> Scene {
+        Scene {
+            camera: Some(Camera {
+                width: 320,
+                height: 200,
+                field_of_view: 1.2,
+                from: Point::new(0.0, 1.5, -5.0),
+                to: Point::new(0.0, 1.0, 0.0),
+                up: Vec3::new(0.0, 1.0, 0.0),
+            }),
+            environment: Some(Environment {
+                file: "studio.hdr".to_string(),
+                intensity: 0.5,
+            }),
+            lights: vec![
+                Light {
+                    position: Point::new(-10.0, 10.0, -10.0),
+                    intensity: Color::new(1.0, 1.0, 1.0),
+                    attenuation: Some(Attenuation {
+                        constant: 1.0,
+                        linear: 0.0,
+                        quadratic: 0.01,
+                    }),
+                    ..Default::default()
+                },
+                Light {
+                    position: Point::new(0.0, 4.0, 0.0),
+                    intensity: Color::new(4.0, 4.0, 4.0),
+                    kind: LightKind::Disk {
+                        normal: Vec3::new(0.0, -1.0, 0.0),
+                        radius: 0.5,
+                    },
+                    power: Some(100.0),
+                    samples: 9,
+                    ..Default::default()
+                },
+            ],
+            objects: vec![Object {
+                shape: Shape::Group {
+                    children: vec![
+                        Object {
+                            shape: Shape::Cylinder {
+                                minimum: f64::NEG_INFINITY,
+                                maximum: 2.0,
+                                closed: true,
+                            },
+                            transform: translate(1.0, 2.0, 3.0),
+                            material: Material {
+                                reflective: 0.5,
+                                emission: Color::new(0.5, 0.0, 0.0),
+                                principled: Some(Principled {
+                                    metallic: 1.0,
+                                    roughness: 0.2,
+                                    ..Default::default()
+                                }),
+                                ..Default::default()
+                            },
+                            shadow: false,
+                        },
+                        Object {
+                            shape: Shape::Obj {
+                                file: "teapot.obj".to_string(),
+                            },
+                            transform: Matrix::<4, 4>::identity(),
+                            material: Default::default(),
+                            shadow: true,
+                        },
+                    ],
+                },
+                transform: translate(0.0, 1.0, 0.0),
+                material: Default::default(),
+                shadow: true,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let json = to_json(&scene()).unwrap();
+
+        assert_eq!(parse_json(&json).unwrap(), scene());
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let toml = to_toml(&scene()).unwrap();
+
+        assert_eq!(parse_toml(&toml).unwrap(), scene());
+    }
+
+    #[test]
+    fn test_json_defaults() {
+        let scene = parse_json(r#"{ "objects": [{ "shape": { "type": "cone" } }] }"#).unwrap();
+        let object = &scene.objects[0];
+
+        assert_eq!(object.transform, Matrix::<4, 4>::identity());
+        assert_eq!(object.material, Material::default());
+        assert!(object.shadow);
+        assert_eq!(
+            object.shape,
+            Shape::Cone {
+                minimum: f64::NEG_INFINITY,
+                maximum: f64::INFINITY,
+                closed: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_json_error_path() {
+        let error = parse_json(
+            r#"{ "objects": [
+                { "shape": { "type": "sphere" } },
+                { "shape": { "type": "sphere" }, "material": { "diffuse": "high" } }
+            ] }"#,
+        )
+        .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("objects[1].material.diffuse: invalid type"));
+    }
+
+    #[test]
+    fn test_toml_error_path() {
+        let error = parse_toml("[[lights]]\nposition = [1.0, 2.0]\nintensity = [1.0, 1.0, 1.0]\n")
+            .unwrap_err();
+
+        assert!(error.to_string().starts_with("lights[0].position:"));
+    }
+
+    #[test]
+    fn test_referenced_files() {
+        assert_eq!(scene().referenced_files(), vec!["studio.hdr", "teapot.obj"]);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut invalid = scene();
+        if let Shape::Group { children } = &mut invalid.objects[0].shape {
+            children[1].material.diffuse = -1.0;
+        }
+
+        assert_eq!(
+            invalid.validate().unwrap_err().to_string(),
+            "objects[0].shape.children[1].material.diffuse: must be a finite, non-negative number"
+        );
+
+        let mut invalid = scene();
+        if let Shape::Group { children } = &mut invalid.objects[0].shape {
+            children[1].material.refractive_index = f64::NAN;
+        }
+
+        assert_eq!(
+            invalid.validate().unwrap_err().to_string(),
+            "objects[0].shape.children[1].material.refractive_index: must be a finite, positive number"
+        );
+
+        let mut invalid = scene();
+        invalid.lights[0].intensity = Color::new(1.0, f64::NAN, 1.0);
+
+        assert_eq!(
+            invalid.validate().unwrap_err().to_string(),
+            "lights[0].intensity: must be finite and non-negative"
+        );
+
+        let mut invalid = scene();
+        if let Shape::Group { children } = &mut invalid.objects[0].shape {
+            children[0].material.principled.as_mut().unwrap().roughness = 2.0;
+        }
+
+        assert_eq!(
+            invalid.validate().unwrap_err().to_string(),
+            "objects[0].shape.children[0].material.principled.roughness: must be between 0 and 1"
+        );
+
+        let mut invalid = scene();
+        invalid.lights[1].kind = LightKind::Sphere { radius: 0.0 };
+
+        assert_eq!(
+            invalid.validate().unwrap_err().to_string(),
+            "lights[1].kind.radius: must be a finite, positive number"
+        );
+
+        let mut invalid = scene();
+        invalid.environment.as_mut().unwrap().intensity = f64::NAN;
+
+        assert_eq!(
+            invalid.validate().unwrap_err().to_string(),
+            "environment.intensity: must be a finite, non-negative number"
+        );
+    }
+
+    #[test]
+    fn test_validate_geometry() {
+        let error = |edit: fn(&mut Scene)| {
+            let mut invalid = scene();
+            edit(&mut invalid);
+            invalid.validate().unwrap_err().to_string()
+        };
+
+        assert_eq!(
+            error(|scene| scene.camera.as_mut().unwrap().from = Point::new(f64::NAN, 0.0, 0.0)),
+            "camera.from: must be finite"
+        );
+        assert_eq!(
+            error(|scene| scene.camera.as_mut().unwrap().up = Vec3::new(0.0, f64::INFINITY, 0.0)),
+            "camera.up: must be finite"
+        );
+        assert_eq!(
+            error(|scene| {
+                let camera = scene.camera.as_mut().unwrap();
+                camera.to = camera.from;
+            }),
+            "camera.to: must differ from camera.from"
+        );
+        assert_eq!(
+            error(|scene| {
+                let camera = scene.camera.as_mut().unwrap();
+                camera.up = (camera.to - camera.from) * 2.0;
+            }),
+            "camera.up: must not be zero or parallel to the view direction"
+        );
+        assert_eq!(
+            error(|scene| scene.lights[0].position = Point::new(0.0, 0.0, f64::NEG_INFINITY)),
+            "lights[0].position: must be finite"
+        );
+        assert_eq!(
+            error(|scene| {
+                scene.lights[1].kind = LightKind::Rect {
+                    u: Vec3::new(1.0, 0.0, 0.0),
+                    v: Vec3::new(0.0, f64::NAN, 1.0),
+                }
+            }),
+            "lights[1].kind.v: must be finite"
+        );
+        assert_eq!(
+            error(|scene| {
+                scene.lights[1].kind = LightKind::Directional {
+                    direction: Vec3::new(f64::NAN, -1.0, 0.0),
+                }
+            }),
+            "lights[1].kind.direction: must be finite"
+        );
+        assert_eq!(
+            error(|scene| scene.objects[0].transform[(0, 3)] = f64::NAN),
+            "objects[0].transform: must be finite"
+        );
+        assert_eq!(
+            error(|scene| {
+                if let Shape::Group { children } = &mut scene.objects[0].shape {
+                    children[1].transform = scale(1.0, 0.0, 1.0);
+                }
+            }),
+            "objects[0].shape.children[1].transform: must be invertible"
+        );
+    }
+}
@@ -0,0 +1,13 @@
+mod description;
+mod format;
+mod projection;
+mod world;
+mod yaml;
+
+pub use description::{
+    Attenuation, Camera, Environment, Light, LightKind, Material, Object, Scene, Shape,
+};
+pub use format::{load_scene, parse_json, parse_toml, save_scene, to_json, to_toml};
+pub use projection::Projection;
+pub use world::SceneWorld;
+pub use yaml::{load_yaml, parse_yaml};
@@ -0,0 +1,131 @@
+use super::Camera;
+use crate::core::{
+    transformations::{view_transform, Transform},
+    Matrix, Point, Ray,
+};
+
+/// Turns raster positions into camera rays for an image of a given size.
+///
+/// The camera's field of view spans the longer side of the image, so the
+/// size may differ from the one in the scene file without distorting it.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::{Point, Vec3};
+/// use raytracing::scene::{Camera, Projection};
+///
+/// let camera = Camera {
+///     width: 201,
+///     height: 101,
+///     field_of_view: std::f64::consts::FRAC_PI_2,
+///     from: Point::new(0.0, 0.0, 0.0),
+///     to: Point::new(0.0, 0.0, -1.0),
+///     up: Vec3::new(0.0, 1.0, 0.0),
+/// };
+/// let ray = Projection::new(&camera, 201, 101).ray(100.5, 50.5);
+///
+/// assert_eq!(ray.origin, Point::new(0.0, 0.0, 0.0));
+/// assert!((ray.direction - Vec3::new(0.0, 0.0, -1.0)).magnitude() < 1e-12);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Projection {
+    /// Camera to world transformation.
+    inverse_view: Matrix<4, 4>,
+    half_width: f64,
+    half_height: f64,
+    pixel_size: f64,
+}
+
+impl Projection {
+    pub fn new(camera: &Camera, width: usize, height: usize) -> Projection {
+        let half_view = (camera.field_of_view / 2.0).tan();
+        let aspect = width as f64 / height as f64;
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+
+        Projection {
+            inverse_view: view_transform(camera.from, camera.to, camera.up).inverse(),
+            half_width,
+            half_height,
+            pixel_size: half_width * 2.0 / width as f64,
+        }
+    }
+
+    /// Returns the unit length ray through the raster position `(x, y)`,
+    /// where pixel (0, 0) covers [0, 1) × [0, 1) in the top left corner.
+    pub fn ray(&self, x: f64, y: f64) -> Ray {
+        let world_x = self.half_width - x * self.pixel_size;
+        let world_y = self.half_height - y * self.pixel_size;
+
+        let pixel = Point::new(world_x, world_y, -1.0).transform(&self.inverse_view);
+        let origin = Point::default().transform(&self.inverse_view);
+
+        Ray::new(origin, (pixel - origin).normalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        transformations::{rotate_y, translate},
+        Vec3,
+    };
+    use approx::assert_abs_diff_eq;
+
+    fn camera(width: usize, height: usize) -> Camera {
+        Camera {
+            width,
+            height,
+            field_of_view: std::f64::consts::FRAC_PI_2,
+            from: Point::new(0.0, 0.0, 0.0),
+            to: Point::new(0.0, 0.0, -1.0),
+            up: Vec3::new(0.0, 1.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn test_pixel_size() {
+        assert_abs_diff_eq!(
+            Projection::new(&camera(200, 125), 200, 125).pixel_size,
+            0.01
+        );
+        assert_abs_diff_eq!(
+            Projection::new(&camera(125, 200), 125, 200).pixel_size,
+            0.01
+        );
+    }
+
+    #[test]
+    fn test_ray_through_corner() {
+        let ray = Projection::new(&camera(201, 101), 201, 101).ray(0.5, 0.5);
+
+        assert_abs_diff_eq!(
+            ray.direction,
+            Vec3::new(0.66519, 0.33259, -0.66851),
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_ray_with_transformed_camera() {
+        // The camera at (0, 2, -5) looking along +z after turning around.
+        let mut camera = camera(201, 101);
+        let transform = rotate_y(std::f64::consts::FRAC_PI_4) * translate(0.0, -2.0, 5.0);
+        camera.from = Point::new(0.0, 2.0, -5.0);
+        camera.to = camera.from + Vec3::new(0.0, 0.0, -1.0).transform(&transform.inverse());
+
+        let ray = Projection::new(&camera, 201, 101).ray(100.5, 50.5);
+
+        assert_abs_diff_eq!(ray.origin, Point::new(0.0, 2.0, -5.0), epsilon = 1e-12);
+        assert_abs_diff_eq!(
+            ray.direction,
+            Vec3::new(2.0_f64.sqrt() / 2.0, 0.0, -(2.0_f64.sqrt()) / 2.0),
+            epsilon = 1e-12
+        );
+    }
+}
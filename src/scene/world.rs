@@ -0,0 +1,616 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use anyhow::{Context, Result};
+
+use super::{Light, Material, Object, Scene, Shape};
+use crate::core::{transformations::Transform, Matrix, Ray, Vec3};
+use crate::geometry::{Bounded, Bvh, Instance, Mesh, Primitive};
+use crate::integrator::{Hit, World};
+use crate::light::{EnvironmentMap, MeshLight};
+use crate::output::load_canvas;
+use crate::render::SurfaceSample;
+
+/// A scene ready to be rendered: its shapes placed in a bounding volume
+/// hierarchy, with the meshes and the environment image it refers to
+/// loaded.
+///
+/// Groups are flattened into their children. Emissive OBJ meshes are also
+/// [mesh lights](World::mesh_lights) that integrators sample; other
+/// emissive shapes only emit light when rays happen to hit them.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::{Point, Ray, Vec3};
+/// use raytracing::integrator::World;
+/// use raytracing::scene::{parse_yaml, SceneWorld};
+///
+/// let scene = parse_yaml(
+///     "
+/// - add: sphere
+///   transform:
+///     - [translate, 0, 0, 5]
+/// ",
+/// )
+/// .unwrap();
+/// let world = SceneWorld::load(&scene, ".".as_ref()).unwrap();
+///
+/// let ray = Ray::new(Point::default(), Vec3::new(0.0, 0.0, 1.0));
+/// let hit = world.intersect(&ray).unwrap();
+///
+/// assert_eq!(hit.t, 4.0);
+/// assert_eq!(hit.normal, Vec3::new(0.0, 0.0, -1.0));
+/// ```
+#[derive(Debug)]
+pub struct SceneWorld {
+    /// Instances with finite bounds, in the hierarchy.
+    instances: Vec<Instance<Primitive>>,
+    bvh: Bvh,
+    /// Planes and open ended cylinders and cones, tested one by one.
+    unbounded: Vec<Instance<Primitive>>,
+    /// Surfaces of the instances, indexed by their material id, which is
+    /// also the object id of the instance.
+    surfaces: Vec<Surface>,
+    lights: Vec<Light>,
+    mesh_lights: Vec<MeshLight>,
+    environment: Option<EnvironmentMap>,
+}
+
+#[derive(Debug)]
+struct Surface {
+    material: Material,
+    /// Shared by the objects with an identical material.
+    material_id: usize,
+    shadow: bool,
+    light: Option<usize>,
+}
+
+impl SceneWorld {
+    /// Builds the world of a scene, reading the files it refers to relative
+    /// to `directory`.
+    pub fn load(scene: &Scene, directory: &Path) -> Result<SceneWorld> {
+        let mut builder = Builder {
+            directory,
+            meshes: HashMap::new(),
+            instances: Vec::new(),
+            surfaces: Vec::new(),
+            materials: Vec::new(),
+            mesh_lights: Vec::new(),
+        };
+        builder.add_objects(&scene.objects, &Matrix::<4, 4>::identity(), true)?;
+
+        let environment = match &scene.environment {
+            Some(environment) => {
+                let path = directory.join(&environment.file);
+                let canvas = load_canvas(path.to_str().context("Invalid environment path")?)?;
+                Some(EnvironmentMap::new(canvas, environment.intensity))
+            }
+            None => None,
+        };
+
+        let (instances, unbounded): (Vec<_>, Vec<_>) =
+            builder.instances.into_iter().partition(is_finite);
+
+        Ok(SceneWorld {
+            bvh: Bvh::build(&instances),
+            instances,
+            unbounded,
+            surfaces: builder.surfaces,
+            lights: scene.lights.clone(),
+            mesh_lights: builder.mesh_lights,
+            environment,
+        })
+    }
+
+    /// Returns the surface information of the first hit along a camera
+    /// ray, if any, with the number of shapes tested for the intersection.
+    ///
+    /// The depth is the distance along the ray, measured in units of its
+    /// direction's length.
+    pub fn surface_sample(&self, ray: &Ray) -> (Option<SurfaceSample>, u32) {
+        let mut tests = 0;
+        let sample = self.closest(ray, &mut tests).map(|(instance, t, normal)| {
+            let surface = self.surface(instance);
+
+            SurfaceSample {
+                depth: t,
+                normal: instance.normal_to_world(&normal),
+                albedo: surface
+                    .material
+                    .principled
+                    .map_or(surface.material.color, |principled| principled.base_color),
+                object_id: instance.surface.unwrap_or_default(),
+                material_id: surface.material_id,
+            }
+        });
+
+        (sample, tests)
+    }
+
+    /// Returns the closest instance along the ray with the distance and the
+    /// object space normal of the hit, counting the shapes tested.
+    fn closest(&self, ray: &Ray, tests: &mut u32) -> Option<(&Instance<Primitive>, f64, Vec3)> {
+        let mut closest = None;
+        let bounded = self.bvh.intersect(ray, f64::INFINITY, |index, ray, t_max| {
+            *tests += 1;
+            let instance = &self.instances[index];
+            let (t, normal) =
+                instance.intersect(ray, |primitive, ray| primitive.intersect(ray, t_max))?;
+            closest = Some((instance, normal));
+            Some(t)
+        });
+        let mut closest = bounded
+            .zip(closest)
+            .map(|((_, t), (instance, normal))| (instance, t, normal));
+
+        for instance in &self.unbounded {
+            *tests += 1;
+            let t_max = closest.map_or(f64::INFINITY, |(_, t, _)| t);
+            if let Some((t, normal)) =
+                instance.intersect(ray, |primitive, ray| primitive.intersect(ray, t_max))
+            {
+                closest = Some((instance, t, normal));
+            }
+        }
+
+        closest
+    }
+
+    fn hit(&self, instance: &Instance<Primitive>, ray: &Ray, t: f64, normal: &Vec3) -> Hit {
+        let surface = self.surface(instance);
+
+        Hit {
+            t,
+            point: ray.position(t),
+            normal: instance.normal_to_world(normal),
+            material: surface.material,
+            emission: surface.material.emission,
+            light: surface.light,
+        }
+    }
+
+    fn surface(&self, instance: &Instance<Primitive>) -> &Surface {
+        &self.surfaces[instance.surface.unwrap_or_default()]
+    }
+}
+
+impl World for SceneWorld {
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        self.closest(ray, &mut 0)
+            .map(|(instance, t, normal)| self.hit(instance, ray, t, &normal))
+    }
+
+    fn environment(&self) -> Option<&EnvironmentMap> {
+        self.environment.as_ref()
+    }
+
+    fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    fn mesh_lights(&self) -> &[MeshLight] {
+        &self.mesh_lights
+    }
+
+    /// Objects that do not cast shadows are ignored.
+    fn is_occluded(&self, ray: &Ray, distance: f64) -> bool {
+        let occludes = |instance: &Instance<Primitive>, ray: &Ray| {
+            if !self.surface(instance).shadow {
+                return None;
+            }
+            instance
+                .intersect(ray, |primitive, ray| primitive.intersect(ray, distance))
+                .map(|(t, _)| t)
+        };
+
+        self.bvh.any_hit(ray, distance, |index, ray| {
+            occludes(&self.instances[index], ray)
+        }) || self
+            .unbounded
+            .iter()
+            .any(|instance| occludes(instance, ray).is_some())
+    }
+}
+
+struct Builder<'a> {
+    directory: &'a Path,
+    /// Meshes that were read, by file name, so repeated files are shared.
+    meshes: HashMap<String, Arc<Primitive>>,
+    instances: Vec<Instance<Primitive>>,
+    surfaces: Vec<Surface>,
+    /// Distinct materials, indexed by material id.
+    materials: Vec<Material>,
+    mesh_lights: Vec<MeshLight>,
+}
+
+impl Builder<'_> {
+    fn add_objects(
+        &mut self,
+        objects: &[Object],
+        transform: &Matrix<4, 4>,
+        shadow: bool,
+    ) -> Result<()> {
+        for object in objects {
+            let transform = *transform * object.transform;
+            let shadow = shadow && object.shadow;
+            let primitive = match &object.shape {
+                Shape::Sphere => Primitive::Sphere,
+                Shape::Plane => Primitive::Plane,
+                Shape::Cube => Primitive::Cube,
+                &Shape::Cylinder {
+                    minimum,
+                    maximum,
+                    closed,
+                } => Primitive::Cylinder {
+                    minimum,
+                    maximum,
+                    closed,
+                },
+                &Shape::Cone {
+                    minimum,
+                    maximum,
+                    closed,
+                } => Primitive::Cone {
+                    minimum,
+                    maximum,
+                    closed,
+                },
+                Shape::Group { children } => {
+                    self.add_objects(children, &transform, shadow)?;
+                    continue;
+                }
+                Shape::Obj { file } => {
+                    let mesh = self.mesh(file)?;
+                    self.add_instance(mesh, transform, object.material, shadow);
+                    continue;
+                }
+            };
+
+            self.add_instance(Arc::new(primitive), transform, object.material, shadow);
+        }
+
+        Ok(())
+    }
+
+    fn add_instance(
+        &mut self,
+        primitive: Arc<Primitive>,
+        transform: Matrix<4, 4>,
+        material: Material,
+        shadow: bool,
+    ) {
+        let mut light = None;
+        if let Primitive::Mesh(mesh) = primitive.as_ref() {
+            if material.emission != Default::default() {
+                let triangles = mesh
+                    .triangles()
+                    .iter()
+                    .map(|triangle| triangle.vertices.map(|vertex| vertex.transform(&transform)))
+                    .collect();
+                light = Some(self.mesh_lights.len());
+                self.mesh_lights
+                    .push(MeshLight::new(triangles, material.emission));
+            }
+        }
+
+        self.instances
+            .push(Instance::new(primitive, transform).with_surface(self.surfaces.len()));
+        let material_id = match self.materials.iter().position(|m| *m == material) {
+            Some(id) => id,
+            None => {
+                self.materials.push(material);
+                self.materials.len() - 1
+            }
+        };
+        self.surfaces.push(Surface {
+            material,
+            material_id,
+            shadow,
+            light,
+        });
+    }
+
+    fn mesh(&mut self, file: &str) -> Result<Arc<Primitive>> {
+        if let Some(mesh) = self.meshes.get(file) {
+            return Ok(mesh.clone());
+        }
+
+        let path = self.directory.join(file);
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Error while reading {}", path.display()))?;
+        let mesh = Arc::new(Primitive::Mesh(
+            Mesh::parse_obj(&source).with_context(|| format!("Invalid mesh {}", path.display()))?,
+        ));
+        self.meshes.insert(file.to_string(), mesh.clone());
+
+        Ok(mesh)
+    }
+}
+
+fn is_finite(instance: &Instance<Primitive>) -> bool {
+    let bounds = instance.bounds();
+
+    [bounds.min, bounds.max]
+        .iter()
+        .all(|point| point.x().is_finite() && point.y().is_finite() && point.z().is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Color, Point};
+    use crate::integrator::{Integrator, Whitted};
+    use crate::scene::parse_yaml;
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn world(source: &str) -> SceneWorld {
+        SceneWorld::load(&parse_yaml(source).unwrap(), Path::new(".")).unwrap()
+    }
+
+    fn down(x: f64) -> Ray {
+        Ray::new(Point::new(x, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0))
+    }
+
+    fn whitted(world: &SceneWorld, ray: &Ray, max_depth: usize) -> Color {
+        Whitted { max_depth }.radiance(world, ray, &mut StdRng::seed_from_u64(0))
+    }
+
+    #[test]
+    fn test_group_transforms_apply_to_children() {
+        let world = world(
+            "
+- add: group
+  transform:
+    - [translate, 5, 0, 0]
+  children:
+    - add: cube
+      transform:
+        - [scale, 1, 2, 1]
+",
+        );
+
+        let hit = world.intersect(&down(5.0)).unwrap();
+
+        assert_eq!(hit.t, 8.0);
+        assert_eq!(hit.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!(world.intersect(&down(0.0)).is_none());
+    }
+
+    #[test]
+    fn test_closest_of_bounded_and_unbounded() {
+        let world = world(
+            "
+- add: plane
+  material:
+    color: [0, 1, 0]
+- add: sphere
+  material:
+    color: [1, 0, 0]
+  transform:
+    - [translate, 0, 3, 0]
+- add: sphere
+  transform:
+    - [translate, 3, -3, 0]
+",
+        );
+
+        assert_eq!(
+            world.intersect(&down(0.0)).unwrap().material.color,
+            Color::new(1.0, 0.0, 0.0)
+        );
+        // The second sphere lies below the plane.
+        assert_eq!(
+            world.intersect(&down(3.0)).unwrap().material.color,
+            Color::new(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_surface_sample() {
+        let world = world(
+            "
+- add: plane
+- add: sphere
+  material:
+    color: [1, 0, 0]
+  transform:
+    - [translate, 0, 3, 0]
+- add: sphere
+  transform:
+    - [translate, 3, 3, 0]
+",
+        );
+
+        let (sample, tests) = world.surface_sample(&down(0.0));
+        let sample = sample.unwrap();
+        assert_eq!(sample.depth, 6.0);
+        assert_eq!(sample.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(sample.albedo, Color::new(1.0, 0.0, 0.0));
+        assert_eq!((sample.object_id, sample.material_id), (1, 1));
+        assert!(tests >= 2);
+
+        // The plane and the second sphere share the default material.
+        let sample = world.surface_sample(&down(3.0)).0.unwrap();
+        assert_eq!((sample.object_id, sample.material_id), (2, 0));
+        let sample = world.surface_sample(&down(-3.0)).0.unwrap();
+        assert_eq!((sample.object_id, sample.material_id), (0, 0));
+
+        let up = Ray::new(Point::new(0.0, 10.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(world.surface_sample(&up).0, None);
+    }
+
+    #[test]
+    fn test_objects_without_shadow_do_not_occlude() {
+        let world = world(
+            "
+- add: sphere
+  shadow: false
+- add: cube
+  transform:
+    - [translate, 5, 0, 0]
+",
+        );
+
+        assert!(world.intersect(&down(0.0)).is_some());
+        assert!(!world.is_occluded(&down(0.0), f64::INFINITY));
+        assert!(world.is_occluded(&down(5.0), f64::INFINITY));
+        assert!(!world.is_occluded(&down(5.0), 8.5));
+    }
+
+    #[test]
+    fn test_whitted_lit_and_shadowed() {
+        let world = world(
+            "
+- add: light
+  at: [0, 10, 0]
+  intensity: [1, 1, 1]
+- add: plane
+- add: sphere
+  transform:
+    - [translate, 0, 3, 0]
+",
+        );
+        let up = Ray::new(Point::new(0.0, 10.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let below_sphere = Ray::new(Point::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+
+        // The floor gets ambient and diffuse light, the highlight is
+        // reflected away from the eye.
+        assert_abs_diff_eq!(
+            whitted(&world, &down(10.0), 5),
+            Color::new(1.0, 1.0, 1.0) * (0.1 + 0.9 * std::f64::consts::FRAC_1_SQRT_2),
+            epsilon = 1e-6
+        );
+        // Only ambient light reaches the floor in the shadow of the sphere.
+        assert_abs_diff_eq!(
+            whitted(&world, &below_sphere, 5),
+            Color::new(0.1, 0.1, 0.1),
+            epsilon = 1e-6
+        );
+        assert_eq!(whitted(&world, &up, 5), Color::default());
+    }
+
+    #[test]
+    fn test_whitted_reflection() {
+        let world = world(
+            "
+- add: light
+  at: [0, 10, 0]
+  intensity: [1, 1, 1]
+- add: plane
+  material:
+    color: [0, 0, 0]
+    ambient: 0
+    diffuse: 0
+    specular: 0
+    reflective: 1
+- add: sphere
+  material:
+    color: [1, 0, 0]
+  transform:
+    - [translate, 0, 3, 0]
+",
+        );
+        // Hits the black mirror floor at (1, 0, 0), from where the reflected
+        // ray sees the unlit underside of the sphere.
+        let ray = Ray::new(Point::new(2.0, 3.0, 0.0), Vec3::new(-1.0, -3.0, 0.0));
+
+        assert_abs_diff_eq!(
+            whitted(&world, &ray, 5),
+            Color::new(0.1, 0.0, 0.0),
+            epsilon = 1e-6
+        );
+        assert_eq!(whitted(&world, &ray, 0), Color::default());
+    }
+
+    #[test]
+    fn test_emissive_meshes_are_lights() {
+        let directory =
+            std::env::temp_dir().join(format!("raytracing-world-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("panel.obj"),
+            "v -1 0 -1\nv 1 0 -1\nv 1 0 1\nv -1 0 1\nf 1 2 3 4\n",
+        )
+        .unwrap();
+        let scene = parse_yaml(
+            "
+- add: obj
+  file: panel.obj
+  material:
+    emission: [4, 4, 4]
+  transform:
+    - [translate, 0, 2, 0]
+- add: obj
+  file: panel.obj
+",
+        )
+        .unwrap();
+
+        let world = SceneWorld::load(&scene, &directory).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(world.mesh_lights().len(), 1);
+        assert_abs_diff_eq!(world.mesh_lights()[0].area(), 4.0);
+        let hit = world.intersect(&down(0.5)).unwrap();
+        assert_eq!(hit.t, 8.0);
+        assert_eq!(hit.light, Some(0));
+        assert_eq!(hit.emission, Color::new(4.0, 4.0, 4.0));
+        let up = Ray::new(Point::new(0.5, -10.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let floor = world.intersect(&up).unwrap();
+        assert_eq!(floor.t, 10.0);
+        assert_eq!(floor.light, None);
+        assert_eq!(
+            Arc::strong_count(&world.instances[0].geometry),
+            2,
+            "the mesh is read once"
+        );
+    }
+
+    #[test]
+    fn test_environment_is_loaded() {
+        let directory = std::env::temp_dir().join(format!("raytracing-sky-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let mut canvas = crate::core::Canvas::new(4, 2);
+        for x in 0..4 {
+            canvas[(x, 0)] = Color::new(2.0, 3.0, 4.0);
+        }
+        crate::output::save_canvas_hdr(&canvas, directory.join("sky.exr").to_str().unwrap())
+            .unwrap();
+        let scene = parse_yaml(
+            "
+- add: environment
+  file: sky.exr
+  intensity: 0.5
+",
+        )
+        .unwrap();
+
+        let world = SceneWorld::load(&scene, &directory).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        let up = Ray::new(Point::default(), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(world.background(&up), Color::new(1.0, 1.5, 2.0));
+        assert_eq!(world.background(&down(0.0)), Color::default());
+        assert!(world.environment().unwrap().sample((0.5, 0.5)).is_some());
+    }
+
+    #[test]
+    fn test_missing_environment() {
+        let scene = parse_yaml("- add: environment\n  file: missing.hdr").unwrap();
+        let error = SceneWorld::load(&scene, Path::new("/nonexistent")).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Error while loading /nonexistent/missing.hdr"
+        );
+    }
+
+    #[test]
+    fn test_missing_mesh() {
+        let scene = parse_yaml("- add: obj\n  file: missing.obj").unwrap();
+        let error = SceneWorld::load(&scene, Path::new("/nonexistent")).unwrap_err();
+
+        assert!(format!("{:#}", error).contains("Error while reading /nonexistent/missing.obj"));
+    }
+}
@@ -0,0 +1,1046 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_yaml::{Mapping, Value};
+
+use super::{Attenuation, Camera, Environment, Light, LightKind, Material, Object, Scene, Shape};
+use crate::bsdf::Principled;
+use crate::core::{transformations, Color, Matrix, Point, Vec3};
+
+/// Loads a scene from a file in the YAML format of The Ray Tracer
+/// Challenge.
+pub fn load_yaml(path: impl AsRef<Path>) -> Result<Scene> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("Error while reading {}", path.display()))?;
+
+    parse_yaml(&source).with_context(|| format!("Error while parsing {}", path.display()))
+}
+
+/// Parses a scene in the YAML format of The Ray Tracer Challenge.
+///
+/// The document is a list of items: `add` items add the camera, the
+/// environment image, lights and shapes; `define` items declare named
+/// values, optionally `extend`ing another definition, that can be used in
+/// place of a material or of entries in a transform list. A definition of
+/// a shape can be added by name: the `material` of the item replaces the
+/// definition's and its `transform` is applied after the definition's.
+/// Definitions can only refer to definitions before them.
+///
+/// # Examples
+///
+/// ```
+/// use raytracing::core::{transformations::translate, Point};
+/// use raytracing::scene::{parse_yaml, Shape};
+///
+/// let scene = parse_yaml(
+///     "
+/// - add: light
+///   at: [-10, 10, -10]
+///   intensity: [1, 1, 1]
+/// - define: shiny
+///   value:
+///     specular: 1.0
+/// - add: sphere
+///   material: shiny
+///   transform:
+///     - [translate, 0, 1, 0]
+/// ",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(scene.lights[0].position, Point::new(-10.0, 10.0, -10.0));
+/// assert_eq!(scene.objects[0].shape, Shape::Sphere);
+/// assert_eq!(scene.objects[0].material.specular, 1.0);
+/// assert_eq!(scene.objects[0].transform, translate(0.0, 1.0, 0.0));
+/// ```
+pub fn parse_yaml(source: &str) -> Result<Scene> {
+    let document: Value = serde_yaml::from_str(source)?;
+    let items = document
+        .as_sequence()
+        .context("A scene must be a list of items")?;
+
+    let mut parser = Parser::default();
+    let mut scene = Scene::default();
+
+    for (i, item) in items.iter().enumerate() {
+        let item = item
+            .as_mapping()
+            .with_context(|| format!("Item {} must be a mapping", i + 1))?;
+
+        parser
+            .parse_item(item, &mut scene)
+            .with_context(|| format!("Error in item {} ({})", i + 1, describe(item)))?;
+    }
+    scene.validate()?;
+
+    Ok(scene)
+}
+
+/// The kinds of shapes `add` items can name.
+const SHAPES: [&str; 7] = [
+    "sphere", "plane", "cube", "cylinder", "cone", "group", "obj",
+];
+
+#[derive(Default)]
+struct Parser {
+    defines: HashMap<String, Value>,
+}
+
+impl Parser {
+    fn parse_item(&mut self, item: &Mapping, scene: &mut Scene) -> Result<()> {
+        if let Some(name) = item.get("define") {
+            return self.parse_define(name, item);
+        }
+
+        let kind = get(item, "add")
+            .context("Expected an `add` or `define` key")?
+            .as_str()
+            .context("`add` must be a string")?;
+
+        match kind {
+            "camera" => scene.camera = Some(parse_camera(item)?),
+            "environment" => scene.environment = Some(parse_environment(item)?),
+            "light" => scene.lights.push(parse_light(item)?),
+            _ => {
+                let item = self.expand_object(item)?;
+                let kind = get(&item, "add")?
+                    .as_str()
+                    .context("`add` must be a string")?;
+                scene.objects.push(self.parse_object(kind, &item)?);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_define(&mut self, name: &Value, item: &Mapping) -> Result<()> {
+        let name = name.as_str().context("`define` must be a string")?;
+        let mut value = match get(item, "value").context("A definition needs a `value`")? {
+            Value::Sequence(entries) => Value::Sequence(self.expand_names(entries)?),
+            value => value.clone(),
+        };
+
+        if let Some(base) = item.get("extend") {
+            let base = base.as_str().context("`extend` must be a string")?;
+            let Value::Mapping(mut merged) = self.lookup(base)?.clone() else {
+                bail!("Only mappings can be extended, `{}` is not a mapping", base);
+            };
+            let extension = value
+                .as_mapping()
+                .context("Only mappings can extend a definition")?;
+
+            for (key, value) in extension {
+                merged.insert(key.clone(), value.clone());
+            }
+            value = Value::Mapping(merged);
+        }
+        if let Value::Mapping(mapping) = &value {
+            if mapping.contains_key("add") {
+                value = Value::Mapping(self.expand_object(mapping)?);
+            }
+        }
+
+        self.defines.insert(name.to_string(), value);
+
+        Ok(())
+    }
+
+    /// Replaces the names in a list with the entries of the lists they
+    /// define, so a definition never refers to another one and cannot
+    /// refer to itself.
+    fn expand_names(&self, entries: &[Value]) -> Result<Vec<Value>> {
+        let mut expanded = Vec::with_capacity(entries.len());
+        for entry in entries {
+            match entry {
+                Value::String(name) => match self.lookup(name)? {
+                    Value::Sequence(entries) => expanded.extend(entries.iter().cloned()),
+                    _ => bail!("`{}` is not a list", name),
+                },
+                entry => expanded.push(entry.clone()),
+            }
+        }
+
+        Ok(expanded)
+    }
+
+    /// Replaces a shape item that adds a defined shape, and the children of
+    /// groups, with the definitions they name, so like lists, a stored
+    /// definition never refers to another one.
+    ///
+    /// The `material` of the item replaces the definition's, its
+    /// `transform` is appended to the definition's and any other key
+    /// overrides the definition's.
+    fn expand_object(&self, item: &Mapping) -> Result<Mapping> {
+        let kind = get(item, "add")?
+            .as_str()
+            .context("`add` must be a string")?;
+
+        let mut object = if SHAPES.contains(&kind) {
+            item.clone()
+        } else {
+            let definition = match self.defines.get(kind) {
+                Some(Value::Mapping(definition)) if definition.contains_key("add") => definition,
+                Some(_) => bail!("`{}` does not define a shape", kind),
+                None => bail!("Unknown item `{}`", kind),
+            };
+
+            let mut object = definition.clone();
+            for (key, value) in item {
+                match (key.as_str(), object.get_mut(key)) {
+                    (Some("add"), _) => {}
+                    (Some("transform"), Some(Value::Sequence(transform))) => transform.extend(
+                        value
+                            .as_sequence()
+                            .context("`transform` must be a list")?
+                            .iter()
+                            .cloned(),
+                    ),
+                    _ => {
+                        object.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            object
+        };
+
+        if let Some(Value::Sequence(children)) = object.get_mut("children") {
+            for (i, child) in children.iter_mut().enumerate() {
+                let expanded = child
+                    .as_mapping()
+                    .context("A child must be a mapping")
+                    .and_then(|child| self.expand_object(child))
+                    .with_context(|| format!("Error in child {}", i + 1))?;
+                *child = Value::Mapping(expanded);
+            }
+        }
+
+        Ok(object)
+    }
+
+    fn lookup(&self, name: &str) -> Result<&Value> {
+        self.defines
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown definition `{}`", name))
+    }
+
+    fn parse_object(&self, kind: &str, item: &Mapping) -> Result<Object> {
+        let shape = match kind {
+            "sphere" => Shape::Sphere,
+            "plane" => Shape::Plane,
+            "cube" => Shape::Cube,
+            "cylinder" | "cone" => {
+                let minimum = optional_number(item, "min")?.unwrap_or(f64::NEG_INFINITY);
+                let maximum = optional_number(item, "max")?.unwrap_or(f64::INFINITY);
+                let closed = optional_bool(item, "closed")?.unwrap_or(false);
+
+                if kind == "cylinder" {
+                    Shape::Cylinder {
+                        minimum,
+                        maximum,
+                        closed,
+                    }
+                } else {
+                    Shape::Cone {
+                        minimum,
+                        maximum,
+                        closed,
+                    }
+                }
+            }
+            "group" => {
+                let children = match item.get("children") {
+                    Some(children) => children
+                        .as_sequence()
+                        .context("`children` must be a list")?
+                        .iter()
+                        .enumerate()
+                        .map(|(i, child)| {
+                            self.parse_child(child)
+                                .with_context(|| format!("Error in child {}", i + 1))
+                        })
+                        .collect::<Result<_>>()?,
+                    None => Vec::new(),
+                };
+
+                Shape::Group { children }
+            }
+            "obj" => Shape::Obj {
+                file: get(item, "file")?
+                    .as_str()
+                    .context("`file` must be a string")?
+                    .to_string(),
+            },
+            _ => bail!("Unknown item `{}`", kind),
+        };
+
+        let transform = match item.get("transform") {
+            Some(transform) => self
+                .parse_transform(transform)
+                .context("Invalid transform")?,
+            None => Matrix::<4, 4>::identity(),
+        };
+        let material = match item.get("material") {
+            Some(material) => self.parse_material(material).context("Invalid material")?,
+            None => Material::default(),
+        };
+
+        Ok(Object {
+            shape,
+            transform,
+            material,
+            shadow: optional_bool(item, "shadow")?.unwrap_or(true),
+        })
+    }
+
+    fn parse_child(&self, child: &Value) -> Result<Object> {
+        let child = child.as_mapping().context("A child must be a mapping")?;
+        let kind = get(child, "add")?
+            .as_str()
+            .context("`add` must be a string")?;
+
+        self.parse_object(kind, child)
+    }
+
+    /// Parses a list of transformations, applied in the order they appear.
+    fn parse_transform(&self, value: &Value) -> Result<Matrix<4, 4>> {
+        let mut transform = Matrix::<4, 4>::identity();
+
+        for entry in value.as_sequence().context("Expected a list")? {
+            let step = match entry {
+                Value::String(name) => self
+                    .parse_transform(self.lookup(name)?)
+                    .with_context(|| format!("Invalid transform definition `{}`", name))?,
+                Value::Sequence(entry) => parse_transformation(entry)?,
+                _ => bail!("Expected a transformation or a definition name"),
+            };
+            transform = step * transform;
+        }
+
+        Ok(transform)
+    }
+
+    fn parse_material(&self, value: &Value) -> Result<Material> {
+        let mapping = match value {
+            Value::String(name) => self.lookup(name)?,
+            value => value,
+        }
+        .as_mapping()
+        .context("Expected a mapping or a definition name")?;
+
+        let mut material = Material::default();
+        for (key, value) in mapping {
+            let key = key.as_str().context("Material keys must be strings")?;
+            match key {
+                "color" => material.color = parse_color(value)?,
+                "emission" => material.emission = parse_color(value)?,
+                "principled" => {
+                    material.principled = Some(
+                        self.parse_principled(value)
+                            .context("Invalid principled material")?,
+                    )
+                }
+                _ => {
+                    let number = value
+                        .as_f64()
+                        .with_context(|| format!("`{}` must be a number", key))?;
+                    match key {
+                        "ambient" => material.ambient = number,
+                        "diffuse" => material.diffuse = number,
+                        "specular" => material.specular = number,
+                        "shininess" => material.shininess = number,
+                        "reflective" => material.reflective = number,
+                        "transparency" => material.transparency = number,
+                        "refractive-index" => material.refractive_index = number,
+                        _ => bail!("Unknown material property `{}`", key),
+                    }
+                }
+            }
+        }
+
+        Ok(material)
+    }
+
+    fn parse_principled(&self, value: &Value) -> Result<Principled> {
+        let mapping = match value {
+            Value::String(name) => self.lookup(name)?,
+            value => value,
+        }
+        .as_mapping()
+        .context("Expected a mapping or a definition name")?;
+
+        let mut principled = Principled::default();
+        for (key, value) in mapping {
+            let key = key.as_str().context("Material keys must be strings")?;
+            if key == "base-color" {
+                principled.base_color = parse_color(value)?;
+                continue;
+            }
+
+            let number = value
+                .as_f64()
+                .with_context(|| format!("`{}` must be a number", key))?;
+            match key {
+                "metallic" => principled.metallic = number,
+                "roughness" => principled.roughness = number,
+                "specular" => principled.specular = number,
+                "clearcoat" => principled.clearcoat = number,
+                "clearcoat-roughness" => principled.clearcoat_roughness = number,
+                "sheen" => principled.sheen = number,
+                "sheen-tint" => principled.sheen_tint = number,
+                "transmission" => principled.transmission = number,
+                _ => bail!("Unknown principled property `{}`", key),
+            }
+        }
+
+        Ok(principled)
+    }
+}
+
+fn parse_camera(item: &Mapping) -> Result<Camera> {
+    Ok(Camera {
+        width: get(item, "width")?
+            .as_u64()
+            .context("`width` must be a positive integer")? as usize,
+        height: get(item, "height")?
+            .as_u64()
+            .context("`height` must be a positive integer")? as usize,
+        field_of_view: number(get(item, "field-of-view")?, "field-of-view")?,
+        from: parse_point(get(item, "from")?).context("Invalid `from`")?,
+        to: parse_point(get(item, "to")?).context("Invalid `to`")?,
+        up: parse_vec3(get(item, "up")?).context("Invalid `up`")?,
+    })
+}
+
+/// Parses the environment image `file`, with an optional `intensity`.
+fn parse_environment(item: &Mapping) -> Result<Environment> {
+    Ok(Environment {
+        file: get(item, "file")?
+            .as_str()
+            .context("`file` must be a string")?
+            .to_string(),
+        intensity: optional_number(item, "intensity")?.unwrap_or(1.0),
+    })
+}
+
+/// Parses a light, a point light unless a `type` of `rect` (with edges `u`
+/// and `v`), `disk` (with a `normal` and `radius`), `sphere` (with a
+/// `radius`), `spot` (with a `direction`, `inner-angle` and `outer-angle`)
+/// or `directional` (with a `direction` and no `at`) is given.
+///
+/// `power` gives the light in watts, `attenuation` lists the constant,
+/// linear and quadratic attenuation terms.
+fn parse_light(item: &Mapping) -> Result<Light> {
+    let vector =
+        |key: &str| parse_vec3(get(item, key)?).with_context(|| format!("Invalid `{}`", key));
+    let radius = || number(get(item, "radius")?, "radius");
+
+    let kind = match item.get("type").map(|kind| kind.as_str()) {
+        None | Some(Some("point")) => LightKind::Point,
+        Some(Some("rect")) => LightKind::Rect {
+            u: vector("u")?,
+            v: vector("v")?,
+        },
+        Some(Some("disk")) => LightKind::Disk {
+            normal: vector("normal")?,
+            radius: radius()?,
+        },
+        Some(Some("sphere")) => LightKind::Sphere { radius: radius()? },
+        Some(Some("spot")) => LightKind::Spot {
+            direction: vector("direction")?,
+            inner_angle: number(get(item, "inner-angle")?, "inner-angle")?,
+            outer_angle: number(get(item, "outer-angle")?, "outer-angle")?,
+        },
+        Some(Some("directional")) => LightKind::Directional {
+            direction: vector("direction")?,
+        },
+        Some(Some(kind)) => bail!("Unknown light type `{}`", kind),
+        Some(None) => bail!("`type` must be a string"),
+    };
+    let samples = match item.get("samples") {
+        Some(samples) => samples
+            .as_u64()
+            .context("`samples` must be a positive integer")? as usize,
+        None => 1,
+    };
+
+    let attenuation = match item.get("attenuation") {
+        Some(value) => {
+            let (constant, linear, quadratic) =
+                triple(value).context("`attenuation` must list 3 numbers")?;
+            Some(Attenuation {
+                constant,
+                linear,
+                quadratic,
+            })
+        }
+        None => None,
+    };
+    let position = match kind {
+        LightKind::Directional { .. } => Point::new(0.0, 0.0, 0.0),
+        _ => parse_point(get(item, "at")?).context("Invalid `at`")?,
+    };
+
+    Ok(Light {
+        position,
+        intensity: parse_color(get(item, "intensity")?).context("Invalid `intensity`")?,
+        kind,
+        power: optional_number(item, "power")?,
+        attenuation,
+        samples,
+    })
+}
+
+fn parse_transformation(entry: &[Value]) -> Result<Matrix<4, 4>> {
+    let (name, args) = entry.split_first().context("Empty transformation")?;
+    let name = name
+        .as_str()
+        .context("A transformation must start with its name")?;
+    let args = args
+        .iter()
+        .map(|arg| number(arg, name))
+        .collect::<Result<Vec<_>>>()?;
+
+    let expect = |count: usize| {
+        if args.len() == count {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "`{}` takes {} arguments, got {}",
+                name,
+                count,
+                args.len()
+            ))
+        }
+    };
+
+    Ok(match name {
+        "translate" => {
+            expect(3)?;
+            transformations::translate(args[0], args[1], args[2])
+        }
+        "scale" => {
+            expect(3)?;
+            transformations::scale(args[0], args[1], args[2])
+        }
+        "rotate-x" => {
+            expect(1)?;
+            transformations::rotate_x(args[0])
+        }
+        "rotate-y" => {
+            expect(1)?;
+            transformations::rotate_y(args[0])
+        }
+        "rotate-z" => {
+            expect(1)?;
+            transformations::rotate_z(args[0])
+        }
+        "shear" => {
+            expect(6)?;
+            transformations::shear(args[0], args[1], args[2], args[3], args[4], args[5])
+        }
+        _ => bail!("Unknown transformation `{}`", name),
+    })
+}
+
+fn get<'a>(item: &'a Mapping, key: &str) -> Result<&'a Value> {
+    item.get(key).ok_or_else(|| anyhow!("Missing `{}`", key))
+}
+
+fn number(value: &Value, name: &str) -> Result<f64> {
+    value
+        .as_f64()
+        .with_context(|| format!("`{}` expects numbers", name))
+}
+
+fn optional_number(item: &Mapping, key: &str) -> Result<Option<f64>> {
+    item.get(key).map(|value| number(value, key)).transpose()
+}
+
+fn optional_bool(item: &Mapping, key: &str) -> Result<Option<bool>> {
+    item.get(key)
+        .map(|value| {
+            value
+                .as_bool()
+                .with_context(|| format!("`{}` must be true or false", key))
+        })
+        .transpose()
+}
+
+fn triple(value: &Value) -> Result<(f64, f64, f64)> {
+    match value.as_sequence().map(|values| values.as_slice()) {
+        Some([x, y, z]) => Ok((number(x, "x")?, number(y, "y")?, number(z, "z")?)),
+        _ => bail!("Expected a list of three numbers"),
+    }
+}
+
+fn parse_point(value: &Value) -> Result<Point> {
+    triple(value).map(Point::from)
+}
+
+fn parse_vec3(value: &Value) -> Result<Vec3> {
+    triple(value).map(Vec3::from)
+}
+
+fn parse_color(value: &Value) -> Result<Color> {
+    triple(value).map(Color::from)
+}
+
+/// Describes an item for error messages, e.g. `add: sphere`.
+fn describe(item: &Mapping) -> String {
+    ["add", "define"]
+        .iter()
+        .find_map(|key| {
+            item.get(*key)
+                .and_then(|value| value.as_str())
+                .map(|value| format!("{}: {}", key, value))
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::transformations::{rotate_x, scale, translate};
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_parse_camera() {
+        let scene = parse_yaml(
+            "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [-6, 6, -10]
+  to: [6, 0, 6]
+  up: [-0.45, 1, 0]
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            scene.camera,
+            Some(Camera {
+                width: 100,
+                height: 50,
+                field_of_view: 0.785,
+                from: Point::new(-6.0, 6.0, -10.0),
+                to: Point::new(6.0, 0.0, 6.0),
+                up: Vec3::new(-0.45, 1.0, 0.0),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_transform_order() {
+        let scene = parse_yaml(
+            "
+- add: plane
+  transform:
+    - [rotate-x, 1.5707963267948966]
+    - [scale, 2, 2, 2]
+    - [translate, 0, 0, 5]
+",
+        )
+        .unwrap();
+
+        assert_abs_diff_eq!(
+            scene.objects[0].transform,
+            translate(0.0, 0.0, 5.0) * scale(2.0, 2.0, 2.0) * rotate_x(std::f64::consts::FRAC_PI_2)
+        );
+    }
+
+    #[test]
+    fn test_parse_defines() {
+        let scene = parse_yaml(
+            "
+- define: white-material
+  value:
+    color: [1, 1, 1]
+    diffuse: 0.7
+    ambient: 0.1
+- define: blue-material
+  extend: white-material
+  value:
+    color: [0.537, 0.831, 0.914]
+- define: standard-transform
+  value:
+    - [translate, 1, -1, 1]
+    - [scale, 0.5, 0.5, 0.5]
+- define: large-object
+  value:
+    - standard-transform
+    - [scale, 3.5, 3.5, 3.5]
+- add: cube
+  material: blue-material
+  transform:
+    - large-object
+    - [translate, 4, 0, 0]
+",
+        )
+        .unwrap();
+        let object = &scene.objects[0];
+
+        assert_eq!(object.material.color, Color::new(0.537, 0.831, 0.914));
+        assert_eq!(object.material.diffuse, 0.7);
+        assert_abs_diff_eq!(
+            object.transform,
+            translate(4.0, 0.0, 0.0)
+                * scale(3.5, 3.5, 3.5)
+                * scale(0.5, 0.5, 0.5)
+                * translate(1.0, -1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_parse_define_referring_to_itself() {
+        let error = parse_yaml(
+            "
+- define: a
+  value: [a]
+- add: sphere
+  transform: [a]
+",
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", error).contains("Unknown definition `a`"));
+
+        // Redefining a name in terms of its earlier value extends it.
+        let scene = parse_yaml(
+            "
+- define: a
+  value:
+    - [scale, 2, 2, 2]
+- define: a
+  value:
+    - a
+    - [translate, 1, 0, 0]
+- add: sphere
+  transform: [a]
+",
+        )
+        .unwrap();
+        assert_abs_diff_eq!(
+            scene.objects[0].transform,
+            translate(1.0, 0.0, 0.0) * scale(2.0, 2.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn test_parse_principled() {
+        let scene = parse_yaml(
+            "
+- define: brushed
+  value:
+    roughness: 0.35
+    metallic: 1
+- add: sphere
+  material:
+    color: [1, 0, 0]
+    emission: [2, 2, 1]
+    principled:
+      base-color: [0.95, 0.64, 0.54]
+      metallic: 1
+      clearcoat-roughness: 0.1
+- add: cube
+  material:
+    principled: brushed
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            scene.objects[0].material.emission,
+            Color::new(2.0, 2.0, 1.0)
+        );
+        let copper = scene.objects[0].material.principled.unwrap();
+        assert_eq!(copper.base_color, Color::new(0.95, 0.64, 0.54));
+        assert_eq!(copper.metallic, 1.0);
+        assert_eq!(copper.clearcoat_roughness, 0.1);
+        assert_eq!(copper.roughness, Principled::default().roughness);
+        assert_eq!(
+            scene.objects[1].material.principled.unwrap().roughness,
+            0.35
+        );
+        assert_eq!(scene.objects[1].material.principled.unwrap().metallic, 1.0);
+    }
+
+    #[test]
+    fn test_parse_area_lights() {
+        let scene = parse_yaml(
+            "
+- add: light
+  at: [0, 4, 0]
+  intensity: [1, 1, 1]
+- add: light
+  type: rect
+  at: [0, 4, 0]
+  u: [2, 0, 0]
+  v: [0, 0, 1]
+  samples: 16
+  intensity: [5, 5, 5]
+- add: light
+  type: sphere
+  at: [1, 2, 3]
+  radius: 0.5
+  intensity: [2, 2, 2]
+",
+        )
+        .unwrap();
+
+        assert_eq!(scene.lights[0].kind, LightKind::Point);
+        assert_eq!(scene.lights[0].samples, 1);
+        assert_eq!(
+            scene.lights[1].kind,
+            LightKind::Rect {
+                u: Vec3::new(2.0, 0.0, 0.0),
+                v: Vec3::new(0.0, 0.0, 1.0)
+            }
+        );
+        assert_eq!(scene.lights[1].samples, 16);
+        assert_eq!(scene.lights[2].kind, LightKind::Sphere { radius: 0.5 });
+    }
+
+    #[test]
+    fn test_parse_light_falloff() {
+        let scene = parse_yaml(
+            "
+- add: light
+  at: [0, 5, 0]
+  intensity: [1, 0.9, 0.8]
+  power: 60
+- add: light
+  at: [0, 5, 0]
+  intensity: [1, 1, 1]
+  attenuation: [1, 0, 0.1]
+",
+        )
+        .unwrap();
+
+        assert_eq!(scene.lights[0].power, Some(60.0));
+        assert_eq!(scene.lights[0].attenuation, None);
+        assert_eq!(
+            scene.lights[1].attenuation,
+            Some(Attenuation {
+                constant: 1.0,
+                linear: 0.0,
+                quadratic: 0.1
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_spot_and_directional_lights() {
+        let scene = parse_yaml(
+            "
+- add: light
+  type: spot
+  at: [0, 5, 0]
+  direction: [0, -1, 0]
+  inner-angle: 0.3
+  outer-angle: 0.5
+  intensity: [10, 10, 10]
+- add: light
+  type: directional
+  direction: [1, -1, 0]
+  intensity: [1, 0.9, 0.8]
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            scene.lights[0].kind,
+            LightKind::Spot {
+                direction: Vec3::new(0.0, -1.0, 0.0),
+                inner_angle: 0.3,
+                outer_angle: 0.5
+            }
+        );
+        assert_eq!(
+            scene.lights[1].kind,
+            LightKind::Directional {
+                direction: Vec3::new(1.0, -1.0, 0.0)
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_environment() {
+        let scene = parse_yaml(
+            "
+- add: environment
+  file: sky/noon.exr
+  intensity: 2
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            scene.environment,
+            Some(Environment {
+                file: "sky/noon.exr".to_string(),
+                intensity: 2.0
+            })
+        );
+        assert_eq!(
+            parse_yaml("- add: environment\n  file: sky.hdr")
+                .unwrap()
+                .environment
+                .unwrap()
+                .intensity,
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_parse_group() {
+        let scene = parse_yaml(
+            "
+- add: group
+  transform:
+    - [translate, 0, 1, 0]
+  children:
+    - add: cylinder
+      min: 0
+      max: 1
+      closed: true
+    - add: sphere
+      shadow: false
+",
+        )
+        .unwrap();
+
+        let Shape::Group { children } = &scene.objects[0].shape else {
+            panic!("Expected a group");
+        };
+        assert_eq!(
+            children[0].shape,
+            Shape::Cylinder {
+                minimum: 0.0,
+                maximum: 1.0,
+                closed: true
+            }
+        );
+        assert!(!children[1].shadow);
+        assert_eq!(scene.object_count(), 3);
+    }
+
+    #[test]
+    fn test_parse_defined_group_added_twice() {
+        let scene = parse_yaml(
+            "
+- define: pillar
+  value:
+    add: group
+    transform:
+      - [scale, 1, 3, 1]
+    children:
+      - add: cylinder
+        min: 0
+        max: 1
+      - add: sphere
+        transform:
+          - [translate, 0, 1, 0]
+- define: red-pillar
+  value:
+    add: pillar
+    material:
+      color: [1, 0, 0]
+- add: red-pillar
+  transform:
+    - [translate, -2, 0, 0]
+- add: pillar
+  material:
+    reflective: 0.5
+  transform:
+    - [translate, 2, 0, 0]
+",
+        )
+        .unwrap();
+
+        assert_eq!(scene.objects.len(), 2);
+        assert_eq!(scene.object_count(), 6);
+        for (object, x) in scene.objects.iter().zip([-2.0, 2.0]) {
+            let Shape::Group { children } = &object.shape else {
+                panic!("Expected a group");
+            };
+            assert_eq!(children.len(), 2);
+            assert_eq!(children[1].transform, translate(0.0, 1.0, 0.0));
+            assert_abs_diff_eq!(
+                object.transform,
+                translate(x, 0.0, 0.0) * scale(1.0, 3.0, 1.0)
+            );
+        }
+        assert_eq!(scene.objects[0].material.color, Color::new(1.0, 0.0, 0.0));
+        // The material replaces the definition's instead of extending it.
+        assert_eq!(scene.objects[1].material.color, Color::new(1.0, 1.0, 1.0));
+        assert_eq!(scene.objects[1].material.reflective, 0.5);
+    }
+
+    #[test]
+    fn test_parse_defined_shape_errors() {
+        let cases = [
+            (
+                "- define: a
+  value: {add: group, children: [{add: a}]}",
+                "Unknown item `a`",
+            ),
+            (
+                "- define: a
+  value: {color: [1, 0, 0]}
+- add: a",
+                "`a` does not define a shape",
+            ),
+        ];
+
+        for (source, message) in cases {
+            let error = format!("{:#}", parse_yaml(source).unwrap_err());
+            assert!(
+                error.contains(message),
+                "{} does not contain {}",
+                error,
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = [
+            ("add: sphere", "A scene must be a list"),
+            ("- add: torus", "Unknown item `torus`"),
+            (
+                "- add: light\n  type: tube\n  at: [0, 0, 0]\n  intensity: [1, 1, 1]",
+                "Unknown light type `tube`",
+            ),
+            (
+                "- add: sphere\n  transform: [[skew, 1]]",
+                "Unknown transformation `skew`",
+            ),
+            (
+                "- add: sphere\n  transform: [[translate, 1, 2]]",
+                "`translate` takes 3 arguments, got 2",
+            ),
+            (
+                "- add: sphere\n  material: missing",
+                "Unknown definition `missing`",
+            ),
+            ("- add: light\n  at: [1, 2, 3]", "Missing `intensity`"),
+            (
+                "- add: sphere\n  material:\n    principled:\n      gloss: 1",
+                "Unknown principled property `gloss`",
+            ),
+        ];
+
+        for (source, message) in cases {
+            let error = format!("{:#}", parse_yaml(source).unwrap_err());
+            assert!(
+                error.contains(message),
+                "{} does not contain {}",
+                error,
+                message
+            );
+        }
+    }
+}